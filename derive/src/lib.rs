@@ -0,0 +1,160 @@
+//! Derive macros for `safe-lock`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Index, ItemStruct, Lifetime, parse_macro_input};
+
+/// Generates field projections on the write guards of a `SafeRwLock` from `parking_lot`
+/// protecting the struct.
+///
+/// For a struct `Config`, this generates a `ConfigSafeProject` trait implemented for
+/// `SafeRwLockGuard<'_, Config>`, with a `map_<field>` method for each field returning a
+/// `SafeMappedRwLockGuard` over the field. Fields of tuple structs are named after their index.
+#[proc_macro_derive(SafeProject)]
+pub fn derive_safe_project(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Wraps the fields marked with `#[safe]` into `SafeLock`s, and generates an accessor for each.
+///
+/// A field `name: L` marked with `#[safe]` is rewritten into `name: SafeLock<L>`, and a method
+/// `fn name(&self) -> SafeGuard<&L, _>` acquiring the lock with `lock_blocking` is generated, with
+/// the visibility of the field. The lock `L` must have infallible blocking acquisitions.
+#[proc_macro_attribute]
+pub fn safe_fields(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    if !args.is_empty() {
+        return Error::new_spanned(args, "`safe_fields` takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+    let input = parse_macro_input!(input as ItemStruct);
+    expand_safe_fields(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_safe_fields(mut input: ItemStruct) -> syn::Result<proc_macro2::TokenStream> {
+    let Fields::Named(fields) = &mut input.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`safe_fields` can only be applied to structs with named fields",
+        ));
+    };
+
+    let mut accessors = Vec::new();
+    for field in &mut fields.named {
+        let len = field.attrs.len();
+        field.attrs.retain(|attr| !attr.path().is_ident("safe"));
+        if field.attrs.len() == len {
+            continue;
+        }
+
+        let vis = &field.vis;
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let doc = format!("Acquires the lock of the `{ident}` field.");
+        accessors.push(quote! {
+            #[doc = #doc]
+            #vis fn #ident(&self) -> ::safe_lock::SafeGuard<&#ty, <#ty as ::safe_lock::LockBlocking>::Guard<'_>> {
+                self.#ident.by_ref().lock_blocking()
+            }
+        });
+        field.ty = syn::parse_quote!(::safe_lock::SafeLock<#ty>);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #input
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#accessors)*
+        }
+    })
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`SafeProject` can only be derived for structs",
+        ));
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let trait_name = format_ident!("{}SafeProject", name);
+    let lifetime = Lifetime::new("'__safe_lock", Span::call_site());
+
+    let mut generics = input.generics.clone();
+    generics.params.insert(0, syn::parse_quote!(#lifetime));
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#name #ty_generics: #lifetime));
+    let (impl_generics, trait_generics, where_clause) = generics.split_for_impl();
+
+    let projections = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                (format_ident!("map_{}", ident), quote!(#ident), &field.ty)
+            })
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let index = Index::from(i);
+                (format_ident!("map_{}", i), quote!(#index), &field.ty)
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let guard = quote!(::safe_lock::parking_lot::SafeRwLockGuard<#lifetime, #name #ty_generics>);
+    let mapped = |ty| quote!(::safe_lock::parking_lot::SafeMappedRwLockGuard<#lifetime, #name #ty_generics, #ty>);
+
+    let declarations = projections.iter().map(|(method, _, ty)| {
+        let mapped = mapped(ty);
+        let doc = format!(
+            "Maps the guard to the `{}` field of the `{}`.",
+            method.to_string().trim_start_matches("map_"),
+            name
+        );
+        quote! {
+            #[doc = #doc]
+            fn #method(self) -> #mapped;
+        }
+    });
+    let definitions = projections.iter().map(|(method, member, ty)| {
+        let mapped = mapped(ty);
+        quote! {
+            fn #method(self) -> #mapped {
+                self.map(|value| &mut value.#member)
+            }
+        }
+    });
+    let doc = format!("Field projections on the write guards of a `SafeRwLock<{name}>`.");
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis trait #trait_name #impl_generics #where_clause {
+            #(#declarations)*
+        }
+
+        impl #impl_generics #trait_name #trait_generics for #guard #where_clause {
+            #(#definitions)*
+        }
+    })
+}
@@ -0,0 +1,108 @@
+use ::core::ops::Deref;
+use ::std::sync::Arc;
+
+use ::arc_swap::ArcSwap;
+
+use crate::SafeLock;
+
+/// A wrapper around [`ArcSwap`] from `arc-swap`, providing safe read-copy-update behavior.
+pub type SafeArcSwap<'a, T> = SafeLock<&'a ArcSwap<T>>;
+
+/// A guard over an [`Arc`] snapshot of the value of an [`ArcSwap`], as returned by
+/// [`SafeArcSwap::read`].
+///
+/// Inspecting the snapshot never blocks anyone. [`upgrade`](Self::upgrade) publishes a new value
+/// only if the snapshot is still the current value, so the checks made on the snapshot are still
+/// valid when the new value replaces it.
+#[derive(Debug)]
+pub struct SafeArcSwapGuard<'a, T> {
+    lock: SafeArcSwap<'a, T>,
+    snapshot: Arc<T>,
+}
+
+impl<'a, T> SafeArcSwap<'a, T> {
+    /// Takes a snapshot of the current value, without blocking readers nor writers.
+    ///
+    /// The returned guard allows read-only access to the snapshot. Publishing a new value is not
+    /// possible until explicitly upgrading the guard.
+    pub fn read(self) -> SafeArcSwapGuard<'a, T> {
+        let snapshot = self.0.load_full();
+        SafeArcSwapGuard {
+            lock: self,
+            snapshot,
+        }
+    }
+}
+
+impl<'a, T> SafeArcSwapGuard<'a, T> {
+    /// Publishes `value` in place of the snapshot, provided it is still the current value.
+    ///
+    /// Returns the snapshot that was replaced. If another value was published in between, nothing is
+    /// published and this method returns `Err` with the original [`SafeLock`], so that the value
+    /// can be read and checked again, along with the rejected value, so that it can be reused.
+    pub fn upgrade(self, value: impl Into<Arc<T>>) -> Result<Arc<T>, (SafeArcSwap<'a, T>, Arc<T>)> {
+        let value = value.into();
+        let previous = self
+            .lock
+            .0
+            .compare_and_swap(&self.snapshot, Arc::clone(&value));
+        if Arc::ptr_eq(&previous, &self.snapshot) {
+            Ok(self.snapshot)
+        } else {
+            Err((self.lock, value))
+        }
+    }
+
+    /// Returns the snapshot, which stays valid after the guard is gone.
+    pub fn snapshot(&self) -> &Arc<T> {
+        &self.snapshot
+    }
+
+    /// Discards the snapshot and returns the original [`SafeLock`], allowing further attempts.
+    pub fn unlock(self) -> SafeArcSwap<'a, T> {
+        self.lock
+    }
+}
+
+impl<T> Deref for SafeArcSwapGuard<'_, T> {
+    type Target = T;
+
+    /// Provides read-only access to the snapshot.
+    ///
+    /// Publishing a new value is only possible by calling [`upgrade`](Self::upgrade).
+    fn deref(&self) -> &Self::Target {
+        &self.snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_publishes_over_the_current_snapshot() {
+        let swap = ArcSwap::from_pointee(1);
+        let guard = SafeLock::new(&swap).read();
+        assert_eq!(*guard, 1);
+        let previous = guard.upgrade(2).unwrap();
+        assert_eq!(*previous, 1);
+        assert_eq!(**swap.load(), 2);
+    }
+
+    #[test]
+    fn upgrade_hands_back_the_rejected_value() {
+        let swap = ArcSwap::from_pointee(1);
+        let stale = SafeLock::new(&swap).read();
+        SafeLock::new(&swap).read().upgrade(2).unwrap();
+
+        let value = Arc::new(3);
+        let (safe, rejected) = stale.upgrade(Arc::clone(&value)).unwrap_err();
+        assert!(Arc::ptr_eq(&rejected, &value));
+        assert_eq!(**swap.load(), 2);
+
+        let guard = safe.read();
+        assert_eq!(*guard, 2);
+        guard.upgrade(rejected).unwrap();
+        assert_eq!(**swap.load(), 3);
+    }
+}
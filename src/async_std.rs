@@ -0,0 +1,143 @@
+use ::core::convert::Infallible;
+use ::std::sync::Arc;
+
+use ::async_std::sync::{Mutex, MutexGuard, MutexGuardArc, RwLock, RwLockWriteGuard};
+
+use crate::{LockAsync, LockImmediate, SafeGuard, SafeLock, future::LockFuture};
+
+/// A wrapper around [`RwLock`](RwLock) from `async-std`, providing safe locking behavior.
+pub type SafeRwLock<'a, T> = SafeLock<&'a RwLock<T>>;
+pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a RwLock<T>, RwLockWriteGuard<'a, T>>;
+
+/// A wrapper around [`Mutex`](Mutex) from `async-std`, providing safe locking behavior.
+pub type SafeMutex<'a, T> = SafeLock<&'a Mutex<T>>;
+pub type SafeMutexGuard<'a, T> = SafeGuard<&'a Mutex<T>, MutexGuard<'a, T>>;
+
+/// A wrapper around an [`Arc`]-shared [`Mutex`] from `async-std`, whose guards are `'static`.
+pub type SafeArcMutex<T> = SafeLock<Arc<Mutex<T>>>;
+pub type SafeArcMutexGuard<T> = SafeGuard<Arc<Mutex<T>>, MutexGuardArc<T>>;
+
+impl<T: ?Sized> LockAsync for RwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    async fn lock_async(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.write().await)
+    }
+}
+
+impl<T: ?Sized> LockImmediate for RwLock<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_write().ok_or(())
+    }
+}
+
+impl<T: ?Sized> LockAsync for Mutex<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    async fn lock_async(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.lock().await)
+    }
+}
+
+impl<T: ?Sized> LockImmediate for Mutex<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock().ok_or(())
+    }
+}
+
+impl<T: ?Sized> SafeArcMutex<T> {
+    /// Asynchronously acquires the mutex and returns a `'static` guard for the locked data.
+    ///
+    /// The returned guard keeps its own reference to the [`Arc`], so it does not borrow anything
+    /// and can be held across task boundaries. Mutation is not possible until explicitly upgrading the guard.
+    /// The acquisition can be cancelled while recovering the handle, see [`LockFuture`].
+    pub fn lock_async(
+        self,
+    ) -> LockFuture<Arc<Mutex<T>>, impl Future<Output = MutexGuardArc<T>> + use<T>> {
+        let lock = Arc::clone(&self.0);
+        LockFuture::new(self, async move { lock.lock_arc().await })
+    }
+
+    /// Attempts to acquire the mutex without waiting and returns a `'static` guard if successful.
+    ///
+    /// If the mutex is already held, this method will return `Err(self)`.
+    pub fn try_lock_immediate(self) -> Result<SafeArcMutexGuard<T>, Self> {
+        match self.0.try_lock_arc() {
+            Some(guard) => Ok(SafeGuard::new(self, guard)),
+            None => Err(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::async_std::task;
+
+    use super::*;
+
+    #[test]
+    fn async_guards_upgrade() {
+        let lock = Mutex::new(0);
+        task::block_on(async {
+            let guard = SafeMutex::new(&lock).lock_async().await;
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+        });
+        assert_eq!(*lock.try_lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn immediate_acquisitions_fail_while_held() {
+        let lock = RwLock::new(0);
+        let held = lock.try_write().unwrap();
+        let safe = SafeRwLock::new(&lock).try_lock_immediate().unwrap_err();
+        drop(held);
+        assert_eq!(*safe.try_lock_immediate().unwrap(), 0);
+    }
+
+    #[test]
+    fn arc_guards_outlive_the_handle() {
+        let lock = Arc::new(Mutex::new(0));
+        let guard = task::block_on(SafeArcMutex::new(Arc::clone(&lock)).lock_async());
+        let handle = task::spawn(async move {
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+        });
+        task::block_on(handle);
+
+        let guard = SafeArcMutex::new(lock).try_lock_immediate().unwrap();
+        assert_eq!(*guard, 1);
+    }
+}
@@ -0,0 +1,240 @@
+#[cfg(feature = "std")]
+use ::core::time::Duration;
+use ::core::{convert::Infallible, hint::spin_loop};
+
+use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+
+/// A strategy deciding how long to wait between two attempts of a retry loop.
+///
+/// This is implemented by [`Spin`], [`Yield`], [`ExponentialSleep`], and by closures returning
+/// whether to keep retrying.
+pub trait Backoff {
+    /// Waits before the next attempt, returning `false` if the strategy gives up instead.
+    fn backoff(&mut self) -> bool;
+}
+
+impl<F: FnMut() -> bool> Backoff for F {
+    fn backoff(&mut self) -> bool {
+        self()
+    }
+}
+
+/// A [`Backoff`] busy-waiting with [`spin_loop`] between attempts.
+#[derive(Debug, Clone)]
+pub struct Spin {
+    remaining: usize,
+}
+
+impl Spin {
+    /// Creates a strategy that gives up after `max_retries` retries.
+    pub const fn new(max_retries: usize) -> Self {
+        Self {
+            remaining: max_retries,
+        }
+    }
+}
+
+impl Backoff for Spin {
+    fn backoff(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        spin_loop();
+        true
+    }
+}
+
+/// A [`Backoff`] yielding the current thread to the scheduler between attempts.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Yield {
+    remaining: usize,
+}
+
+#[cfg(feature = "std")]
+impl Yield {
+    /// Creates a strategy that gives up after `max_retries` retries.
+    pub const fn new(max_retries: usize) -> Self {
+        Self {
+            remaining: max_retries,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Backoff for Yield {
+    fn backoff(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        ::std::thread::yield_now();
+        true
+    }
+}
+
+/// A [`Backoff`] sleeping between attempts, doubling the delay every time up to a maximum.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct ExponentialSleep {
+    delay: Duration,
+    max_delay: Duration,
+    remaining: usize,
+}
+
+#[cfg(feature = "std")]
+impl ExponentialSleep {
+    /// Creates a strategy sleeping `min_delay` first, at most `max_delay`, and giving up after
+    /// `max_retries` retries.
+    pub const fn new(min_delay: Duration, max_delay: Duration, max_retries: usize) -> Self {
+        Self {
+            delay: min_delay,
+            max_delay,
+            remaining: max_retries,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Backoff for ExponentialSleep {
+    fn backoff(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        ::std::thread::sleep(self.delay);
+        self.delay = (self.delay * 2).min(self.max_delay);
+        true
+    }
+}
+
+impl<'a, L: ?Sized> SafeLock<&'a L> {
+    /// Attempts to acquire the lock in write mode without blocking, retrying according to `backoff`.
+    ///
+    /// If the lock is still held once the strategy gives up, this method will return `Err(self)`.
+    pub fn try_lock_with_backoff<B>(
+        self,
+        mut backoff: B,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockImmediate,
+        B: Backoff,
+    {
+        let mut safe = self;
+        loop {
+            match safe.try_lock_immediate() {
+                Ok(guard) => return Ok(guard),
+                Err(unlocked) => safe = unlocked,
+            }
+            if !backoff.backoff() {
+                return Err(safe);
+            }
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking, spinning at most `max_spins` times.
+    ///
+    /// Between two attempts, the current thread busy-waits with [`spin_loop`]. This suits very short
+    /// critical sections, where blocking would cost more than waiting. If the lock is still held
+    /// after the last spin, this method will return `Err(self)`.
+    pub fn try_lock_immediate_spin(
+        self,
+        max_spins: usize,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockImmediate,
+    {
+        self.try_lock_with_backoff(Spin::new(max_spins))
+    }
+
+    /// Acquires the lock in write mode, retrying without blocking according to `backoff` first.
+    ///
+    /// Once the strategy gives up, this method falls back to blocking until the lock is acquired.
+    pub fn lock_with_backoff<B>(
+        self,
+        backoff: B,
+    ) -> SafeGuard<&'a L, <L as LockBlocking>::Guard<'a>>
+    where
+        L: LockBlocking<Error<'a> = Infallible>
+            + LockImmediate<Guard<'a> = <L as LockBlocking>::Guard<'a>>,
+        B: Backoff,
+    {
+        match self.try_lock_with_backoff(backoff) {
+            Ok(guard) => guard,
+            Err(safe) => safe.lock_blocking(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+    use ::std::thread;
+
+    use super::*;
+
+    #[test]
+    fn strategies_give_up_after_their_retries() {
+        let mut spin = Spin::new(2);
+        assert!(spin.backoff());
+        assert!(spin.backoff());
+        assert!(!spin.backoff());
+
+        let mut sleep = ExponentialSleep::new(Duration::from_nanos(1), Duration::from_nanos(3), 3);
+        assert!((0..3).all(|_| sleep.backoff()));
+        assert_eq!(sleep.delay, Duration::from_nanos(3));
+        assert!(!sleep.backoff());
+        assert!(!Yield::new(0).backoff());
+    }
+
+    #[test]
+    fn retries_stop_when_the_strategy_gives_up() {
+        let lock = Mutex::new(0);
+        let held = lock.lock();
+        let mut attempts = 0;
+        let safe = SafeLock::new(&lock)
+            .try_lock_with_backoff(|| {
+                attempts += 1;
+                attempts < 3
+            })
+            .unwrap_err();
+        assert_eq!(attempts, 3);
+        drop(held);
+        assert_eq!(*safe.try_lock_immediate_spin(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn lock_with_backoff_falls_back_to_blocking() {
+        let lock = Mutex::new(0);
+        let held = lock.lock();
+        thread::scope(|s| {
+            let waiter = s.spawn(|| {
+                let guard = SafeLock::new(&lock).lock_with_backoff(Spin::new(1));
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+            thread::sleep(Duration::from_millis(10));
+            drop(held);
+            waiter.join().unwrap();
+        });
+        assert_eq!(*lock.lock(), 1);
+    }
+
+    #[test]
+    fn spinning_acquires_locks_released_in_the_meantime() {
+        let lock = Mutex::new(0);
+        let held = lock.lock();
+        let safe = SafeLock::new(&lock).try_lock_immediate_spin(8).unwrap_err();
+        thread::scope(|s| {
+            s.spawn(move || {
+                let guard = safe.try_lock_immediate_spin(usize::MAX).unwrap();
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+            thread::sleep(Duration::from_millis(10));
+            drop(held);
+        });
+        assert_eq!(*lock.lock(), 1);
+    }
+}
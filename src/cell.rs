@@ -0,0 +1,104 @@
+use ::core::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+
+use crate::{
+    LockBlocking, LockError, LockErrorKind, LockImmediate, LockShared, SafeGuard, SafeLock,
+    SafeReadGuard,
+};
+
+/// A wrapper around a [`RefCell`], providing the same locking behavior single-threaded.
+///
+/// A [`RefCell`] cannot block, so conflicting borrows are reported as errors by the blocking and
+/// immediate acquisitions alike, rather than panicking.
+pub type SafeRefCell<'a, T> = SafeLock<&'a RefCell<T>>;
+pub type SafeRefCellGuard<'a, T> = SafeGuard<&'a RefCell<T>, RefMut<'a, T>>;
+pub type SafeRefCellReadGuard<'a, T> = SafeReadGuard<&'a RefCell<T>, Ref<'a, T>>;
+
+impl From<BorrowError> for LockError {
+    fn from(_: BorrowError) -> Self {
+        LockErrorKind::WouldBlock.into()
+    }
+}
+
+impl From<BorrowMutError> for LockError {
+    fn from(_: BorrowMutError) -> Self {
+        LockErrorKind::WouldBlock.into()
+    }
+}
+
+impl<T: ?Sized> LockBlocking for RefCell<T> {
+    type Error<'a>
+        = BorrowMutError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_borrow_mut()
+    }
+}
+
+impl<T: ?Sized> LockImmediate for RefCell<T> {
+    type Error<'a>
+        = BorrowMutError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_borrow_mut()
+    }
+}
+
+impl<T: ?Sized> LockShared for RefCell<T> {
+    type Error<'a>
+        = BorrowError
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = BorrowError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_borrow()
+    }
+
+    #[track_caller]
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.try_borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicting_borrows_are_errors() {
+        let cell = RefCell::new(0);
+        let reader = SafeRefCell::new(&cell).try_read_blocking().unwrap();
+        let (safe, err) = SafeRefCell::new(&cell).try_lock_blocking_err().unwrap_err();
+        assert_eq!(LockError::from(err).kind(), LockErrorKind::WouldBlock);
+        assert!(safe.try_lock_immediate().is_err());
+        drop(reader);
+
+        let guard = SafeRefCell::new(&cell).try_lock_blocking().unwrap();
+        assert!(SafeRefCell::new(&cell).try_read_blocking().is_err());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*cell.borrow(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+use ::core::{marker::PhantomData, ops::Deref};
+
+use crate::{SafeGuard, SafeLock};
+
+/// Marker for a [`CheckedSafeGuard`] whose data has not been validated yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Unchecked;
+
+/// Marker for a [`CheckedSafeGuard`] whose data has been validated by [`check`](CheckedSafeGuard::check).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Checked;
+
+/// A [`SafeGuard`] tracking at the type level whether its data has been validated.
+///
+/// The guard starts in the [`Unchecked`] state, where it can only be inspected or unlocked.
+/// [`upgrade`](CheckedSafeGuard::upgrade) only exists in the [`Checked`] state, which can only be
+/// reached through [`check`](CheckedSafeGuard::check).
+#[derive(Debug)]
+pub struct CheckedSafeGuard<L, G, S = Unchecked> {
+    inner: SafeGuard<L, G>,
+    state: PhantomData<S>,
+}
+
+impl<L, G> SafeGuard<L, G> {
+    /// Converts the guard into an [`Unchecked`] [`CheckedSafeGuard`], which can only be upgraded
+    /// after a successful [`check`](CheckedSafeGuard::check).
+    pub fn into_checked(self) -> CheckedSafeGuard<L, G> {
+        CheckedSafeGuard {
+            inner: self,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<L, G> CheckedSafeGuard<L, G, Unchecked> {
+    /// Validates the data with `pred`, returning a [`Checked`] guard if it holds.
+    ///
+    /// If the predicate does not hold, the unchecked guard is returned as `Err(self)`.
+    pub fn check<F, T>(self, pred: F) -> Result<CheckedSafeGuard<L, G, Checked>, Self>
+    where
+        G: Deref<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&T) -> bool,
+    {
+        if pred(&*self.inner) {
+            Ok(CheckedSafeGuard {
+                inner: self.inner,
+                state: PhantomData,
+            })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<L, G> CheckedSafeGuard<L, G, Checked> {
+    /// Upgrades the checked guard to the underlying guard, allowing mutation of the locked data.
+    pub fn upgrade(self) -> G {
+        self.inner.upgrade()
+    }
+}
+
+impl<L, G, S> CheckedSafeGuard<L, G, S> {
+    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts.
+    pub fn unlock(self) -> SafeLock<L> {
+        self.inner.unlock()
+    }
+}
+
+impl<L, G, S, T> Deref for CheckedSafeGuard<L, G, S>
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+{
+    type Target = T;
+
+    /// Provides read-only access to the underlying value.
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// An [`Unchecked`] guard has no `upgrade`:
+///
+/// ```compile_fail,E0599
+/// use std::sync::RwLock;
+/// use safe_lock::std::SafeRwLock;
+///
+/// let lock = RwLock::new(0);
+/// let guard = SafeRwLock::new(&lock).lock_blocking_ignore_poison().into_checked();
+/// *guard.upgrade() = 1;
+/// ```
+///
+/// Neither has the guard handed back by a failed [`check`](CheckedSafeGuard::check):
+///
+/// ```compile_fail,E0599
+/// use std::sync::RwLock;
+/// use safe_lock::std::SafeRwLock;
+///
+/// let lock = RwLock::new(0);
+/// let guard = SafeRwLock::new(&lock).lock_blocking_ignore_poison().into_checked();
+/// if let Err(guard) = guard.check(|value| *value > 0) {
+///     *guard.upgrade() = 1;
+/// }
+/// ```
+///
+/// And the [`Checked`] state cannot be forged:
+///
+/// ```compile_fail,E0451
+/// use std::{marker::PhantomData, sync::RwLock};
+/// use safe_lock::{checked::{Checked, CheckedSafeGuard}, std::SafeRwLock};
+///
+/// let lock = RwLock::new(0);
+/// let inner = SafeRwLock::new(&lock).lock_blocking_ignore_poison();
+/// let guard: CheckedSafeGuard<_, _, Checked> = CheckedSafeGuard { inner, state: PhantomData };
+/// *guard.upgrade() = 1;
+/// ```
+#[cfg(doctest)]
+pub struct UpgradeRequiresCheck;
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+
+    use super::*;
+
+    #[test]
+    fn checked_guards_upgrade() {
+        let lock = RwLock::new(1);
+        let guard = SafeLock::new(&lock).lock_blocking().into_checked();
+        let Ok(guard) = guard.check(|value| *value == 1) else {
+            panic!("the predicate holds");
+        };
+        *guard.upgrade() = 2;
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn failed_checks_hand_the_unchecked_guard_back() {
+        let lock = RwLock::new(1);
+        let guard = SafeLock::new(&lock).lock_blocking().into_checked();
+        let Err(guard) = guard.check(|value| *value == 0) else {
+            panic!("the predicate does not hold");
+        };
+        assert_eq!(*guard, 1);
+        let safe = guard.unlock();
+        assert!(lock.try_write().is_some());
+        assert_eq!(*safe.lock_blocking(), 1);
+    }
+}
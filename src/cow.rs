@@ -0,0 +1,128 @@
+use ::core::ops::{Deref, DerefMut};
+
+use crate::{SafeGuard, SafeLock};
+
+/// A [`SafeGuard`] upgrading itself lazily, the first time the data is accessed mutably.
+///
+/// The guard dereferences to the locked data and stays read-only until it is dereferenced
+/// mutably, at which point it upgrades transparently. This suits code paths that only mutate
+/// conditionally. These paths must still inspect the data first: with the `debug-checks` feature,
+/// the upgrade panics otherwise.
+#[derive(Debug)]
+pub struct CowGuard<L, G> {
+    state: CowState<L, G>,
+}
+
+#[derive(Debug)]
+enum CowState<L, G> {
+    Inspecting(SafeGuard<L, G>),
+    Upgraded(G),
+    /// Transient state while moving the guard out of `Inspecting`.
+    Upgrading,
+}
+
+impl<L, G> SafeGuard<L, G> {
+    /// Converts the guard into a [`CowGuard`], which upgrades on the first mutable access.
+    pub fn to_cow(self) -> CowGuard<L, G> {
+        CowGuard {
+            state: CowState::Inspecting(self),
+        }
+    }
+}
+
+impl<L, G> CowGuard<L, G> {
+    /// Returns whether the guard has been upgraded.
+    pub fn is_upgraded(&self) -> bool {
+        matches!(self.state, CowState::Upgraded(..))
+    }
+
+    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts,
+    /// provided the guard has not been upgraded.
+    ///
+    /// Once the data has been mutated, retrying is no longer an option: this returns `Err` with the
+    /// upgraded guard instead, whose release commits the mutation.
+    pub fn unlock(self) -> Result<SafeLock<L>, G> {
+        match self.state {
+            CowState::Inspecting(guard) => Ok(guard.unlock()),
+            CowState::Upgraded(guard) => Err(guard),
+            CowState::Upgrading => unreachable!(),
+        }
+    }
+
+    #[track_caller]
+    fn upgraded(&mut self) -> &mut G {
+        if let CowState::Inspecting(guard) = &self.state {
+            guard.assert_inspected();
+            let CowState::Inspecting(SafeGuard { guard, .. }) =
+                ::core::mem::replace(&mut self.state, CowState::Upgrading)
+            else {
+                unreachable!()
+            };
+            self.state = CowState::Upgraded(guard);
+        }
+        match &mut self.state {
+            CowState::Upgraded(guard) => guard,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<L, G, T> Deref for CowGuard<L, G>
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+{
+    type Target = T;
+
+    /// Provides read-only access to the underlying value, without upgrading.
+    fn deref(&self) -> &Self::Target {
+        match &self.state {
+            CowState::Inspecting(guard) => guard,
+            CowState::Upgraded(guard) => guard,
+            CowState::Upgrading => unreachable!(),
+        }
+    }
+}
+
+impl<L, G, T> DerefMut for CowGuard<L, G>
+where
+    G: DerefMut<Target = T>,
+    T: ?Sized,
+{
+    /// Upgrades the guard if needed, and provides mutable access to the underlying value.
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.upgraded()
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+
+    use super::*;
+
+    #[test]
+    fn reading_does_not_upgrade() {
+        let lock = RwLock::new(1);
+        let cow = SafeLock::new(&lock).lock_blocking().to_cow();
+        assert_eq!(*cow, 1);
+        assert!(!cow.is_upgraded());
+        assert!(cow.unlock().is_ok());
+    }
+
+    #[test]
+    fn the_first_mutable_access_upgrades() {
+        let lock = RwLock::new(1);
+        let mut cow = SafeLock::new(&lock).lock_blocking().to_cow();
+        if *cow == 1 {
+            *cow += 1;
+        }
+        assert!(cow.is_upgraded());
+        assert_eq!(*cow, 2);
+        let guard = cow.unlock().unwrap_err();
+        assert_eq!(*guard, 2);
+        drop(guard);
+        assert_eq!(*lock.read(), 2);
+    }
+}
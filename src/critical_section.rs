@@ -0,0 +1,47 @@
+use ::core::cell::{RefCell, RefMut};
+
+use ::critical_section::Mutex;
+
+use crate::{SafeGuard, SafeLock};
+
+/// A wrapper around a [`Mutex`] from `critical-section`, providing safe locking behavior on targets
+/// where interrupts are the other threads.
+pub type SafeCsMutex<'a, T> = SafeLock<&'a Mutex<RefCell<T>>>;
+pub type SafeCsMutexGuard<'a, 'cs, T> = SafeGuard<&'a Mutex<RefCell<T>>, RefMut<'cs, T>>;
+
+impl<'a, T> SafeCsMutex<'a, T> {
+    /// Enters a critical section and calls `f` with a guard over the protected value.
+    ///
+    /// The guard cannot outlive the critical section, but can be unlocked inside `f` to hand the
+    /// lock back through its return value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is already borrowed by an enclosing call on the same mutex.
+    pub fn lock_with<R>(self, f: impl for<'cs> FnOnce(SafeCsMutexGuard<'a, 'cs, T>) -> R) -> R {
+        ::critical_section::with(|cs| {
+            let guard = self.0.borrow_ref_mut(cs);
+            f(SafeGuard::new(self, guard))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_are_confined_to_the_critical_section() {
+        let mutex = Mutex::new(RefCell::new(0));
+        let safe = SafeCsMutex::new(&mutex).lock_with(|guard| {
+            assert_eq!(*guard, 0);
+            guard.unlock()
+        });
+        safe.lock_with(|guard| {
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+        });
+        let value = ::critical_section::with(|cs| *mutex.borrow_ref(cs));
+        assert_eq!(value, 1);
+    }
+}
@@ -0,0 +1,210 @@
+use ::core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    ops::{Deref, DerefMut},
+};
+use ::std::hash::RandomState;
+
+use ::dashmap::{
+    DashMap,
+    mapref::{
+        entry::{Entry, OccupiedEntry, VacantEntry},
+        one::RefMut,
+    },
+};
+
+use crate::{SafeGuard, SafeLock};
+
+/// A wrapper around a [`DashMap`], giving its entries the check-before-mutate semantics of
+/// [`SafeGuard`]s.
+///
+/// Each entry reference holds the shard of its key in write mode, and only gives read-only access
+/// to the value until explicitly upgraded, exactly like the guard of a coarse lock.
+pub type SafeDashMap<'a, K, V, S = RandomState> = SafeLock<&'a DashMap<K, V, S>>;
+/// A guard over the value of a key of a [`SafeDashMap`], as returned by
+/// [`get_mut`](SafeDashMap::get_mut).
+pub type SafeDashMapGuard<'a, K, V, S = RandomState> =
+    SafeGuard<&'a DashMap<K, V, S>, RefMut<'a, K, V>>;
+/// A guard over an occupied entry of a [`SafeDashMap`], as returned by
+/// [`entry`](SafeDashMap::entry).
+pub type SafeDashOccupiedGuard<'a, K, V, S = RandomState> =
+    SafeGuard<&'a DashMap<K, V, S>, DashOccupiedEntry<'a, K, V>>;
+
+/// An entry of a [`SafeDashMap`], as returned by [`SafeDashMap::entry`].
+pub enum SafeDashEntry<'a, K, V, S = RandomState> {
+    /// The key is in the map: this is a guard over its entry.
+    Occupied(SafeDashOccupiedGuard<'a, K, V, S>),
+    /// The key is not in the map.
+    Vacant(SafeDashVacantEntry<'a, K, V, S>),
+}
+
+/// A vacant entry of a [`SafeDashMap`], holding the shard of its key in write mode.
+pub struct SafeDashVacantEntry<'a, K, V, S = RandomState> {
+    lock: SafeDashMap<'a, K, V, S>,
+    entry: VacantEntry<'a, K, V>,
+}
+
+/// An occupied entry of a [`DashMap`], dereferencing to its value.
+pub struct DashOccupiedEntry<'a, K, V>(OccupiedEntry<'a, K, V>);
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher + Clone> SafeDashMap<'a, K, V, S> {
+    /// Locks the shard of `key` and returns a guard over its value.
+    ///
+    /// If `key` is not in the map, this method will return `Err(self)`. The returned guard allows
+    /// read-only access to the value until explicitly upgraded.
+    pub fn get_mut<Q>(self, key: &Q) -> Result<SafeDashMapGuard<'a, K, V, S>, Self>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.0.get_mut(key) {
+            Some(guard) => Ok(SafeGuard::new(self, guard)),
+            None => Err(self),
+        }
+    }
+
+    /// Locks the shard of `key` and returns its entry.
+    ///
+    /// The value of an occupied entry must be inspected before upgrading or removing it, like any
+    /// other guard.
+    pub fn entry(self, key: K) -> SafeDashEntry<'a, K, V, S> {
+        match self.0.entry(key) {
+            Entry::Occupied(entry) => {
+                SafeDashEntry::Occupied(SafeGuard::new(self, DashOccupiedEntry(entry)))
+            }
+            Entry::Vacant(entry) => {
+                SafeDashEntry::Vacant(SafeDashVacantEntry { lock: self, entry })
+            }
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S> SafeDashOccupiedGuard<'a, K, V, S> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.guard.0.key()
+    }
+
+    /// Removes the entry from the map, returning its key and value along with the original
+    /// [`SafeLock`].
+    ///
+    /// Removing is a mutation: with the `debug-checks` feature, this panics if the value was never
+    /// inspected through the guard.
+    #[track_caller]
+    pub fn remove(self) -> (SafeDashMap<'a, K, V, S>, (K, V)) {
+        self.assert_inspected();
+        let SafeGuard { lock, guard, .. } = self;
+        (lock, guard.0.remove_entry())
+    }
+
+    /// Converts the guard into a [`SafeDashMapGuard`], keeping its inspection state.
+    pub fn into_ref(self) -> SafeDashMapGuard<'a, K, V, S> {
+        self.map_guard(|entry| entry.0.into_ref())
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S> SafeDashVacantEntry<'a, K, V, S> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.entry.key()
+    }
+
+    /// Inserts `value` for the key of the entry, and returns a guard over it.
+    pub fn insert(self, value: V) -> SafeDashMapGuard<'a, K, V, S> {
+        let guard = self.entry.insert(value);
+        SafeGuard::new(self.lock, guard)
+    }
+
+    /// Releases the shard without inserting anything, and returns the original [`SafeLock`].
+    pub fn unlock(self) -> SafeDashMap<'a, K, V, S> {
+        self.lock
+    }
+}
+
+impl<K: Eq + Hash, V> Deref for DashOccupiedEntry<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.get()
+    }
+}
+
+impl<K: Eq + Hash, V> DerefMut for DashOccupiedEntry<'_, K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.get_mut()
+    }
+}
+
+// The map is left out of the `Debug` impls, since formatting it locks every shard, including the
+// one held by the entry.
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S> fmt::Debug for SafeDashEntry<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied(guard) => f.debug_tuple("Occupied").field(&guard.guard).finish(),
+            Self::Vacant(entry) => f.debug_tuple("Vacant").field(entry).finish(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V, S> fmt::Debug for SafeDashVacantEntry<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SafeDashVacantEntry")
+            .field("key", self.entry.key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug> fmt::Debug for DashOccupiedEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DashOccupiedEntry")
+            .field("key", self.0.key())
+            .field("value", self.0.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_upgrade_the_value_of_a_key() {
+        let map = DashMap::new();
+        map.insert(1, 10);
+        let safe = SafeDashMap::new(&map).get_mut(&2).unwrap_err();
+        let guard = safe.get_mut(&1).unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*map.get(&1).unwrap(), 11);
+    }
+
+    #[test]
+    fn entries_insert_and_remove() {
+        let map = DashMap::new();
+        let SafeDashEntry::Vacant(entry) = SafeDashMap::new(&map).entry(1) else {
+            panic!("the map is empty");
+        };
+        assert_eq!(*entry.key(), 1);
+        let safe = entry.insert(10).unlock();
+
+        let SafeDashEntry::Occupied(guard) = safe.entry(1) else {
+            panic!("the key was inserted");
+        };
+        assert_eq!((*guard.key(), *guard), (1, 10));
+        let (_, removed) = guard.remove();
+        assert_eq!(removed, (1, 10));
+        assert!(map.is_empty());
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn removing_requires_an_inspection() {
+        let map = DashMap::new();
+        map.insert(1, 10);
+        if let SafeDashEntry::Occupied(guard) = SafeDashMap::new(&map).entry(1) {
+            guard.remove();
+        }
+    }
+}
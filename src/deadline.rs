@@ -0,0 +1,284 @@
+use ::core::{
+    error::Error,
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+use ::std::{
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
+
+use crate::{LockBlocking, LockError, LockErrorKind, LockImmediate, LockTimed, SafeLock};
+
+/// A [`SafeLock`] over a [`DeadlineLock`], supporting immediate and timed acquisitions.
+pub type DeadlineSafeLock<L> = SafeLock<DeadlineLock<L>>;
+
+/// A lock decorator adding immediate and timed acquisitions to any blocking lock `L`.
+///
+/// Ownership of the lock is tracked by a gate next to it, made of a flag and a condition variable,
+/// which supports waiting with a deadline. A thread only calls the blocking acquisition of `L` once
+/// it has passed the gate, at which point the wrapped lock is free. This lets timeouts work
+/// uniformly, even for backends lacking native timed acquisition.
+///
+/// The gate only knows about the acquisitions made through the decorator: acquiring the wrapped lock
+/// directly, through [`inner`](Self::inner), can still make the acquisitions of the decorator block.
+#[derive(Debug, Default)]
+pub struct DeadlineLock<L> {
+    lock: L,
+    gate: Gate,
+}
+
+#[derive(Debug, Default)]
+struct Gate {
+    held: Mutex<bool>,
+    released: Condvar,
+}
+
+/// A guard returned by a [`DeadlineLock`], opening its gate when dropped.
+#[derive(Debug)]
+pub struct DeadlineGuard<'a, G> {
+    guard: ManuallyDrop<G>,
+    gate: &'a Gate,
+}
+
+/// An error returned when acquiring a [`DeadlineLock`] without blocking or with a deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeadlineError<E> {
+    /// The wrapped lock could not be acquired.
+    Lock(E),
+    /// The lock is held, and the acquisition was not allowed to wait for it.
+    WouldBlock,
+    /// The lock was still held when the deadline expired.
+    TimedOut,
+}
+
+impl<E: fmt::Display> fmt::Display for DeadlineError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lock(err) => err.fmt(f),
+            Self::WouldBlock => f.write_str("the lock is held by someone else"),
+            Self::TimedOut => f.write_str("timed out waiting for the lock"),
+        }
+    }
+}
+
+impl<E: Error> Error for DeadlineError<E> {}
+
+impl<E: Into<LockError>> From<DeadlineError<E>> for LockError {
+    fn from(err: DeadlineError<E>) -> Self {
+        match err {
+            DeadlineError::Lock(err) => err.into(),
+            DeadlineError::WouldBlock => LockErrorKind::WouldBlock.into(),
+            DeadlineError::TimedOut => LockErrorKind::TimedOut.into(),
+        }
+    }
+}
+
+impl Gate {
+    const fn new() -> Self {
+        Self {
+            held: Mutex::new(false),
+            released: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, bool> {
+        // The flag is always consistent, so poisoning can be ignored.
+        self.held.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn enter(&self) {
+        let state = self.state();
+        let mut held = self
+            .released
+            .wait_while(state, |held| *held)
+            .unwrap_or_else(PoisonError::into_inner);
+        *held = true;
+    }
+
+    fn try_enter(&self) -> bool {
+        let mut held = self.state();
+        !::core::mem::replace(&mut *held, true)
+    }
+
+    fn enter_until(&self, deadline: Instant) -> bool {
+        let mut held = self.state();
+        while *held {
+            let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+                return false;
+            };
+            held = self
+                .released
+                .wait_timeout(held, timeout)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        *held = true;
+        true
+    }
+
+    fn leave(&self) {
+        *self.state() = false;
+        self.released.notify_one();
+    }
+}
+
+impl<L> DeadlineLock<L> {
+    /// Wraps `lock` to support immediate and timed acquisitions.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            gate: Gate::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<L: LockBlocking> DeadlineLock<L> {
+    /// Acquires the wrapped lock once the gate has been passed, opening it again on failure.
+    fn acquire(&self) -> Result<DeadlineGuard<'_, L::Guard<'_>>, L::Error<'_>> {
+        match self.lock.lock_blocking() {
+            Ok(guard) => Ok(DeadlineGuard {
+                guard: ManuallyDrop::new(guard),
+                gate: &self.gate,
+            }),
+            Err(err) => {
+                self.gate.leave();
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<G> Drop for DeadlineGuard<'_, G> {
+    fn drop(&mut self) {
+        // The wrapped lock is released before opening the gate, so the next owner does not block on it.
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.gate.leave();
+    }
+}
+
+impl<G: Deref> Deref for DeadlineGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for DeadlineGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<L: LockBlocking> LockBlocking for DeadlineLock<L> {
+    type Error<'a>
+        = L::Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = DeadlineGuard<'a, L::Guard<'a>>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.gate.enter();
+        self.acquire()
+    }
+}
+
+impl<L: LockBlocking> LockImmediate for DeadlineLock<L> {
+    type Error<'a>
+        = DeadlineError<L::Error<'a>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = DeadlineGuard<'a, L::Guard<'a>>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        if !self.gate.try_enter() {
+            return Err(DeadlineError::WouldBlock);
+        }
+        self.acquire().map_err(DeadlineError::Lock)
+    }
+}
+
+impl<L: LockBlocking> LockTimed for DeadlineLock<L> {
+    type Error<'a>
+        = DeadlineError<L::Error<'a>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = DeadlineGuard<'a, L::Guard<'a>>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.lock_until(deadline),
+            None => self.lock_blocking().map_err(DeadlineError::Lock),
+        }
+    }
+
+    #[track_caller]
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        if !self.gate.enter_until(deadline) {
+            return Err(DeadlineError::TimedOut);
+        }
+        self.acquire().map_err(DeadlineError::Lock)
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::std::thread;
+
+    use super::*;
+
+    #[test]
+    fn acquisitions_time_out_while_held() {
+        let lock = DeadlineLock::new(::parking_lot::Mutex::new(0));
+        let held = lock.lock_blocking().unwrap();
+        assert_eq!(
+            lock.lock_immediate().unwrap_err(),
+            DeadlineError::WouldBlock
+        );
+        let (safe, err) = SafeLock::new(&lock)
+            .try_lock_blocking_for_err(Duration::from_millis(10))
+            .unwrap_err();
+        assert_eq!(err, DeadlineError::TimedOut);
+        assert_eq!(LockError::from(err).kind(), LockErrorKind::TimedOut);
+        drop(held);
+
+        let guard = safe.try_lock_immediate().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.inner().lock(), 1);
+    }
+
+    #[test]
+    fn released_guards_hand_the_lock_to_waiters() {
+        let lock = DeadlineLock::new(::parking_lot::Mutex::new(0));
+        let held = lock.lock_blocking().unwrap();
+        thread::scope(|s| {
+            let waiter = s.spawn(|| lock.lock_for(Duration::MAX).map(|guard| *guard));
+            thread::sleep(Duration::from_millis(10));
+            drop(held);
+            assert_eq!(waiter.join().unwrap(), Ok(0));
+        });
+        assert!(lock.lock_immediate().is_ok());
+    }
+}
@@ -0,0 +1,261 @@
+use ::core::{
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use ::std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    LockImmediate, LockShared, SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Deferred`] lock, queuing mutations while the lock is contended.
+pub type DeferredSafeLock<L, T> = SafeLock<Deferred<L, T>>;
+
+/// A mutation queued by a [`Deferred`] lock.
+type Update<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+/// A lock decorator queuing the mutations of the data `T` of the wrapped lock `L` that cannot be
+/// applied immediately.
+///
+/// Mutations submitted with [`update_or_defer`](Self::update_or_defer) while the lock is held are
+/// queued, and applied in order by whichever guard first accesses the data mutably afterwards,
+/// which is after it was upgraded. Hot contended updates are thus batched by the next writer
+/// instead of each waiting for the lock.
+///
+/// Queued mutations are not visible while a guard is being inspected, so they must not invalidate
+/// the checks of other writers, as is the case for commutative updates such as counters.
+pub struct Deferred<L, T: ?Sized> {
+    lock: L,
+    queue: Queue<T>,
+}
+
+struct Queue<T: ?Sized> {
+    pending: AtomicUsize,
+    updates: Mutex<Vec<Update<T>>>,
+}
+
+/// A guard returned by a [`Deferred`] lock, applying the queued mutations on mutable accesses.
+pub struct DeferredGuard<'a, G, T: ?Sized> {
+    guard: G,
+    queue: &'a Queue<T>,
+}
+
+impl<T: ?Sized> Queue<T> {
+    const fn new() -> Self {
+        Self {
+            pending: AtomicUsize::new(0),
+            updates: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn updates(&self) -> MutexGuard<'_, Vec<Update<T>>> {
+        self.updates.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, update: Update<T>) {
+        let mut updates = self.updates();
+        updates.push(update);
+        self.pending.store(updates.len(), Ordering::Release);
+    }
+
+    fn apply(&self, data: &mut T) {
+        if self.pending.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let updates = {
+            let mut updates = self.updates();
+            self.pending.store(0, Ordering::Relaxed);
+            mem::take(&mut *updates)
+        };
+        for update in updates {
+            update(data);
+        }
+    }
+}
+
+impl<L, T: ?Sized> Deferred<L, T> {
+    /// Wraps `lock` to queue the mutations that cannot be applied immediately.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            queue: Queue::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns the number of queued mutations.
+    pub fn pending(&self) -> usize {
+        self.queue.pending.load(Ordering::Relaxed)
+    }
+
+    /// Applies `update` to the data if the lock can be acquired immediately, or queues it otherwise.
+    ///
+    /// Returns whether the lock was acquired, in which case `update` and every queued mutation were
+    /// applied. Otherwise, `update` is applied by the next guard accessing the data mutably.
+    pub fn update_or_defer<'s, F>(&'s self, update: F) -> bool
+    where
+        L: LockImmediate,
+        L::Guard<'s>: DerefMut<Target = T>,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        if let Ok(mut guard) = self.lock_immediate() {
+            update(&mut guard);
+            return true;
+        }
+        self.queue.push(Box::new(update));
+        // The holder may have released the lock before the mutation was queued, in which case
+        // nobody would apply it until the next write.
+        match self.lock_immediate() {
+            Ok(mut guard) => {
+                self.queue.apply(&mut guard.guard);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl<L, T: ?Sized> SafeLock<Deferred<L, T>> {
+    /// Applies `update` to the data if the lock can be acquired immediately, or queues it otherwise.
+    ///
+    /// See [`Deferred::update_or_defer`].
+    pub fn update_or_defer<'s, F>(&'s self, update: F) -> bool
+    where
+        L: LockImmediate,
+        L::Guard<'s>: DerefMut<Target = T>,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        self.0.update_or_defer(update)
+    }
+}
+
+impl<L: Default, T: ?Sized> Default for Deferred<L, T> {
+    fn default() -> Self {
+        Self::new(L::default())
+    }
+}
+
+impl<L: fmt::Debug, T: ?Sized> fmt::Debug for Deferred<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deferred")
+            .field("lock", &self.lock)
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
+
+impl<G: fmt::Debug, T: ?Sized> fmt::Debug for DeferredGuard<'_, G, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferredGuard")
+            .field("guard", &self.guard)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G: Deref<Target = T>, T: ?Sized> Deref for DeferredGuard<'_, G, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut<Target = T>, T: ?Sized> DerefMut for DeferredGuard<'_, G, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.queue.apply(&mut self.guard);
+        &mut self.guard
+    }
+}
+
+impl<L, T: ?Sized> Layer for Deferred<L, T> {
+    type Inner = L;
+    type Guard<'a, G>
+        = DeferredGuard<'a, G, T>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        _exclusive: bool,
+    ) -> Result<DeferredGuard<'_, G, T>, E> {
+        res.map(|guard| DeferredGuard {
+            guard,
+            queue: &self.queue,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L, T: ?Sized] Deferred<L, T> => LockBlocking, LockImmediate, LockTimed;
+    type Guard = DeferredGuard<'a, _, T>;
+}
+
+impl<L: LockShared, T: ?Sized> LockShared for Deferred<L, T> {
+    type Error<'a>
+        = L::Error<'a>
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = L::ImmediateError<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = L::Guard<'a>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.lock.read_blocking()
+    }
+
+    #[track_caller]
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.lock.read_immediate()
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn uncontended_updates_apply_immediately() {
+        let lock = SafeLock::new(Deferred::new(Mutex::new(0)));
+        assert!(lock.update_or_defer(|value| *value += 1));
+        assert_eq!(lock.0.pending(), 0);
+        assert_eq!(*lock.0.inner().lock(), 1);
+    }
+
+    #[test]
+    fn contended_updates_apply_on_the_next_write() {
+        let lock = SafeLock::new(Deferred::new(Mutex::new(0)));
+        let held = lock.0.inner().lock();
+        assert!(!lock.update_or_defer(|value| *value += 1));
+        assert!(!lock.update_or_defer(|value| *value *= 10));
+        assert_eq!(lock.0.pending(), 2);
+        drop(held);
+
+        let guard = lock.by_ref().lock_blocking();
+        assert_eq!(*guard, 0);
+        let mut data = guard.upgrade();
+        *data += 1;
+        drop(data);
+        assert_eq!(lock.0.pending(), 0);
+        assert_eq!(*lock.0.inner().lock(), 11);
+    }
+}
@@ -0,0 +1,9 @@
+#[cfg(feature = "deadlock-detection")]
+mod deadlock;
+#[cfg(feature = "diagnostics")]
+mod named;
+
+#[cfg(feature = "deadlock-detection")]
+pub use self::deadlock::{DeadlockedThread, detect_deadlocks};
+#[cfg(feature = "diagnostics")]
+pub use self::named::{HeldLock, Nameable, Named, NamedError, NamedGuard, NamedSafeLock, dump};
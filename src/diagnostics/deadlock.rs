@@ -0,0 +1,100 @@
+use ::std::thread::ThreadId;
+
+/// A thread taking part in a deadlock reported by [`detect_deadlocks`].
+#[derive(Debug, Clone)]
+pub struct DeadlockedThread {
+    thread_id: ThreadId,
+    backtrace: String,
+}
+
+impl DeadlockedThread {
+    /// Returns the id of the deadlocked thread.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Returns the backtrace of the lock acquisition the thread is blocked on.
+    pub fn backtrace(&self) -> &str {
+        &self.backtrace
+    }
+}
+
+/// Checks for deadlocks among the threads blocked on `parking_lot` locks.
+///
+/// Each returned cycle lists the deadlocked threads along with the backtrace of the acquisition
+/// they are blocked on. This covers every [`SafeLock`](crate::SafeLock) wrapping a `parking_lot`
+/// lock, which makes it possible to tell a stuck retry loop apart from an actual deadlock.
+///
+/// This function is relatively expensive and is meant to be called periodically from a
+/// dedicated background thread.
+pub fn detect_deadlocks() -> Vec<Vec<DeadlockedThread>> {
+    parking_lot::deadlock::check_deadlock()
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .iter()
+                .map(|thread| DeadlockedThread {
+                    thread_id: thread.thread_id(),
+                    backtrace: format!("{:?}", thread.backtrace()),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn deadlocks_between_safe_locks_are_reported() {
+        use ::core::time::Duration;
+        use ::std::{env, process::Command, sync::Barrier, thread};
+
+        // `parking_lot` parks the threads of a detected deadlock forever, so the deadlock is set up
+        // by a child process running this test alone, and the threads end along with it.
+        const CHILD: &str = "SAFE_LOCK_DEADLOCK_CHILD";
+        if env::var_os(CHILD).is_none() {
+            let path = concat!(
+                module_path!(),
+                "::deadlocks_between_safe_locks_are_reported"
+            );
+            let (_, test) = path.split_once("::").unwrap();
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", test, "--test-threads=1"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stdout)
+            );
+            assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+            return;
+        }
+
+        static FIRST: Mutex<()> = Mutex::new(());
+        static SECOND: Mutex<()> = Mutex::new(());
+        static BARRIER: Barrier = Barrier::new(2);
+
+        for (outer, inner) in [(&FIRST, &SECOND), (&SECOND, &FIRST)] {
+            thread::spawn(move || {
+                let _outer = crate::SafeLock::new(outer).lock_blocking();
+                BARRIER.wait();
+                let _inner = crate::SafeLock::new(inner).lock_blocking();
+            });
+        }
+
+        let cycle = loop {
+            if let Some(cycle) = detect_deadlocks().pop() {
+                break cycle;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(cycle.len(), 2);
+        assert!(cycle.iter().all(|thread| !thread.backtrace().is_empty()));
+    }
+}
@@ -0,0 +1,439 @@
+use ::core::{
+    cell::{BorrowError, BorrowMutError},
+    convert::Infallible,
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+    panic::Location,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use ::std::{
+    collections::BTreeMap,
+    io,
+    sync::{Mutex, PoisonError, TryLockError},
+    thread::{self, ThreadId},
+    time::Instant,
+};
+
+use crate::{
+    LockBlocking, LockError, LockShared, SafeLock, TimedLockError,
+    deadline::DeadlineError,
+    layer::{Layer, forward_lock_traits},
+    lease::LeaseError,
+    poison::PoisoningError,
+};
+
+static HELD_LOCKS: Mutex<BTreeMap<u64, HeldLock>> = Mutex::new(BTreeMap::new());
+static NEXT_HOLDER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A [`SafeLock`] over a [`Named`] lock, reported by [`dump`] while held.
+pub type NamedSafeLock<L> = SafeLock<Named<L>>;
+
+/// A currently held lock, as reported by [`dump`].
+#[derive(Debug, Clone)]
+pub struct HeldLock {
+    name: &'static str,
+    thread_id: ThreadId,
+    acquired_at: Instant,
+    exclusive: bool,
+}
+
+impl HeldLock {
+    /// Returns the name the lock was created with.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the id of the thread holding the lock.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Returns for how long the lock has been held.
+    pub fn held_for(&self) -> Duration {
+        self.acquired_at.elapsed()
+    }
+
+    /// Returns whether the lock is held in exclusive (write) mode rather than shared mode.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+}
+
+/// Lists every [`Named`] lock currently held, along with its holder thread and hold duration.
+///
+/// Shared locks held by several threads appear once per holder. This is meant to be called when
+/// investigating a hang, for instance from a signal handler thread or a debug endpoint.
+pub fn dump() -> Vec<HeldLock> {
+    HELD_LOCKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// A lock decorator giving a name to the wrapped lock `L`, and registering its holders so they
+/// are reported by [`dump`].
+///
+/// Failed acquisitions report the name of the lock along with the location they were requested at,
+/// through a [`NamedError`]. The blocking acquisitions of a lock that cannot fail stay infallible,
+/// see [`Nameable`].
+#[derive(Debug)]
+pub struct Named<L> {
+    lock: L,
+    name: &'static str,
+}
+
+/// A guard returned by a [`Named`] lock, unregistering its holder when dropped.
+#[derive(Debug)]
+pub struct NamedGuard<G> {
+    guard: G,
+    id: u64,
+}
+
+/// An error returned when a [`Named`] lock could not be acquired.
+///
+/// The error of the wrapped lock is annotated with the name of the lock and the location the
+/// acquisition was requested at, e.g. `lock 'sessions': the lock is held by someone else, requested
+/// at src/api.rs:120:9`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedError<E> {
+    name: &'static str,
+    location: &'static Location<'static>,
+    error: E,
+}
+
+impl<E> NamedError<E> {
+    /// Returns the name of the lock.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the location the acquisition was requested at.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns the error of the wrapped lock.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for NamedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lock '{}': {}, requested at {}",
+            self.name, self.error, self.location
+        )
+    }
+}
+
+impl<E: Error> Error for NamedError<E> {}
+
+impl<E: Into<LockError>> From<NamedError<E>> for LockError {
+    fn from(err: NamedError<E>) -> Self {
+        err.error.into().with_context(err.name, err.location)
+    }
+}
+
+/// An error of a blocking acquisition, which a [`Named`] lock annotates with its name and the
+/// location the acquisition was requested at.
+///
+/// Errors are wrapped in a [`NamedError`], except [`Infallible`], which is kept as is so that the
+/// blocking acquisitions of a lock that cannot fail remain usable through the decorator. Since the
+/// error must be [`Nameable`] for every borrow of the lock, a [`Named`] lock owning a `std` lock
+/// requires its data to be `'static`.
+pub trait Nameable {
+    /// The annotated error.
+    type Named;
+
+    /// Annotates the error with the name of the lock and the location of the acquisition.
+    fn named(self, name: &'static str, location: &'static Location<'static>) -> Self::Named;
+}
+
+impl Nameable for Infallible {
+    type Named = Self;
+
+    fn named(self, _: &'static str, _: &'static Location<'static>) -> Self::Named {
+        self
+    }
+}
+
+/// Implements [`Nameable`] by wrapping the error in a [`NamedError`].
+macro_rules! nameable {
+    ($($(#[$attr:meta])* [$($generics:ident),*] $error:ty,)*) => {$(
+        $(#[$attr])*
+        impl<$($generics),*> Nameable for $error {
+            type Named = NamedError<Self>;
+
+            fn named(
+                self,
+                name: &'static str,
+                location: &'static Location<'static>,
+            ) -> Self::Named {
+                NamedError {
+                    name,
+                    location,
+                    error: self,
+                }
+            }
+        }
+    )*};
+}
+
+nameable! {
+    [] (),
+    [] LockError,
+    [] BorrowError,
+    [] BorrowMutError,
+    [] io::Error,
+    [G] PoisonError<G>,
+    [G] TryLockError<G>,
+    [E] TimedLockError<E>,
+    [E] NamedError<E>,
+    [E] DeadlineError<E>,
+    [E] LeaseError<E>,
+    [E] PoisoningError<E>,
+    #[cfg(feature = "test-util")]
+    [] crate::mock::MockError,
+    #[cfg(feature = "tokio")]
+    [] ::tokio::sync::TryLockError,
+    #[cfg(all(
+        feature = "interprocess",
+        any(target_os = "linux", target_os = "android", target_os = "freebsd")
+    ))]
+    [G] crate::interprocess::InterprocessError<G>,
+}
+
+impl<L> SafeLock<L> {
+    /// Creates a new [`SafeLock`] wrapping the provided lock under the given name.
+    ///
+    /// While held, the lock is reported by [`dump`] along with its holder.
+    pub const fn new_named(lock: L, name: &'static str) -> NamedSafeLock<L> {
+        SafeLock(Named::new(lock, name))
+    }
+}
+
+impl<L> Named<L> {
+    /// Wraps `lock` under the given name.
+    pub const fn new(lock: L, name: &'static str) -> Self {
+        Self { lock, name }
+    }
+
+    /// Returns the name of the lock.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    #[track_caller]
+    fn track_blocking<G, E: Nameable>(
+        &self,
+        res: Result<G, E>,
+        exclusive: bool,
+    ) -> Result<NamedGuard<G>, E::Named> {
+        match res {
+            Ok(guard) => Ok(self.track(guard, exclusive)),
+            Err(error) => Err(error.named(self.name, Location::caller())),
+        }
+    }
+
+    fn track<G>(&self, guard: G, exclusive: bool) -> NamedGuard<G> {
+        let id = NEXT_HOLDER_ID.fetch_add(1, Ordering::Relaxed);
+        let held = HeldLock {
+            name: self.name,
+            thread_id: thread::current().id(),
+            acquired_at: Instant::now(),
+            exclusive,
+        };
+        HELD_LOCKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, held);
+        NamedGuard { guard, id }
+    }
+}
+
+impl<G> Drop for NamedGuard<G> {
+    fn drop(&mut self) {
+        HELD_LOCKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+impl<G: Deref> Deref for NamedGuard<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for NamedGuard<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<L: LockBlocking> LockBlocking for Named<L>
+where
+    for<'a> L::Error<'a>: Nameable,
+{
+    type Error<'a>
+        = <L::Error<'a> as Nameable>::Named
+    where
+        Self: 'a;
+    type Guard<'a>
+        = NamedGuard<L::Guard<'a>>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.track_blocking(self.lock.lock_blocking(), true)
+    }
+}
+
+impl<L> Layer for Named<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = NamedGuard<G>
+    where
+        Self: 'a;
+    type Error<E> = NamedError<E>;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        exclusive: bool,
+    ) -> Result<NamedGuard<G>, NamedError<E>> {
+        match res {
+            Ok(guard) => Ok(self.track(guard, exclusive)),
+            Err(error) => Err(NamedError {
+                name: self.name,
+                location: Location::caller(),
+                error,
+            }),
+        }
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Named<L> => LockImmediate, LockTimed;
+    type Guard = NamedGuard<_>;
+    type Error = NamedError<_>;
+}
+
+impl<L: LockShared> LockShared for Named<L>
+where
+    for<'a> L::Error<'a>: Nameable,
+{
+    type Error<'a>
+        = <L::Error<'a> as Nameable>::Named
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = NamedError<L::ImmediateError<'a>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = NamedGuard<L::Guard<'a>>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.track_blocking(self.lock.read_blocking(), false)
+    }
+
+    #[track_caller]
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.wrap(self.lock.read_immediate(), false)
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+    use crate::LockErrorKind;
+
+    #[test]
+    fn lock_errors_keep_the_name_and_the_location() {
+        let lock = Named::new(Mutex::new(0), "counter");
+        let _guard = lock.inner().lock();
+        let (_, err) = SafeLock::new(&lock).try_lock_immediate_err().unwrap_err();
+        let location = err.location();
+
+        let err = LockError::from(err);
+        assert_eq!(err.kind(), LockErrorKind::WouldBlock);
+        assert_eq!(err.name(), Some("counter"));
+        assert_eq!(err.location(), Some(location));
+        assert!(err.to_string().starts_with("lock 'counter': "));
+    }
+
+    #[test]
+    fn blocking_errors_keep_the_name_and_the_location() {
+        let lock = Named::new(::std::sync::Mutex::new(0), "poisoned");
+        let _ = ::std::thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = lock.inner().lock();
+                panic!("poisoning the mutex");
+            })
+            .join()
+        });
+        let (_, err) = SafeLock::new(&lock).try_lock_blocking_err().unwrap_err();
+        let location = err.location();
+
+        let err = LockError::from(err);
+        assert_eq!(err.kind(), LockErrorKind::Poisoned);
+        assert_eq!(err.name(), Some("poisoned"));
+        assert_eq!(err.location(), Some(location));
+    }
+
+    #[test]
+    fn dump_lists_the_current_holders() {
+        fn held(name: &str) -> Vec<HeldLock> {
+            dump()
+                .into_iter()
+                .filter(|held| held.name() == name)
+                .collect()
+        }
+
+        let safe = SafeLock::new_named(::parking_lot::RwLock::new(0), "dump_holders");
+        let first = safe.read_blocking_ref();
+        let second = safe.read_blocking_ref();
+        let holders = held("dump_holders");
+        assert_eq!(holders.len(), 2);
+        assert!(holders.iter().all(|held| !held.is_exclusive()));
+        assert!(
+            holders
+                .iter()
+                .all(|held| held.thread_id() == ::std::thread::current().id())
+        );
+        drop((first, second));
+
+        let guard = safe.lock_blocking_ref();
+        let [holder] = &held("dump_holders")[..] else {
+            panic!("the lock has a single holder");
+        };
+        assert!(holder.is_exclusive());
+        drop(guard);
+        assert!(held("dump_holders").is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use ::core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{LockBlocking, LockError, LockImmediate, SafeLock};
+
+/// A [`SafeLock`] over a type-erased lock guarding data of type `T`.
+pub type DynSafeLock<'a, T> = SafeLock<&'a dyn DynLock<T>>;
+
+/// A type-erased guard returned by the dyn-compatible lock traits.
+pub struct DynGuard<'a, T: ?Sized>(Box<dyn DerefMut<Target = T> + 'a>);
+
+/// A dyn-compatible version of [`LockBlocking`], erasing the guard and error types.
+///
+/// This is implemented for every lock whose guards dereference to `T` and whose errors convert
+/// into a [`LockError`], for every lifetime of the borrow of the lock. In practice, this leaves out
+/// the locks over borrowed data, such as a `Mutex<&'a str>`.
+pub trait DynLockBlocking<T: ?Sized> {
+    /// Acquires the lock in a blocking manner, boxing the guard.
+    fn lock_blocking_dyn(&self) -> Result<DynGuard<'_, T>, LockError>;
+}
+
+/// A dyn-compatible version of [`LockImmediate`], erasing the guard and error types.
+///
+/// This is implemented for every lock whose guards dereference to `T` and whose errors convert
+/// into a [`LockError`], for every lifetime of the borrow of the lock. In practice, this leaves out
+/// the locks over borrowed data, such as a `Mutex<&'a str>`.
+pub trait DynLockImmediate<T: ?Sized> {
+    /// Attempts to acquire the lock immediately, boxing the guard.
+    fn lock_immediate_dyn(&self) -> Result<DynGuard<'_, T>, LockError>;
+}
+
+/// A dyn-compatible lock guarding data of type `T`, supporting blocking and immediate acquisitions.
+///
+/// Locks of different backends guarding the same data type can be stored together, for instance as
+/// `Vec<Box<dyn DynLock<T>>>`. `dyn DynLock<T>` implements [`LockBlocking`] and [`LockImmediate`],
+/// so a [`DynSafeLock`] retains the whole inspect-then-upgrade API.
+pub trait DynLock<T: ?Sized>: DynLockBlocking<T> + DynLockImmediate<T> {}
+
+impl<T: ?Sized, L: DynLockBlocking<T> + DynLockImmediate<T> + ?Sized> DynLock<T> for L {}
+
+impl<L, T> DynLockBlocking<T> for L
+where
+    L: LockBlocking + ?Sized,
+    T: ?Sized,
+    for<'a> L::Guard<'a>: DerefMut<Target = T>,
+    for<'a> L::Error<'a>: Into<LockError>,
+{
+    #[track_caller]
+    fn lock_blocking_dyn(&self) -> Result<DynGuard<'_, T>, LockError> {
+        match self.lock_blocking() {
+            Ok(guard) => Ok(DynGuard(Box::new(guard))),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<L, T> DynLockImmediate<T> for L
+where
+    L: LockImmediate + ?Sized,
+    T: ?Sized,
+    for<'a> L::Guard<'a>: DerefMut<Target = T>,
+    for<'a> L::Error<'a>: Into<LockError>,
+{
+    #[track_caller]
+    fn lock_immediate_dyn(&self) -> Result<DynGuard<'_, T>, LockError> {
+        match self.lock_immediate() {
+            Ok(guard) => Ok(DynGuard(Box::new(guard))),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for DynGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for DynGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for DynGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for dyn DynLock<T> + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynLock").finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> LockBlocking for dyn DynLock<T> + '_ {
+    type Error<'a>
+        = LockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = DynGuard<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.lock_blocking_dyn()
+    }
+}
+
+impl<T: ?Sized> LockImmediate for dyn DynLock<T> + '_ {
+    type Error<'a>
+        = LockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = DynGuard<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.lock_immediate_dyn()
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn locks_of_different_backends_can_be_stored_together() {
+        let locks: Vec<Box<dyn DynLock<i32>>> = vec![
+            Box::new(::parking_lot::RwLock::new(1)),
+            Box::new(::parking_lot::Mutex::new(2)),
+            Box::new(Mutex::new(3)),
+        ];
+        for lock in &locks {
+            let guard = SafeLock::new(&**lock).try_lock_blocking().unwrap();
+            let value = *guard;
+            *guard.upgrade() = value * 10;
+        }
+        let values: Vec<_> = locks
+            .iter()
+            .map(|lock| *lock.lock_immediate_dyn().unwrap())
+            .collect();
+        assert_eq!(values, [10, 20, 30]);
+    }
+}
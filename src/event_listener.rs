@@ -0,0 +1,256 @@
+use ::core::{
+    convert::Infallible,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+use ::event_listener::{Event, Listener};
+
+use crate::{
+    LockBlocking, LockImmediate, SafeGuard, SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Listening`] lock, which can be waited on until a predicate holds.
+pub type ListeningSafeLock<L> = SafeLock<Listening<L>>;
+
+/// A lock decorator notifying the threads and tasks waiting in
+/// [`lock_when_notified`](SafeLock::lock_when_notified) or
+/// [`lock_when_notified_async`](SafeLock::lock_when_notified_async) whenever a guard of the wrapped
+/// lock `L` that accessed the data mutably is released.
+///
+/// Notifications go through an [`Event`] from `event-listener`, so waiting works from synchronous
+/// code and from any async runtime alike.
+#[derive(Debug)]
+pub struct Listening<L> {
+    lock: L,
+    /// Notified by the releases of the guards that accessed the data mutably.
+    changed: Event,
+    /// Notified by every release, for the tasks waiting for the lock to be available.
+    released: Event,
+}
+
+/// A guard returned by a [`Listening`] lock.
+///
+/// When dropped, the wrapped guard is released first, then the waiters are notified.
+#[derive(Debug)]
+pub struct ListeningGuard<'a, G> {
+    guard: ManuallyDrop<G>,
+    changed: &'a Event,
+    released: &'a Event,
+    written: bool,
+}
+
+impl<L> Listening<L> {
+    /// Wraps `lock` to notify waiters of its releases.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            changed: Event::new(),
+            released: Event::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<G> Drop for ListeningGuard<'_, G> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        // Waking the waiters for a release that changed nothing would only make them spin.
+        if self.written {
+            self.changed.notify(usize::MAX);
+        }
+        self.released.notify(usize::MAX);
+    }
+}
+
+impl<G: Deref> Deref for ListeningGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for ListeningGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.written = true;
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Listening<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = ListeningGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<ListeningGuard<'_, G>, E> {
+        res.map(|guard| ListeningGuard {
+            guard: ManuallyDrop::new(guard),
+            changed: &self.changed,
+            released: &self.released,
+            written: false,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Listening<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = ListeningGuard<'a, _>;
+}
+
+impl<'a, L> SafeLock<&'a Listening<L>> {
+    /// Acquires the lock in write mode once `pred` holds for the locked data, and returns a guard for it.
+    ///
+    /// Whenever the predicate does not hold, the lock is released and the current thread parks until
+    /// a guard of the lock that accessed the data mutably is released, before checking again. The
+    /// returned guard is ready to be upgraded.
+    #[allow(clippy::type_complexity)]
+    pub fn lock_when_notified<F, T>(
+        self,
+        mut pred: F,
+    ) -> SafeGuard<&'a Listening<L>, ListeningGuard<'a, L::Guard<'a>>>
+    where
+        L: LockBlocking<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnMut(&T) -> bool,
+    {
+        let changed = &self.0.changed;
+        let mut safe = self;
+        loop {
+            // Listening before checking ensures no notification is missed in between.
+            let listener = changed.listen();
+            let guard = safe.lock_blocking();
+            if pred(&*guard) {
+                return guard;
+            }
+            safe = guard.unlock();
+            listener.wait();
+        }
+    }
+
+    /// Asynchronously acquires the lock in write mode once `pred` holds for the locked data, and
+    /// returns a guard for it.
+    ///
+    /// The lock is only ever acquired without blocking: whenever it is unavailable, the task waits
+    /// until a guard of the lock is released, and whenever the predicate does not hold, until a guard
+    /// that accessed the data mutably is released, before trying again. Any error of the immediate
+    /// acquisition counts as the lock being unavailable. This does not depend
+    /// on any async runtime, and works with synchronous locks. The returned guard is ready to be
+    /// upgraded.
+    #[allow(clippy::type_complexity)]
+    pub async fn lock_when_notified_async<F, T>(
+        self,
+        mut pred: F,
+    ) -> SafeGuard<&'a Listening<L>, ListeningGuard<'a, L::Guard<'a>>>
+    where
+        L: LockImmediate,
+        L::Guard<'a>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnMut(&T) -> bool,
+    {
+        let (changed, released) = (&self.0.changed, &self.0.released);
+        let mut safe = self;
+        loop {
+            let (on_change, on_release) = (changed.listen(), released.listen());
+            safe = match safe.try_lock_immediate() {
+                Ok(guard) if pred(&*guard) => return guard,
+                Ok(guard) => {
+                    let safe = guard.unlock();
+                    on_change.await;
+                    safe
+                }
+                Err(safe) => {
+                    on_release.await;
+                    safe
+                }
+            };
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use ::parking_lot::Mutex;
+    use ::std::thread;
+
+    use super::*;
+
+    fn increment(lock: &Listening<Mutex<i32>>) {
+        let guard = SafeLock::new(lock).lock_blocking();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    fn increment_slowly(lock: &Listening<Mutex<i32>>) {
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(1));
+            increment(lock);
+        }
+    }
+
+    #[test]
+    fn waiters_are_woken_by_releases() {
+        let lock = Listening::new(Mutex::new(0));
+        thread::scope(|s| {
+            s.spawn(|| increment_slowly(&lock));
+            let guard = SafeLock::new(&lock).lock_when_notified(|value| *value == 3);
+            *guard.upgrade() = 0;
+        });
+        assert_eq!(*lock.inner().lock(), 0);
+    }
+
+    #[test]
+    fn waiters_sleep_until_the_data_changes() {
+        let lock = Listening::new(Mutex::new(0));
+        let checks = AtomicUsize::new(0);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&lock).lock_when_notified(|value| {
+                    checks.fetch_add(1, Ordering::Relaxed);
+                    *value == 1
+                });
+                drop(guard);
+            });
+            thread::sleep(Duration::from_millis(50));
+            increment(&lock);
+        });
+        // Once before the change, and once after.
+        assert!(checks.load(Ordering::Relaxed) <= 2);
+    }
+
+    #[test]
+    fn async_waiters_are_woken_by_releases() {
+        let lock = Listening::new(Mutex::new(0));
+        thread::scope(|s| {
+            s.spawn(|| increment_slowly(&lock));
+            ::tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let guard = SafeLock::new(&lock)
+                        .lock_when_notified_async(|value| *value == 3)
+                        .await;
+                    *guard.upgrade() = 0;
+                });
+        });
+        assert_eq!(*lock.inner().lock(), 0);
+    }
+}
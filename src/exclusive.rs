@@ -0,0 +1,173 @@
+use ::core::{
+    cell::Cell,
+    convert::Infallible,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+
+/// A wrapper around an [`ExclusiveLock`], providing safe locking behavior over borrowed data.
+pub type SafeExclusiveLock<'l, 'a, T> = SafeLock<&'l ExclusiveLock<'a, T>>;
+pub type SafeExclusiveGuard<'l, 'a, T> =
+    SafeGuard<&'l ExclusiveLock<'a, T>, ExclusiveGuard<'l, 'a, T>>;
+
+/// A lock over exclusively borrowed data, so that code generic over the lock can be called without
+/// an actual lock, e.g. in tests or in single-owner setup phases.
+///
+/// Since the data is not shared, acquisitions never contend with anyone else and are infallible.
+/// The lock only tracks whether a guard is alive, with a single non-atomic flag: acquiring it again
+/// while a guard is alive is a bug, and panics.
+pub struct ExclusiveLock<'a, T: ?Sized> {
+    data: NonNull<T>,
+    held: Cell<bool>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+/// A guard returned by an [`ExclusiveLock`].
+pub struct ExclusiveGuard<'l, 'a, T: ?Sized> {
+    lock: &'l ExclusiveLock<'a, T>,
+}
+
+// SAFETY: the lock behaves as the `&mut T` it was created from.
+unsafe impl<T: ?Sized + Send> Send for ExclusiveLock<'_, T> {}
+
+impl<'a, T: ?Sized> ExclusiveLock<'a, T> {
+    /// Wraps the exclusively borrowed `data`.
+    pub fn new(data: &'a mut T) -> Self {
+        Self {
+            data: NonNull::from(data),
+            held: Cell::new(false),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a mutable reference to the data, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: no guard can be alive since `self` is borrowed mutably.
+        unsafe { self.data.as_mut() }
+    }
+
+    /// Returns the exclusive borrow the lock was created from.
+    pub fn into_inner(self) -> &'a mut T {
+        let mut data = self.data;
+        // SAFETY: the lock owned the borrow, and no guard can be alive since `self` is moved.
+        unsafe { data.as_mut() }
+    }
+
+    fn acquire(&self) -> ExclusiveGuard<'_, 'a, T> {
+        assert!(
+            !self.held.replace(true),
+            "the exclusive lock is already held by a guard"
+        );
+        ExclusiveGuard { lock: self }
+    }
+}
+
+impl<'a, T: ?Sized> From<&'a mut T> for ExclusiveLock<'a, T> {
+    fn from(data: &'a mut T) -> Self {
+        Self::new(data)
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ExclusiveLock<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExclusiveLock")
+            .field("held", &self.held.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> Drop for ExclusiveGuard<'_, '_, T> {
+    fn drop(&mut self) {
+        self.lock.held.set(false);
+    }
+}
+
+impl<T: ?Sized> Deref for ExclusiveGuard<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the guard is the only one alive.
+        unsafe { self.lock.data.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ExclusiveGuard<'_, '_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the guard is the only one alive.
+        unsafe { &mut *self.lock.data.as_ptr() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ExclusiveGuard<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T: ?Sized> LockBlocking for ExclusiveLock<'a, T> {
+    type Error<'l>
+        = Infallible
+    where
+        Self: 'l;
+    type Guard<'l>
+        = ExclusiveGuard<'l, 'a, T>
+    where
+        Self: 'l;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.acquire())
+    }
+}
+
+impl<'a, T: ?Sized> LockImmediate for ExclusiveLock<'a, T> {
+    type Error<'l>
+        = Infallible
+    where
+        Self: 'l;
+    type Guard<'l>
+        = ExclusiveGuard<'l, 'a, T>
+    where
+        Self: 'l;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.acquire())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{assert_impl, assert_not_impl};
+
+    assert_impl!(ExclusiveLock<'static, u32>: Send);
+    assert_not_impl!(ExclusiveLock<'static, u32>: Sync);
+
+    #[test]
+    fn guards_write_through_the_borrow() {
+        let mut data = 0;
+        let lock = ExclusiveLock::new(&mut data);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        let guard = SafeLock::new(&lock).lock_immediate();
+        assert_eq!(*guard, 1);
+        drop(guard);
+        *lock.into_inner() += 1;
+        assert_eq!(data, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "already held")]
+    fn reentrant_acquisitions_panic() {
+        let mut data = 0;
+        let lock = ExclusiveLock::new(&mut data);
+        let _guard = SafeLock::new(&lock).lock_blocking();
+        let _again = SafeLock::new(&lock).lock_blocking();
+    }
+}
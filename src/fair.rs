@@ -0,0 +1,210 @@
+use ::core::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use ::std::{collections::BTreeSet, sync::Mutex};
+
+use crate::{
+    LockImmediate, SafeGuard, SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Fair`] lock, granting immediate acquisitions in arrival order.
+pub type FairSafeLock<L> = SafeLock<Fair<L>>;
+
+/// A lock decorator granting the retried immediate acquisitions of the wrapped lock `L` in FIFO
+/// order.
+///
+/// Threads retrying [`try_lock_immediate_fair`](SafeLock::try_lock_immediate_fair) take a
+/// [`FairTicket`] on their first failure, and are then only allowed to acquire the lock once every
+/// ticket taken before theirs was served or dropped. Immediate acquisitions do not barge past
+/// queued threads, so none of them starves. Blocking acquisitions are passed through as is.
+pub struct Fair<L> {
+    lock: L,
+    next: AtomicU64,
+    serving: AtomicU64,
+    abandoned: Mutex<BTreeSet<u64>>,
+}
+
+/// A place in the queue of a [`Fair`] lock.
+///
+/// Dropping the ticket before it is served gives up its place.
+#[must_use = "dropping the ticket gives up its place in the queue"]
+pub struct FairTicket<'a, L> {
+    lock: &'a Fair<L>,
+    id: u64,
+}
+
+impl<L> Fair<L> {
+    /// Wraps `lock` to grant its retried immediate acquisitions in arrival order.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            next: AtomicU64::new(0),
+            serving: AtomicU64::new(0),
+            abandoned: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns the number of tickets waiting to be served.
+    pub fn queued(&self) -> u64 {
+        let serving = self.serving.load(Ordering::Acquire);
+        self.next.load(Ordering::Acquire).saturating_sub(serving)
+    }
+
+    fn take_ticket(&self) -> FairTicket<'_, L> {
+        FairTicket {
+            lock: self,
+            id: self.next.fetch_add(1, Ordering::AcqRel),
+        }
+    }
+
+    /// Serves the ticket `id`, skipping the following tickets that were dropped.
+    fn retire(&self, id: u64) {
+        let mut abandoned = self.abandoned.lock().unwrap_or_else(|err| err.into_inner());
+        if id != self.serving.load(Ordering::Acquire) {
+            abandoned.insert(id);
+            return;
+        }
+        let mut serving = id + 1;
+        while abandoned.remove(&serving) {
+            serving += 1;
+        }
+        self.serving.store(serving, Ordering::Release);
+    }
+}
+
+impl<L> FairTicket<'_, L> {
+    /// Returns whether it is the turn of this ticket.
+    pub fn is_serving(&self) -> bool {
+        self.lock.serving.load(Ordering::Acquire) == self.id
+    }
+}
+
+impl<L> Drop for FairTicket<'_, L> {
+    fn drop(&mut self) {
+        self.lock.retire(self.id);
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for Fair<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fair")
+            .field("lock", &self.lock)
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
+
+impl<L> fmt::Debug for FairTicket<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FairTicket").field("id", &self.id).finish()
+    }
+}
+
+impl<L> Layer for Fair<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = G
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<G, E> {
+        res
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Fair<L> => LockBlocking;
+}
+
+impl<'a, L: LockImmediate> SafeLock<&'a Fair<L>> {
+    /// Attempts to acquire the lock in write mode without blocking, in arrival order.
+    ///
+    /// `ticket` must be `None` on the first attempt and is then kept by the caller across retries.
+    /// The lock is only attempted if no other thread is queued, or once it is the turn of `ticket`.
+    /// On failure, `ticket` is set to the place of the caller in the queue, and `Err(self)` is
+    /// returned. On success, `ticket` is served and reset to `None`.
+    pub fn try_lock_immediate_fair(
+        self,
+        ticket: &mut Option<FairTicket<'a, L>>,
+    ) -> Result<SafeGuard<&'a Fair<L>, L::Guard<'a>>, Self> {
+        let lock = self.0;
+        let turn = match ticket {
+            Some(ticket) => ticket.is_serving(),
+            None => lock.queued() == 0,
+        };
+        if turn {
+            if let Ok(guard) = lock.lock.lock_immediate() {
+                *ticket = None;
+                return Ok(SafeGuard::new(self, guard));
+            }
+        }
+        if ticket.is_none() {
+            *ticket = Some(lock.take_ticket());
+        }
+        Err(self)
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::{Mutex, MutexGuard};
+
+    use super::*;
+
+    type Ticket<'a> = Option<FairTicket<'a, Mutex<i32>>>;
+    type Guard<'a> = SafeGuard<&'a Fair<Mutex<i32>>, MutexGuard<'a, i32>>;
+
+    fn attempt<'a>(lock: &'a Fair<Mutex<i32>>, ticket: &mut Ticket<'a>) -> Option<Guard<'a>> {
+        SafeLock::new(lock).try_lock_immediate_fair(ticket).ok()
+    }
+
+    #[test]
+    fn queued_tickets_are_served_in_order() {
+        let lock = Fair::new(Mutex::new(0));
+        let held = lock.inner().lock();
+        let (mut first, mut second) = (None, None);
+        assert!(attempt(&lock, &mut first).is_none());
+        assert!(attempt(&lock, &mut second).is_none());
+        assert_eq!(lock.queued(), 2);
+        drop(held);
+
+        assert!(attempt(&lock, &mut second).is_none());
+        let mut late = None;
+        assert!(attempt(&lock, &mut late).is_none());
+        let guard = attempt(&lock, &mut first).unwrap();
+        assert!(first.is_none());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+
+        let guard = attempt(&lock, &mut second).unwrap();
+        assert_eq!(*guard, 1);
+        drop(guard);
+        assert!(late.as_ref().is_some_and(FairTicket::is_serving));
+    }
+
+    #[test]
+    fn dropped_tickets_give_up_their_place() {
+        let lock = Fair::new(Mutex::new(0));
+        let held = lock.inner().lock();
+        let (mut first, mut second) = (None, None);
+        assert!(attempt(&lock, &mut first).is_none());
+        assert!(attempt(&lock, &mut second).is_none());
+        drop(held);
+
+        drop(first);
+        assert!(attempt(&lock, &mut second).is_some());
+        assert_eq!(lock.queued(), 0);
+    }
+}
@@ -0,0 +1,362 @@
+use ::core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+use ::fs4::fs_std::FileExt;
+use ::std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{
+        Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError,
+    },
+};
+
+use crate::{LockBlocking, LockImmediate, LockShared, SafeGuard, SafeLock, SafeReadGuard};
+
+/// A wrapper around a [`FileLock`], providing safe locking behavior across processes.
+pub type SafeFileLock<'a> = SafeLock<&'a FileLock>;
+pub type SafeFileLockGuard<'a> = SafeGuard<&'a FileLock, FileLockWriteGuard<'a>>;
+pub type SafeFileLockReadGuard<'a> = SafeReadGuard<&'a FileLock, FileLockReadGuard<'a>>;
+
+/// A lock over the contents of a file, relying on advisory file locks (`flock` on Unix,
+/// `LockFileEx` on Windows) to coordinate with other processes.
+///
+/// Acquiring the lock reads the whole file, which the guard then derefs to. Changes made through a
+/// write guard are written back when it is [flushed](SafeGuard::flush) or released.
+///
+/// Advisory locks only exclude the processes that also lock the file: plain reads and writes of
+/// the file are not prevented. Since file locks do not exclude the threads of the same process
+/// sharing a handle, acquisitions are also synchronized by an in-process [`RwLock`].
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    local: RwLock<()>,
+    // The number of guards of this process holding the file lock in shared mode, the first one
+    // acquiring it and the last one releasing it.
+    readers: Mutex<usize>,
+}
+
+/// A guard holding a [`FileLock`] exclusively, dereferencing to the contents of the file.
+pub struct FileLockWriteGuard<'a> {
+    lock: &'a FileLock,
+    contents: Vec<u8>,
+    dirty: bool,
+    _local: RwLockWriteGuard<'a, ()>,
+}
+
+/// A guard holding a [`FileLock`] in shared mode, dereferencing to the contents of the file.
+pub struct FileLockReadGuard<'a> {
+    lock: &'a FileLock,
+    contents: Vec<u8>,
+    _local: RwLockReadGuard<'a, ()>,
+}
+
+impl FileLock {
+    /// Creates a new lock over `file`, which must be opened for reading and writing.
+    pub const fn new(file: File) -> Self {
+        Self {
+            file,
+            local: RwLock::new(()),
+            readers: Mutex::new(0),
+        }
+    }
+
+    /// Opens the file at `path` for reading and writing, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+
+    /// Returns a reference to the underlying file.
+    pub const fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Consumes the lock, returning the underlying file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+
+    fn read_file(&self) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        (&self.file).seek(SeekFrom::Start(0))?;
+        (&self.file).read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    fn readers(&self) -> MutexGuard<'_, usize> {
+        self.readers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes the file lock in exclusive mode, then reads the file.
+    fn enter_exclusive(&self, lock: impl FnOnce(&File) -> io::Result<bool>) -> io::Result<Vec<u8>> {
+        contended(lock(&self.file))?;
+        self.read_file().inspect_err(|_| self.unlock())
+    }
+
+    /// Takes the file lock in shared mode if no other reader of this process holds it, then reads
+    /// the file.
+    fn enter_shared(&self, lock: impl FnOnce(&File) -> io::Result<bool>) -> io::Result<Vec<u8>> {
+        let mut readers = self.readers();
+        if *readers == 0 {
+            contended(lock(&self.file))?;
+        }
+        match self.read_file() {
+            Ok(contents) => {
+                *readers += 1;
+                Ok(contents)
+            }
+            Err(err) => {
+                if *readers == 0 {
+                    self.unlock();
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn unlock(&self) {
+        let res = FileExt::unlock(&self.file);
+        debug_assert!(res.is_ok(), "failed to unlock a file lock");
+    }
+}
+
+fn contended(res: io::Result<bool>) -> io::Result<()> {
+    match res {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(::fs4::lock_contended_error()),
+        Err(err) => Err(err),
+    }
+}
+
+fn local_contended<G>(res: Result<G, TryLockError<G>>) -> io::Result<G> {
+    match res {
+        Ok(guard) => Ok(guard),
+        Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(::fs4::lock_contended_error()),
+    }
+}
+
+impl FileLockWriteGuard<'_> {
+    /// Writes the contents back to the file if they were changed since the last flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut file = &self.lock.file;
+        file.seek(SeekFrom::Start(0))?;
+        file.set_len(0)?;
+        file.write_all(&self.contents)?;
+        file.flush()?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl<L> SafeGuard<L, FileLockWriteGuard<'_>> {
+    /// Writes the contents of the guard back to the file if they were changed since the last flush.
+    ///
+    /// Releasing the guard also writes back pending changes, but ignores the errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.guard.flush()
+    }
+}
+
+impl Drop for FileLockWriteGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+        self.lock.unlock();
+    }
+}
+
+impl Drop for FileLockReadGuard<'_> {
+    fn drop(&mut self) {
+        let mut readers = self.lock.readers();
+        *readers -= 1;
+        if *readers == 0 {
+            self.lock.unlock();
+        }
+    }
+}
+
+impl Deref for FileLockWriteGuard<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.contents
+    }
+}
+
+impl DerefMut for FileLockWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.dirty = true;
+        &mut self.contents
+    }
+}
+
+impl Deref for FileLockReadGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.contents
+    }
+}
+
+impl fmt::Debug for FileLockWriteGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLockWriteGuard")
+            .field("contents", &self.contents)
+            .field("dirty", &self.dirty)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for FileLockReadGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLockReadGuard")
+            .field("contents", &self.contents)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LockBlocking for FileLock {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = FileLockWriteGuard<'a>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        let local = self.local.write().unwrap_or_else(PoisonError::into_inner);
+        let contents = self.enter_exclusive(|file| FileExt::lock_exclusive(file).map(|()| true))?;
+        Ok(FileLockWriteGuard {
+            lock: self,
+            contents,
+            dirty: false,
+            _local: local,
+        })
+    }
+}
+
+impl LockImmediate for FileLock {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = FileLockWriteGuard<'a>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        let local = local_contended(self.local.try_write())?;
+        let contents = self.enter_exclusive(FileExt::try_lock_exclusive)?;
+        Ok(FileLockWriteGuard {
+            lock: self,
+            contents,
+            dirty: false,
+            _local: local,
+        })
+    }
+}
+
+impl LockShared for FileLock {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = FileLockReadGuard<'a>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        let local = self.local.read().unwrap_or_else(PoisonError::into_inner);
+        let contents = self.enter_shared(|file| FileExt::lock_shared(file).map(|()| true))?;
+        Ok(FileLockReadGuard {
+            lock: self,
+            contents,
+            _local: local,
+        })
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        let local = local_contended(self.local.try_read())?;
+        let contents = self.enter_shared(FileExt::try_lock_shared)?;
+        Ok(FileLockReadGuard {
+            lock: self,
+            contents,
+            _local: local,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::{env, fs, path::PathBuf, process};
+
+    use super::*;
+
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(test: &str) -> Self {
+            Self(env::temp_dir().join(format!("safe-lock-{}-{test}", process::id())))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn released_guards_write_the_contents_back() {
+        let path = TempPath::new("write-back");
+        let lock = FileLock::open(&path.0).unwrap();
+        let guard = SafeFileLock::new(&lock).try_lock_blocking().unwrap();
+        assert!(guard.is_empty());
+        guard.upgrade().extend_from_slice(b"hello");
+        assert_eq!(fs::read(&path.0).unwrap(), b"hello");
+
+        let mut guard = SafeFileLock::new(&lock).try_lock_blocking().unwrap();
+        assert_eq!(*guard, b"hello");
+        guard.flush().unwrap();
+        drop(guard);
+        let guard = SafeFileLock::new(&lock).try_read_blocking().unwrap();
+        assert_eq!(&*guard, b"hello");
+    }
+
+    #[test]
+    fn exclusive_locks_exclude_other_handles() {
+        let path = TempPath::new("exclusive");
+        let (first, second) = (
+            FileLock::open(&path.0).unwrap(),
+            FileLock::open(&path.0).unwrap(),
+        );
+        let guard = SafeFileLock::new(&first).try_lock_immediate().unwrap();
+        assert!(SafeFileLock::new(&first).try_read_immediate().is_err());
+        assert!(SafeFileLock::new(&second).try_read_immediate().is_err());
+        drop(guard);
+
+        let reader = SafeFileLock::new(&first).try_read_immediate().unwrap();
+        let other = SafeFileLock::new(&first).try_read_immediate().unwrap();
+        assert!(SafeFileLock::new(&second).try_lock_immediate().is_err());
+        drop((reader, other));
+        assert!(SafeFileLock::new(&second).try_lock_immediate().is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+use ::core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{SafeGuard, SafeLock};
+
+/// A future acquiring a [`SafeLock`] asynchronously, as returned by
+/// [`SafeLock::lock_async`](crate::SafeLock::lock_async).
+///
+/// Acquiring the lock is cancellation-safe: dropping the future before it completes never leaves
+/// the lock held. The handle consumed to create the future can also be recovered from a pending
+/// future with [`cancel`](Self::cancel), which aborts the acquisition. This makes it possible to
+/// race the acquisition against other futures, e.g. in a `select!` polling `&mut future`, and to
+/// retry later with the same handle if another branch completes first.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct LockFuture<L, F> {
+    lock: Option<SafeLock<L>>,
+    future: Option<F>,
+}
+
+impl<L, F> LockFuture<L, F> {
+    pub(crate) const fn new(lock: SafeLock<L>, future: F) -> Self {
+        Self {
+            lock: Some(lock),
+            future: Some(future),
+        }
+    }
+
+    /// Aborts the acquisition, returning the handle the future was created from.
+    ///
+    /// Returns `None` if the future already completed, in which case the handle belongs to the
+    /// returned guard.
+    pub fn cancel(self: Pin<&mut Self>) -> Option<SafeLock<L>> {
+        // SAFETY: the future is dropped in place, and the handle is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.future = None;
+        this.lock.take()
+    }
+}
+
+impl<L, F: Future> Future for LockFuture<L, F> {
+    type Output = SafeGuard<L, F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the inner future is never moved out of the pinned struct.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = this
+            .future
+            .as_mut()
+            .expect("`LockFuture` polled after completion or cancellation");
+        // SAFETY: the inner future is pinned since the struct is.
+        match unsafe { Pin::new_unchecked(future) }.poll(cx) {
+            Poll::Ready(guard) => {
+                this.future = None;
+                Poll::Ready(SafeGuard::new(this.lock.take().unwrap(), guard))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use ::core::{pin::pin, time::Duration};
+    use ::tokio::{select, sync::RwLock, time};
+
+    use crate::SafeLock;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[tokio::test]
+    async fn cancelled_acquisitions_do_not_hold_the_lock() {
+        let lock = RwLock::new(0);
+        let held = lock.write().await;
+        select! {
+            _ = SafeLock::new(&lock).lock_async() => panic!("the lock is held"),
+            () = time::sleep(TIMEOUT) => {}
+        }
+        drop(held);
+        assert!(lock.try_write().is_ok());
+    }
+
+    #[tokio::test]
+    async fn cancelled_acquisitions_hand_the_handle_back() {
+        let lock = RwLock::new(0);
+        let held = lock.write().await;
+        let mut future = pin!(SafeLock::new(&lock).lock_async());
+        select! {
+            _ = &mut future => panic!("the lock is held"),
+            () = time::sleep(TIMEOUT) => {}
+        }
+        let safe = future.as_mut().cancel().unwrap();
+        assert!(future.cancel().is_none());
+        drop(held);
+
+        let guard = safe.lock_async().await;
+        assert_eq!(*guard, 0);
+    }
+}
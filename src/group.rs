@@ -0,0 +1,158 @@
+use ::core::convert::Infallible;
+
+use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+
+/// A group of [`SafeLock`]s, stored as a tuple, that are acquired together.
+///
+/// Acquiring several locks in different orders from different threads can deadlock. A
+/// [`SafeLockGroup`] always blocks on its locks in a canonical order (by address), whatever the
+/// order of the tuple, and returns the guards in the order of the tuple. Groups of two to four
+/// locks are supported.
+#[derive(Debug)]
+pub struct SafeLockGroup<T>(T);
+
+impl<T> SafeLockGroup<T> {
+    /// Creates a new group from a tuple of [`SafeLock`]s.
+    pub const fn new(locks: T) -> Self {
+        Self(locks)
+    }
+
+    /// Consumes the group, returning the tuple of [`SafeLock`]s.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for SafeLockGroup<T> {
+    fn from(locks: T) -> Self {
+        Self(locks)
+    }
+}
+
+fn addr<L: ?Sized>(lock: &L) -> usize {
+    (lock as *const L).cast::<()>() as usize
+}
+
+macro_rules! impl_safe_lock_group {
+    ($n:literal; $($idx:tt $L:ident $lt:lifetime $g:ident),+) => {
+        impl<$($lt,)+ $($L: ?Sized),+> SafeLockGroup<($(SafeLock<&$lt $L>,)+)> {
+            /// Acquires every lock of the group in write mode, and returns their guards.
+            ///
+            /// The locks are acquired in a canonical order, so that two threads locking overlapping
+            /// groups cannot deadlock. The guards are returned in the order of the tuple.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the same lock appears twice in the group, since locking it twice would
+            /// deadlock.
+            #[allow(clippy::type_complexity)]
+            pub fn lock_all_blocking(self) -> ($(SafeGuard<&$lt $L, <$L as LockBlocking>::Guard<$lt>>,)+)
+            where
+                $($L: LockBlocking<Error<$lt> = Infallible>,)+
+            {
+                let addrs: [usize; $n] = [$(addr(self.0.$idx.0)),+];
+                let mut order: [usize; $n] = [$($idx),+];
+                order.sort_unstable_by_key(|&i| addrs[i]);
+                assert!(
+                    order.windows(2).all(|w| addrs[w[0]] != addrs[w[1]]),
+                    "the same lock appears twice in a `SafeLockGroup`"
+                );
+
+                let mut locks = ($(Some(self.0.$idx),)+);
+                $(let mut $g = None;)+
+                for i in order {
+                    match i {
+                        $($idx => $g = locks.$idx.take().map(|safe| safe.lock_blocking()),)+
+                        _ => unreachable!(),
+                    }
+                }
+                ($($g.unwrap(),)+)
+            }
+
+            /// Attempts to acquire every lock of the group in write mode without blocking.
+            ///
+            /// This is all-or-nothing: if any lock is already held, the locks acquired so far are
+            /// released and this method will return `Err(self)`.
+            #[allow(clippy::type_complexity)]
+            pub fn try_lock_all_immediate(
+                self,
+            ) -> Result<($(SafeGuard<&$lt $L, <$L as LockImmediate>::Guard<$lt>>,)+), Self>
+            where
+                $($L: LockImmediate,)+
+            {
+                let mut locks = ($(Some(self.0.$idx),)+);
+                $(let mut $g = None;)+
+                let mut acquired = true;
+                $(
+                    if acquired {
+                        match locks.$idx.take().unwrap().try_lock_immediate() {
+                            Ok(guard) => $g = Some(guard),
+                            Err(safe) => {
+                                locks.$idx = Some(safe);
+                                acquired = false;
+                            }
+                        }
+                    }
+                )+
+                if acquired {
+                    return Ok(($($g.unwrap(),)+));
+                }
+                Err(Self(($(
+                    match $g {
+                        Some(guard) => guard.unlock(),
+                        None => locks.$idx.take().unwrap(),
+                    },
+                )+)))
+            }
+        }
+    };
+}
+
+impl_safe_lock_group!(2; 0 A 'a ga, 1 B 'b gb);
+impl_safe_lock_group!(3; 0 A 'a ga, 1 B 'b gb, 2 C 'c gc);
+impl_safe_lock_group!(4; 0 A 'a ga, 1 B 'b gb, 2 C 'c gc, 3 D 'd gd);
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+    use ::std::thread;
+
+    use super::*;
+
+    fn transfer(from: &Mutex<i32>, to: &Mutex<i32>) {
+        let (from, to) =
+            SafeLockGroup::new((SafeLock::new(from), SafeLock::new(to))).lock_all_blocking();
+        let (balance, other) = (*from, *to);
+        *from.upgrade() = balance - 1;
+        *to.upgrade() = other + 1;
+    }
+
+    #[test]
+    fn opposite_orders_do_not_deadlock() {
+        let (a, b) = (Mutex::new(0), Mutex::new(0));
+        thread::scope(|s| {
+            s.spawn(|| (0..1000).for_each(|_| transfer(&a, &b)));
+            s.spawn(|| (0..1000).for_each(|_| transfer(&b, &a)));
+        });
+        assert_eq!((*a.lock(), *b.lock()), (0, 0));
+    }
+
+    #[test]
+    fn failed_immediate_acquisitions_release_every_lock() {
+        let (a, b, c) = (Mutex::new(0), Mutex::new(0), Mutex::new(0));
+        let held = c.lock();
+        let group = SafeLockGroup::new((SafeLock::new(&a), SafeLock::new(&b), SafeLock::new(&c)))
+            .try_lock_all_immediate()
+            .unwrap_err();
+        assert!(!a.is_locked() && !b.is_locked());
+        drop(held);
+        assert!(group.try_lock_all_immediate().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "appears twice")]
+    fn duplicate_locks_panic() {
+        let lock = Mutex::new(0);
+        SafeLockGroup::new((SafeLock::new(&lock), SafeLock::new(&lock))).lock_all_blocking();
+    }
+}
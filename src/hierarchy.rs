@@ -0,0 +1,207 @@
+use ::core::{
+    cell::RefCell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Hierarchy`] lock, enforcing a global lock ordering.
+pub type HierarchicalSafeLock<L> = SafeLock<Hierarchy<L>>;
+
+::std::thread_local! {
+    static HELD_LEVELS: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A lock decorator assigning a level to the wrapped lock `L`, enforcing a global lock ordering.
+///
+/// Every acquisition made through a [`Hierarchy`] is recorded for the current thread. Blocking on a
+/// lock whose level is not strictly higher than the level of every lock already held by the same
+/// thread panics, since two threads doing so in opposite orders would deadlock. In particular, two
+/// locks of the same level can only be held together if all but the first are acquired
+/// immediately: immediate acquisitions cannot deadlock and are therefore allowed in any order, but
+/// are still recorded.
+///
+/// Levels are tracked per thread, so the guards are `!Send` and always released on the thread that
+/// acquired them.
+#[derive(Debug)]
+pub struct Hierarchy<L> {
+    lock: L,
+    level: u8,
+}
+
+/// A guard returned by a [`Hierarchy`] lock, releasing its level when dropped.
+#[derive(Debug)]
+pub struct HierarchyGuard<G> {
+    guard: G,
+    level: u8,
+    // The level is released from the thread-local record of the thread dropping the guard.
+    _not_send: PhantomData<*const ()>,
+}
+
+// SAFETY: the marker only opts out of `Send`, sharing the guard is as safe as sharing `G`.
+unsafe impl<G: Sync> Sync for HierarchyGuard<G> {}
+
+impl<L> Hierarchy<L> {
+    /// Wraps `lock`, assigning it the given level.
+    pub const fn new(lock: L, level: u8) -> Self {
+        Self { lock, level }
+    }
+
+    /// Returns the level assigned to the lock.
+    pub const fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Panics if the current thread holds a lock with a level higher than or equal to this one.
+    #[track_caller]
+    fn check_order(&self) {
+        // Asserting outside of the closure reports the panic at the location of the acquisition.
+        if let Some(max) = HELD_LEVELS.with_borrow(|held| held.iter().max().copied()) {
+            assert!(
+                max < self.level,
+                "lock hierarchy violation: acquiring a lock of level {} while holding a lock of level {}",
+                self.level,
+                max
+            );
+        }
+    }
+}
+
+impl<G> HierarchyGuard<G> {
+    /// Returns the level of the lock this guard belongs to.
+    pub const fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+impl<G> Drop for HierarchyGuard<G> {
+    fn drop(&mut self) {
+        // Guards are not necessarily dropped in acquisition order.
+        let _ = HELD_LEVELS.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(index) = held.iter().rposition(|&level| level == self.level) {
+                held.remove(index);
+            }
+        });
+    }
+}
+
+impl<G: Deref> Deref for HierarchyGuard<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for HierarchyGuard<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Hierarchy<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = HierarchyGuard<G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    #[track_caller]
+    fn before_blocking(&self) {
+        self.check_order();
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<HierarchyGuard<G>, E> {
+        res.map(|guard| {
+            HELD_LEVELS.with_borrow_mut(|held| held.push(self.level));
+            HierarchyGuard {
+                guard,
+                level: self.level,
+                _not_send: PhantomData,
+            }
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Hierarchy<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = HierarchyGuard<_>;
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ::std::sync::Mutex;
+
+    use super::*;
+    use crate::{LockBlocking, LockImmediate, macros::assert_not_impl};
+
+    assert_not_impl!(HierarchyGuard<()>: Send);
+
+    #[test]
+    fn increasing_levels_can_be_held_together() {
+        let (low, high) = (
+            Hierarchy::new(Mutex::new(()), 1),
+            Hierarchy::new(Mutex::new(()), 2),
+        );
+        let _low = low.lock_blocking().unwrap();
+        let _high = high.lock_blocking().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "lock hierarchy violation")]
+    fn decreasing_levels_panic() {
+        let (low, high) = (
+            Hierarchy::new(Mutex::new(()), 1),
+            Hierarchy::new(Mutex::new(()), 2),
+        );
+        let _high = high.lock_blocking().unwrap();
+        let _low = low.lock_blocking();
+    }
+
+    #[test]
+    #[should_panic(expected = "lock hierarchy violation")]
+    fn equal_levels_panic() {
+        let (a, b) = (
+            Hierarchy::new(Mutex::new(()), 1),
+            Hierarchy::new(Mutex::new(()), 1),
+        );
+        let _a = a.lock_blocking().unwrap();
+        let _b = b.lock_blocking();
+    }
+
+    #[test]
+    fn immediate_acquisitions_ignore_the_order() {
+        let (a, b) = (
+            Hierarchy::new(Mutex::new(()), 1),
+            Hierarchy::new(Mutex::new(()), 1),
+        );
+        let _a = a.lock_blocking().unwrap();
+        let _b = b.lock_immediate().unwrap();
+    }
+
+    #[test]
+    fn released_levels_are_forgotten() {
+        let (low, high) = (
+            Hierarchy::new(Mutex::new(()), 1),
+            Hierarchy::new(Mutex::new(()), 2),
+        );
+        drop(high.lock_blocking().unwrap());
+        let _low = low.lock_blocking().unwrap();
+        let _high = high.lock_blocking().unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use ::core::ops::{Deref, DerefMut};
+
+use crate::{
+    SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Hooked`] lock, notifying hooks of its lifecycle events.
+pub type HookedSafeLock<L, H> = SafeLock<Hooked<L, H>>;
+
+/// A single callback of a [`Hooks`] set.
+///
+/// This is implemented by closures and by [`NoHook`], which compiles down to nothing.
+pub trait Hook {
+    /// Invokes the callback.
+    fn call(&self);
+}
+
+/// A [`Hook`] that does nothing, used for unset callbacks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NoHook;
+
+impl Hook for NoHook {
+    #[inline(always)]
+    fn call(&self) {}
+}
+
+impl<F: Fn()> Hook for F {
+    #[inline]
+    fn call(&self) {
+        self()
+    }
+}
+
+/// Callbacks invoked by a [`Hooked`] lock over the lifecycle of its guards.
+///
+/// Unset callbacks are [`NoHook`] and cost nothing.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hooks<A = NoHook, B = NoHook, C = NoHook> {
+    /// Runs right after the lock has been acquired, in any mode.
+    pub on_lock: A,
+    /// Runs the first time the data is accessed mutably through a guard.
+    pub on_upgrade: B,
+    /// Runs right before a guard releases the lock.
+    pub on_unlock: C,
+}
+
+impl Hooks {
+    /// Creates a set of hooks where every callback is unset.
+    pub const fn new() -> Self {
+        Self {
+            on_lock: NoHook,
+            on_upgrade: NoHook,
+            on_unlock: NoHook,
+        }
+    }
+}
+
+impl<A, B, C> Hooks<A, B, C> {
+    /// Sets the callback invoked right after the lock has been acquired.
+    pub fn on_lock<F: Hook>(self, f: F) -> Hooks<F, B, C> {
+        Hooks {
+            on_lock: f,
+            on_upgrade: self.on_upgrade,
+            on_unlock: self.on_unlock,
+        }
+    }
+
+    /// Sets the callback invoked the first time the data is accessed mutably through a guard.
+    pub fn on_upgrade<F: Hook>(self, f: F) -> Hooks<A, F, C> {
+        Hooks {
+            on_lock: self.on_lock,
+            on_upgrade: f,
+            on_unlock: self.on_unlock,
+        }
+    }
+
+    /// Sets the callback invoked right before a guard releases the lock.
+    pub fn on_unlock<F: Hook>(self, f: F) -> Hooks<A, B, F> {
+        Hooks {
+            on_lock: self.on_lock,
+            on_upgrade: self.on_upgrade,
+            on_unlock: f,
+        }
+    }
+}
+
+/// A lock decorator invoking [`Hooks`] around the acquisitions of the wrapped lock `L`.
+#[derive(Debug)]
+pub struct Hooked<L, H> {
+    lock: L,
+    hooks: H,
+}
+
+/// A guard returned by a [`Hooked`] lock.
+#[derive(Debug)]
+pub struct HookedGuard<'a, G, B: Hook, C: Hook> {
+    guard: G,
+    on_upgrade: &'a B,
+    on_unlock: &'a C,
+    upgraded: bool,
+}
+
+impl<L> SafeLock<L> {
+    /// Creates a new [`SafeLock`] wrapping the provided lock, invoking `hooks` over its lifecycle.
+    ///
+    /// This makes it possible to plug in logging, assertions or profiling. See [`Hooks`] for when
+    /// each callback runs.
+    pub const fn with_hooks<A, B, C>(
+        lock: L,
+        hooks: Hooks<A, B, C>,
+    ) -> HookedSafeLock<L, Hooks<A, B, C>> {
+        SafeLock(Hooked { lock, hooks })
+    }
+}
+
+impl<L, A: Hook, B: Hook, C: Hook> Hooked<L, Hooks<A, B, C>> {
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<G, B: Hook, C: Hook> Drop for HookedGuard<'_, G, B, C> {
+    fn drop(&mut self) {
+        self.on_unlock.call();
+    }
+}
+
+impl<G: Deref, B: Hook, C: Hook> Deref for HookedGuard<'_, G, B, C> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut, B: Hook, C: Hook> DerefMut for HookedGuard<'_, G, B, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !self.upgraded {
+            self.upgraded = true;
+            self.on_upgrade.call();
+        }
+        &mut self.guard
+    }
+}
+
+impl<L, A: Hook, B: Hook, C: Hook> Layer for Hooked<L, Hooks<A, B, C>> {
+    type Inner = L;
+    type Guard<'a, G>
+        = HookedGuard<'a, G, B, C>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        _exclusive: bool,
+    ) -> Result<HookedGuard<'_, G, B, C>, E> {
+        res.map(|guard| {
+            self.hooks.on_lock.call();
+            HookedGuard {
+                guard,
+                on_upgrade: &self.hooks.on_upgrade,
+                on_unlock: &self.hooks.on_unlock,
+                upgraded: false,
+            }
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L, A: Hook, B: Hook, C: Hook] Hooked<L, Hooks<A, B, C>> =>
+        LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = HookedGuard<'a, _, B, C>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::cell::RefCell;
+    use ::parking_lot::RwLock;
+
+    use super::*;
+
+    #[test]
+    fn hooks_run_over_the_guard_lifecycle() {
+        let events = RefCell::new(Vec::new());
+        let hooks = Hooks::new()
+            .on_lock(|| events.borrow_mut().push("lock"))
+            .on_upgrade(|| events.borrow_mut().push("upgrade"))
+            .on_unlock(|| events.borrow_mut().push("unlock"));
+        let lock = SafeLock::with_hooks(RwLock::new(0), hooks);
+
+        let guard = lock.by_ref().lock_blocking();
+        let value = *guard;
+        let mut data = guard.upgrade();
+        *data = value + 1;
+        *data += 1;
+        drop(data);
+        drop(lock.by_ref().read_blocking());
+        assert_eq!(
+            *events.borrow(),
+            ["lock", "upgrade", "unlock", "lock", "unlock"]
+        );
+        assert_eq!(*lock.0.inner().read(), 2);
+    }
+}
@@ -0,0 +1,604 @@
+use ::core::{
+    cell::UnsafeCell,
+    convert::Infallible,
+    error::Error,
+    ffi::CStr,
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicU32, Ordering},
+};
+use ::std::io;
+
+use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+
+/// A wrapper around an [`InterprocessMutex`], providing safe locking behavior across processes.
+pub type SafeInterprocessMutex<'a, T> = SafeLock<&'a InterprocessMutex<T>>;
+pub type SafeInterprocessMutexGuard<'a, T> =
+    SafeGuard<&'a InterprocessMutex<T>, InterprocessMutexGuard<'a, T>>;
+
+/// The value of the header of a [`SharedMemory`] segment once its mutex is initialized.
+const READY: u32 = 0x5AFE_10C4;
+
+/// A mutex protecting a value of type `T` living in memory shared between processes.
+///
+/// The mutex is a robust, process-shared `pthread_mutex_t`: when a process dies while holding it,
+/// the next acquisition still holds the lock, so the data can be repaired before being
+/// [marked consistent](InterprocessMutexGuard::make_consistent).
+/// [`lock_blocking_or_owner_died`](SafeLock::lock_blocking_or_owner_died) reports it with
+/// [`InterprocessError::OwnerDied`]. The [`LockBlocking`] and [`LockImmediate`] implementations
+/// return the guard as a success instead, so that generic code dropping errors never releases an
+/// inconsistent mutex by accident: their callers must check
+/// [`is_inconsistent`](InterprocessMutexGuard::is_inconsistent).
+///
+/// A mutex cannot be moved once initialized, so it is only ever handled by reference, either
+/// through a [`SharedMemory`] segment or by initializing it in place with [`init`](Self::init).
+#[repr(C)]
+pub struct InterprocessMutex<T: ?Sized> {
+    raw: UnsafeCell<libc::pthread_mutex_t>,
+    data: UnsafeCell<T>,
+}
+
+/// A guard holding an [`InterprocessMutex`].
+pub struct InterprocessMutexGuard<'a, T: ?Sized> {
+    lock: &'a InterprocessMutex<T>,
+    inconsistent: bool,
+    // Releasing a `pthread_mutex_t` from another thread is undefined behavior.
+    _not_send: PhantomData<*mut ()>,
+}
+
+/// An error returned when acquiring an [`InterprocessMutex`].
+pub enum InterprocessError<G> {
+    /// The previous owner of the mutex died while holding it. The mutex is now held through the
+    /// guard, and must be [marked consistent](InterprocessMutexGuard::make_consistent) before it is
+    /// released, or it becomes unusable.
+    OwnerDied(G),
+    /// A previous owner died and the mutex was released without being marked consistent. It can no
+    /// longer be acquired.
+    NotRecoverable,
+    /// The mutex is held by another thread or process.
+    WouldBlock,
+    /// The operating system returned an error.
+    Os(io::Error),
+}
+
+/// A named shared memory segment holding an [`InterprocessMutex`].
+///
+/// The segment is created by one process with [`create`](Self::create), and mapped by the others
+/// with [`open`](Self::open). It is unmapped when dropped, and removed from the system by
+/// [`unlink`](Self::unlink).
+pub struct SharedMemory<T> {
+    segment: NonNull<Segment<T>>,
+}
+
+#[repr(C)]
+struct Segment<T> {
+    state: AtomicU32,
+    mutex: InterprocessMutex<T>,
+}
+
+// SAFETY: the mutex owns its data, which can be moved to another thread along with it. The raw
+// `pthread_mutex_t` is not tied to the thread that initialized it.
+unsafe impl<T: ?Sized + Send> Send for InterprocessMutex<T> {}
+// SAFETY: access to the data is synchronized by the mutex, as for `std::sync::Mutex`.
+unsafe impl<T: ?Sized + Send> Sync for InterprocessMutex<T> {}
+
+// SAFETY: a shared guard only gives access to `&T`, which can be shared between threads when
+// `T: Sync`. Releasing the mutex requires owning the guard, which stays on its thread.
+unsafe impl<T: ?Sized + Sync> Sync for InterprocessMutexGuard<'_, T> {}
+
+// SAFETY: the segment only hands out shared references to a `Sync` mutex, and can be unmapped from
+// any thread.
+unsafe impl<T: Send> Send for SharedMemory<T> {}
+// SAFETY: the segment only hands out shared references to a `Sync` mutex.
+unsafe impl<T: Send> Sync for SharedMemory<T> {}
+
+fn check(code: libc::c_int) -> io::Result<()> {
+    match code {
+        0 => Ok(()),
+        code => Err(io::Error::from_raw_os_error(code)),
+    }
+}
+
+fn check_fd(code: libc::c_int) -> io::Result<libc::c_int> {
+    match code {
+        -1 => Err(io::Error::last_os_error()),
+        fd => Ok(fd),
+    }
+}
+
+impl<G> InterprocessError<G> {
+    /// Maps the guard held by an [`OwnerDied`](Self::OwnerDied) error.
+    pub fn map<H>(self, f: impl FnOnce(G) -> H) -> InterprocessError<H> {
+        match self {
+            Self::OwnerDied(guard) => InterprocessError::OwnerDied(f(guard)),
+            Self::NotRecoverable => InterprocessError::NotRecoverable,
+            Self::WouldBlock => InterprocessError::WouldBlock,
+            Self::Os(err) => InterprocessError::Os(err),
+        }
+    }
+}
+
+impl<G> fmt::Debug for InterprocessError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OwnerDied(_) => f.debug_tuple("OwnerDied").finish_non_exhaustive(),
+            Self::NotRecoverable => f.write_str("NotRecoverable"),
+            Self::WouldBlock => f.write_str("WouldBlock"),
+            Self::Os(err) => f.debug_tuple("Os").field(err).finish(),
+        }
+    }
+}
+
+impl<G> fmt::Display for InterprocessError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OwnerDied(_) => {
+                f.write_str("the previous owner of the lock died while holding it")
+            }
+            Self::NotRecoverable => f.write_str("the lock is not recoverable"),
+            Self::WouldBlock => f.write_str("the lock is held by another thread or process"),
+            Self::Os(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<G> Error for InterprocessError<G> {}
+
+impl<T> InterprocessMutex<T> {
+    /// Initializes a robust, process-shared mutex protecting `value` at `ptr`, and returns a
+    /// reference to it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes and properly aligned, and the memory must stay valid and not
+    /// be moved for `'a`. It must not hold a mutex in use by any process. If the memory is shared,
+    /// `T` must not contain pointers to memory local to a process.
+    pub unsafe fn init<'a>(ptr: NonNull<Self>, value: T) -> io::Result<&'a Self> {
+        let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+        // SAFETY: the attributes are initialized before use and destroyed afterwards. `ptr` is
+        // valid for writes, as guaranteed by the caller.
+        unsafe {
+            check(libc::pthread_mutexattr_init(attr.as_mut_ptr()))?;
+            let res = check(libc::pthread_mutexattr_setpshared(
+                attr.as_mut_ptr(),
+                libc::PTHREAD_PROCESS_SHARED,
+            ))
+            .and_then(|()| {
+                check(libc::pthread_mutexattr_setrobust(
+                    attr.as_mut_ptr(),
+                    libc::PTHREAD_MUTEX_ROBUST,
+                ))
+            })
+            .and_then(|()| {
+                let raw = ptr::addr_of_mut!((*ptr.as_ptr()).raw).cast::<libc::pthread_mutex_t>();
+                check(libc::pthread_mutex_init(raw, attr.as_ptr()))
+            });
+            libc::pthread_mutexattr_destroy(attr.as_mut_ptr());
+            res?;
+            ptr::addr_of_mut!((*ptr.as_ptr()).data)
+                .cast::<T>()
+                .write(value);
+            Ok(ptr.as_ref())
+        }
+    }
+}
+
+impl<T: ?Sized> InterprocessMutex<T> {
+    /// Returns a reference to a mutex already initialized with [`init`](Self::init), possibly by
+    /// another process.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to an initialized mutex protecting a valid `T`, which stays valid and is not
+    /// moved for `'a`.
+    pub const unsafe fn from_ptr<'a>(ptr: NonNull<Self>) -> &'a Self {
+        // SAFETY: guaranteed by the caller.
+        unsafe { ptr.as_ref() }
+    }
+
+    /// Returns a pointer to the underlying `pthread_mutex_t`.
+    pub const fn as_ptr(&self) -> *mut libc::pthread_mutex_t {
+        self.raw.get()
+    }
+
+    fn guard(&self, inconsistent: bool) -> InterprocessMutexGuard<'_, T> {
+        InterprocessMutexGuard {
+            lock: self,
+            inconsistent,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns a guard if `code` reports the mutex as held, including when its owner died.
+    fn acquire(
+        &self,
+        code: libc::c_int,
+    ) -> Result<InterprocessMutexGuard<'_, T>, InterprocessError<Infallible>> {
+        match code {
+            0 => Ok(self.guard(false)),
+            libc::EOWNERDEAD => Ok(self.guard(true)),
+            libc::ENOTRECOVERABLE => Err(InterprocessError::NotRecoverable),
+            libc::EBUSY => Err(InterprocessError::WouldBlock),
+            code => Err(InterprocessError::Os(io::Error::from_raw_os_error(code))),
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for InterprocessMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterprocessMutex")
+            .field("raw", &self.raw.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> InterprocessMutexGuard<'_, T> {
+    /// Marks the mutex as consistent after its previous owner died while holding it.
+    ///
+    /// The data must have been repaired first. Does nothing if the previous owner released the
+    /// mutex normally.
+    pub fn make_consistent(&mut self) -> io::Result<()> {
+        if self.inconsistent {
+            // SAFETY: the guard holds the mutex.
+            check(unsafe { libc::pthread_mutex_consistent(self.lock.as_ptr()) })?;
+            self.inconsistent = false;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the previous owner of the mutex died while holding it, and the mutex was not
+    /// marked consistent since.
+    pub const fn is_inconsistent(&self) -> bool {
+        self.inconsistent
+    }
+}
+
+impl<L, T: ?Sized> SafeGuard<L, InterprocessMutexGuard<'_, T>> {
+    /// Marks the mutex as consistent after its previous owner died while holding it.
+    ///
+    /// The data must have been repaired first, typically during the inspection phase.
+    pub fn make_consistent(&mut self) -> io::Result<()> {
+        self.guard.make_consistent()
+    }
+}
+
+impl<T: ?Sized> Drop for InterprocessMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the mutex.
+        let code = unsafe { libc::pthread_mutex_unlock(self.lock.as_ptr()) };
+        debug_assert_eq!(code, 0, "failed to unlock an interprocess mutex");
+    }
+}
+
+impl<T: ?Sized> Deref for InterprocessMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the guard holds the mutex.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for InterprocessMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the guard holds the mutex.
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for InterprocessMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> SharedMemory<T> {
+    const SIZE: usize = mem::size_of::<Segment<T>>();
+
+    /// Creates the shared memory segment `name`, holding a new mutex protecting `value`.
+    ///
+    /// `name` must start with a slash, and must not name an existing segment. `T` must not contain
+    /// pointers to memory local to the process, such as references or heap allocations.
+    pub fn create(name: &CStr, value: T) -> io::Result<Self> {
+        let flags = libc::O_CREAT | libc::O_EXCL | libc::O_RDWR;
+        // SAFETY: `name` is a valid C string.
+        let fd = check_fd(unsafe { libc::shm_open(name.as_ptr(), flags, 0o600) })?;
+        // SAFETY: `fd` is a valid descriptor, which is closed once mapped.
+        let segment = unsafe {
+            let res = check_fd(libc::ftruncate(fd, Self::SIZE as libc::off_t))
+                .and_then(|_| Self::map(fd));
+            libc::close(fd);
+            res
+        };
+        let segment = segment.inspect_err(|_| {
+            // SAFETY: `name` is a valid C string, naming the segment created above.
+            unsafe { libc::shm_unlink(name.as_ptr()) };
+        })?;
+        // Unmapped when dropped, including if the initialization below fails.
+        let shared = Self { segment };
+        // SAFETY: the mapping is fresh, large enough and page-aligned. The header is published
+        // only once the mutex is initialized.
+        unsafe {
+            let mutex = NonNull::new_unchecked(ptr::addr_of_mut!((*segment.as_ptr()).mutex));
+            if let Err(err) = InterprocessMutex::init(mutex, value) {
+                drop(shared);
+                // SAFETY: `name` is a valid C string, naming the segment created above.
+                libc::shm_unlink(name.as_ptr());
+                return Err(err);
+            }
+            (*segment.as_ptr()).state.store(READY, Ordering::Release);
+        }
+        Ok(shared)
+    }
+
+    /// Maps the existing shared memory segment `name`.
+    ///
+    /// Fails with [`io::ErrorKind::WouldBlock`] if the creator of the segment did not finish
+    /// initializing it yet.
+    ///
+    /// # Safety
+    ///
+    /// The segment must have been created by [`create`](Self::create) with the same type `T`, by a
+    /// program built with the same layout for it.
+    pub unsafe fn open(name: &CStr) -> io::Result<Self> {
+        // SAFETY: `name` is a valid C string.
+        let fd = check_fd(unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR, 0) })?;
+        // SAFETY: `fd` is a valid descriptor, which is closed once mapped.
+        let segment = unsafe {
+            let mut stat = MaybeUninit::<libc::stat>::uninit();
+            let res = check_fd(libc::fstat(fd, stat.as_mut_ptr())).and_then(|_| {
+                if (stat.assume_init().st_size as usize) < Self::SIZE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "shared memory segment too small",
+                    ));
+                }
+                Self::map(fd)
+            });
+            libc::close(fd);
+            res?
+        };
+        let shared = Self { segment };
+        // SAFETY: the mapping is large enough and page-aligned.
+        if unsafe { (*segment.as_ptr()).state.load(Ordering::Acquire) } != READY {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "shared memory segment not initialized yet",
+            ));
+        }
+        Ok(shared)
+    }
+
+    /// Removes the shared memory segment `name` from the system.
+    ///
+    /// Processes which already mapped it keep using it, until they drop their [`SharedMemory`].
+    pub fn unlink(name: &CStr) -> io::Result<()> {
+        // SAFETY: `name` is a valid C string.
+        check_fd(unsafe { libc::shm_unlink(name.as_ptr()) }).map(|_| ())
+    }
+
+    /// Returns the mutex held in the segment.
+    pub const fn mutex(&self) -> &InterprocessMutex<T> {
+        // SAFETY: the segment is mapped and initialized as long as `self` is alive.
+        unsafe { &(*self.segment.as_ptr()).mutex }
+    }
+
+    /// # Safety
+    ///
+    /// `fd` must be a valid descriptor of a shared memory object of at least `SIZE` bytes.
+    unsafe fn map(fd: libc::c_int) -> io::Result<NonNull<Segment<T>>> {
+        // SAFETY: guaranteed by the caller.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                Self::SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `mmap` does not return null on success.
+        Ok(unsafe { NonNull::new_unchecked(ptr.cast()) })
+    }
+}
+
+impl<T> Deref for SharedMemory<T> {
+    type Target = InterprocessMutex<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.mutex()
+    }
+}
+
+impl<T> Drop for SharedMemory<T> {
+    fn drop(&mut self) {
+        // SAFETY: the segment was mapped with `SIZE` bytes, and no reference to it outlives `self`.
+        unsafe { libc::munmap(self.segment.as_ptr().cast(), Self::SIZE) };
+    }
+}
+
+impl<T> fmt::Debug for SharedMemory<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("segment", &self.segment)
+            .finish()
+    }
+}
+
+impl<'a, T: ?Sized> SafeLock<&'a InterprocessMutex<T>> {
+    /// Acquires the mutex and returns a guard for the locked data.
+    ///
+    /// If the previous owner of the mutex died while holding it, the guard is still handed back
+    /// inside [`InterprocessError::OwnerDied`]. The data must then be repaired during inspection,
+    /// and the mutex [marked consistent](SafeGuard::make_consistent), before the guard is released.
+    pub fn lock_blocking_or_owner_died(
+        self,
+    ) -> Result<
+        SafeInterprocessMutexGuard<'a, T>,
+        InterprocessError<SafeInterprocessMutexGuard<'a, T>>,
+    > {
+        match self.0.lock_blocking() {
+            Ok(guard) if guard.is_inconsistent() => {
+                Err(InterprocessError::OwnerDied(SafeGuard::new(self, guard)))
+            }
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err(err.map(|never| match never {})),
+        }
+    }
+}
+
+impl<T: ?Sized> LockBlocking for InterprocessMutex<T> {
+    type Error<'a>
+        = InterprocessError<Infallible>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = InterprocessMutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        // SAFETY: the mutex is initialized and alive.
+        self.acquire(unsafe { libc::pthread_mutex_lock(self.as_ptr()) })
+    }
+}
+
+impl<T: ?Sized> LockImmediate for InterprocessMutex<T> {
+    type Error<'a>
+        = InterprocessError<Infallible>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = InterprocessMutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        // SAFETY: the mutex is initialized and alive.
+        self.acquire(unsafe { libc::pthread_mutex_trylock(self.as_ptr()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::{ffi::CString, process, thread};
+
+    use super::*;
+
+    fn segment_name(test: &str) -> CString {
+        CString::new(format!("/safe-lock-{}-{test}", process::id())).unwrap()
+    }
+
+    /// Locks `mutex` from a thread that exits without releasing it.
+    fn abandon(mutex: &InterprocessMutex<u64>) {
+        thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(mutex).lock_blocking_or_owner_died().unwrap();
+                assert_eq!(*guard, 7);
+                mem::forget(guard);
+            });
+        });
+    }
+
+    #[test]
+    fn opened_segments_share_the_mutex() {
+        let name = segment_name("share");
+        let created = SharedMemory::create(&name, 0u64).unwrap();
+        // SAFETY: the segment was just created with the same type.
+        let opened = unsafe { SharedMemory::<u64>::open(&name) }.unwrap();
+        SharedMemory::<u64>::unlink(&name).unwrap();
+
+        let guard = SafeLock::new(created.mutex())
+            .lock_blocking_or_owner_died()
+            .unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        let guard = SafeLock::new(opened.mutex())
+            .lock_blocking_or_owner_died()
+            .unwrap();
+        assert_eq!(*guard, 1);
+    }
+
+    #[test]
+    fn existing_segments_are_not_created_again() {
+        let name = segment_name("exists");
+        let _created = SharedMemory::create(&name, 0u64).unwrap();
+        let err = SharedMemory::create(&name, 0u64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        SharedMemory::<u64>::unlink(&name).unwrap();
+    }
+
+    #[test]
+    fn dead_owners_are_reported_until_the_mutex_is_made_consistent() {
+        let name = segment_name("owner-died");
+        let shm = SharedMemory::create(&name, 7u64).unwrap();
+        SharedMemory::<u64>::unlink(&name).unwrap();
+        abandon(shm.mutex());
+
+        let Err(InterprocessError::OwnerDied(mut guard)) =
+            SafeLock::new(shm.mutex()).lock_blocking_or_owner_died()
+        else {
+            panic!("the death of the owner was not reported");
+        };
+        assert!(guard.as_guard().is_inconsistent());
+        if *guard == 7 {
+            guard.make_consistent().unwrap();
+            *guard.upgrade() = 0;
+        }
+
+        let guard = SafeLock::new(shm.mutex())
+            .lock_blocking_or_owner_died()
+            .unwrap();
+        assert!(!guard.as_guard().is_inconsistent());
+        assert_eq!(*guard, 0);
+    }
+
+    #[test]
+    fn generic_acquisitions_hand_back_the_guard_of_dead_owners() {
+        let name = segment_name("owner-died-generic");
+        let shm = SharedMemory::create(&name, 7u64).unwrap();
+        SharedMemory::<u64>::unlink(&name).unwrap();
+        abandon(shm.mutex());
+
+        let mut guard = SafeLock::new(shm.mutex()).try_lock_immediate().unwrap();
+        assert!(guard.as_guard().is_inconsistent());
+        guard.make_consistent().unwrap();
+        drop(guard);
+
+        let guard = SafeLock::new(shm.mutex())
+            .lock_blocking_or_owner_died()
+            .unwrap();
+        assert_eq!(*guard, 7);
+    }
+
+    #[test]
+    fn mutexes_released_while_inconsistent_are_not_recoverable() {
+        let name = segment_name("not-recoverable");
+        let shm = SharedMemory::create(&name, 7u64).unwrap();
+        SharedMemory::<u64>::unlink(&name).unwrap();
+        abandon(shm.mutex());
+
+        let Err(InterprocessError::OwnerDied(guard)) =
+            SafeLock::new(shm.mutex()).lock_blocking_or_owner_died()
+        else {
+            panic!("the death of the owner was not reported");
+        };
+        drop(guard);
+
+        assert!(matches!(
+            SafeLock::new(shm.mutex()).lock_blocking_or_owner_died(),
+            Err(InterprocessError::NotRecoverable)
+        ));
+        assert!(matches!(
+            shm.mutex().lock_immediate(),
+            Err(InterprocessError::NotRecoverable)
+        ));
+    }
+}
@@ -0,0 +1,541 @@
+use ::core::fmt;
+#[cfg(any(feature = "std", feature = "watchdog"))]
+use ::core::time::Duration;
+
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::Named;
+#[cfg(feature = "stats")]
+use crate::stats::Stats;
+#[cfg(feature = "watchdog")]
+use crate::watchdog::{HoldReport, Watchdog, ignore_report};
+use crate::{SafeLock, reentrancy::NonReentrant, versioned::Versioned};
+#[cfg(feature = "std")]
+use crate::{
+    deadline::DeadlineLock,
+    fair::Fair,
+    hierarchy::Hierarchy,
+    lease::{LeaseAction, LeaseExpired, Leased},
+    poison::Poisoning,
+};
+
+/// A decorator that can be stacked around a lock `L`.
+///
+/// Layers are combined with a [`LockBuilder`], so cross-cutting concerns such as instrumentation,
+/// ordering checks or timeouts can be composed around any lock, each decorator forwarding the lock
+/// traits it supports.
+pub trait LockLayer<L> {
+    /// The decorated lock.
+    type Lock;
+
+    /// Wraps `lock` with the decorator.
+    fn layer(&self, lock: L) -> Self::Lock;
+}
+
+/// A [`LockLayer`] leaving the lock untouched.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Identity;
+
+/// Two [`LockLayer`]s applied one around the other.
+#[derive(Debug, Clone, Copy)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+/// A [`LockLayer`] built from a closure, as returned by [`layer_fn`].
+#[derive(Clone, Copy)]
+pub struct LayerFn<F>(F);
+
+/// Stacks [`LockLayer`]s around a lock, the first layer added being the outermost one.
+///
+/// For instance, `LockBuilder::new().layer(NonReentrantLayer).layer(HierarchyLayer(3)).build(lock)`
+/// is the same as `SafeLock::new(NonReentrant::new(Hierarchy::new(lock, 3)))`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LockBuilder<L = Identity>(L);
+
+/// Creates a [`LockLayer`] from a closure wrapping a lock.
+pub const fn layer_fn<F>(f: F) -> LayerFn<F> {
+    LayerFn(f)
+}
+
+impl<L> LockLayer<L> for Identity {
+    type Lock = L;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        lock
+    }
+}
+
+impl<L, Inner: LockLayer<L>, Outer: LockLayer<Inner::Lock>> LockLayer<L> for Stack<Inner, Outer> {
+    type Lock = Outer::Lock;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        self.outer.layer(self.inner.layer(lock))
+    }
+}
+
+impl<L, M, F: Fn(L) -> M> LockLayer<L> for LayerFn<F> {
+    type Lock = M;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        (self.0)(lock)
+    }
+}
+
+impl<F> fmt::Debug for LayerFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LayerFn").finish_non_exhaustive()
+    }
+}
+
+impl LockBuilder {
+    /// Creates a builder without any layer.
+    pub const fn new() -> Self {
+        Self(Identity)
+    }
+}
+
+impl<L> LockBuilder<L> {
+    /// Adds `layer` inside the layers added so far.
+    pub fn layer<T>(self, layer: T) -> LockBuilder<Stack<T, L>> {
+        LockBuilder(Stack {
+            inner: layer,
+            outer: self.0,
+        })
+    }
+
+    /// Wraps `lock` with every layer, and returns a [`SafeLock`] over the result.
+    pub fn build<T>(&self, lock: T) -> SafeLock<L::Lock>
+    where
+        L: LockLayer<T>,
+    {
+        SafeLock(self.0.layer(lock))
+    }
+}
+
+/// A decorator wrapping the guards, and possibly the errors, of the lock `Inner` it decorates.
+///
+/// The lock traits of a decorator are generated by [`forward_lock_traits!`] from this trait: every
+/// acquisition goes through [`Layer::before_blocking`] if it may block, and then through
+/// [`Layer::wrap`] or [`Layer::wrap_immediate`].
+pub(crate) trait Layer {
+    /// The decorated lock.
+    type Inner;
+    /// The guard wrapping a guard `G` of the decorated lock.
+    type Guard<'a, G>
+    where
+        Self: 'a;
+    /// The error wrapping an error `E` of the decorated lock.
+    type Error<E>;
+
+    /// Returns the decorated lock.
+    fn inner(&self) -> &Self::Inner;
+
+    /// Runs before every acquisition that may block the current thread.
+    #[track_caller]
+    fn before_blocking(&self) {}
+
+    /// Wraps the outcome of an acquisition, in exclusive mode if `exclusive` is set.
+    #[track_caller]
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        exclusive: bool,
+    ) -> Result<Self::Guard<'_, G>, Self::Error<E>>;
+
+    /// Wraps the outcome of an acquisition that did not block, as [`Layer::wrap`] by default.
+    #[track_caller]
+    fn wrap_immediate<G, E>(
+        &self,
+        res: Result<G, E>,
+        exclusive: bool,
+    ) -> Result<Self::Guard<'_, G>, Self::Error<E>> {
+        self.wrap(res, exclusive)
+    }
+}
+
+/// Implements the listed lock traits for a decorator over the lock `L`, forwarding them to `L`
+/// through the [`Layer`] implementation of the decorator.
+///
+/// The guards of the decorator are spelled out with `_` standing for the guard of `L`, as in
+/// `type Guard = WatchGuard<'a, _>;`, and so are its errors, as in `type Error = NamedError<_>;`.
+/// Either is omitted when the decorator does not wrap them, and both must match the
+/// [`Layer::Guard`] and [`Layer::Error`] of the decorator.
+macro_rules! forward_lock_traits {
+    (
+        impl $generics:tt $ty:ty => $($trait:ident),+ $(,)?;
+        $(type Guard = $guard:ident<$($guard_lt:lifetime,)? _ $(, $guard_rest:ty)*>;)?
+        $(type Error = $error:ident<_>;)?
+    ) => {
+        $crate::layer::forward_lock_traits!(
+            @each [$($trait)+] $generics $ty,
+            [$($guard<$($guard_lt,)? _ $(, $guard_rest)*>)?],
+            [$($error)?]
+        );
+    };
+    (@each [] $generics:tt $ty:ty, $guard:tt, $error:tt) => {};
+    (@each [$trait:ident $($rest:ident)*] $generics:tt $ty:ty, $guard:tt, $error:tt) => {
+        $crate::layer::forward_lock_traits!(@$trait $generics $ty, $guard, $error);
+        $crate::layer::forward_lock_traits!(@each [$($rest)*] $generics $ty, $guard, $error);
+    };
+    (@guard [] $inner:ty) => {
+        $inner
+    };
+    (@guard [$guard:ident<$($lt:lifetime,)? _ $(, $rest:ty)*>] $inner:ty) => {
+        $guard<$($lt,)? $inner $(, $rest)*>
+    };
+    (@error [] $inner:ty) => {
+        $inner
+    };
+    (@error [$error:ident] $inner:ty) => {
+        $error<$inner>
+    };
+    (@LockBlocking [$($generics:tt)*] $ty:ty, $guard:tt, $error:tt) => {
+        impl<$($generics)*> $crate::LockBlocking for $ty
+        where
+            L: $crate::LockBlocking,
+        {
+            type Error<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::Error<'a>)
+            where
+                Self: 'a;
+            type Guard<'a>
+                = $crate::layer::forward_lock_traits!(@guard $guard L::Guard<'a>)
+            where
+                Self: 'a;
+
+            #[track_caller]
+            fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                $crate::layer::Layer::before_blocking(self);
+                let res = $crate::layer::Layer::inner(self).lock_blocking();
+                $crate::layer::Layer::wrap(self, res, true)
+            }
+        }
+    };
+    (@LockImmediate [$($generics:tt)*] $ty:ty, $guard:tt, $error:tt) => {
+        impl<$($generics)*> $crate::LockImmediate for $ty
+        where
+            L: $crate::LockImmediate,
+        {
+            type Error<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::Error<'a>)
+            where
+                Self: 'a;
+            type Guard<'a>
+                = $crate::layer::forward_lock_traits!(@guard $guard L::Guard<'a>)
+            where
+                Self: 'a;
+
+            #[track_caller]
+            fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                let res = $crate::layer::Layer::inner(self).lock_immediate();
+                $crate::layer::Layer::wrap_immediate(self, res, true)
+            }
+        }
+    };
+    (@LockTimed [$($generics:tt)*] $ty:ty, $guard:tt, $error:tt) => {
+        #[cfg(feature = "std")]
+        impl<$($generics)*> $crate::LockTimed for $ty
+        where
+            L: $crate::LockTimed,
+        {
+            type Error<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::Error<'a>)
+            where
+                Self: 'a;
+            type Guard<'a>
+                = $crate::layer::forward_lock_traits!(@guard $guard L::Guard<'a>)
+            where
+                Self: 'a;
+
+            #[track_caller]
+            fn lock_for(
+                &self,
+                timeout: ::core::time::Duration,
+            ) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                $crate::layer::Layer::before_blocking(self);
+                let res = $crate::layer::Layer::inner(self).lock_for(timeout);
+                $crate::layer::Layer::wrap(self, res, true)
+            }
+
+            #[track_caller]
+            fn lock_until(
+                &self,
+                deadline: ::std::time::Instant,
+            ) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                $crate::layer::Layer::before_blocking(self);
+                let res = $crate::layer::Layer::inner(self).lock_until(deadline);
+                $crate::layer::Layer::wrap(self, res, true)
+            }
+        }
+    };
+    (@LockShared [$($generics:tt)*] $ty:ty, $guard:tt, $error:tt) => {
+        impl<$($generics)*> $crate::LockShared for $ty
+        where
+            L: $crate::LockShared,
+        {
+            type Error<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::Error<'a>)
+            where
+                Self: 'a;
+            type ImmediateError<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::ImmediateError<'a>)
+            where
+                Self: 'a;
+            type Guard<'a>
+                = $crate::layer::forward_lock_traits!(@guard $guard L::Guard<'a>)
+            where
+                Self: 'a;
+
+            #[track_caller]
+            fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                $crate::layer::Layer::before_blocking(self);
+                let res = $crate::layer::Layer::inner(self).read_blocking();
+                $crate::layer::Layer::wrap(self, res, false)
+            }
+
+            #[track_caller]
+            fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+                let res = $crate::layer::Layer::inner(self).read_immediate();
+                $crate::layer::Layer::wrap_immediate(self, res, false)
+            }
+        }
+    };
+    (@LockAsync [$($generics:tt)*] $ty:ty, $guard:tt, $error:tt) => {
+        impl<$($generics)*> $crate::LockAsync for $ty
+        where
+            L: $crate::LockAsync,
+        {
+            type Error<'a>
+                = $crate::layer::forward_lock_traits!(@error $error L::Error<'a>)
+            where
+                Self: 'a;
+            type Guard<'a>
+                = $crate::layer::forward_lock_traits!(@guard $guard L::Guard<'a>)
+            where
+                Self: 'a;
+
+            async fn lock_async(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+                let res = $crate::layer::Layer::inner(self).lock_async().await;
+                $crate::layer::Layer::wrap(self, res, true)
+            }
+        }
+    };
+}
+
+pub(crate) use forward_lock_traits;
+
+/// A [`LockLayer`] wrapping locks in a [`DeadlineLock`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DeadlineLayer;
+
+#[cfg(feature = "std")]
+impl<L> LockLayer<L> for DeadlineLayer {
+    type Lock = DeadlineLock<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        DeadlineLock::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Fair`] lock.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FairLayer;
+
+#[cfg(feature = "std")]
+impl<L> LockLayer<L> for FairLayer {
+    type Lock = Fair<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Fair::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Hierarchy`] of the given level.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct HierarchyLayer(pub u8);
+
+#[cfg(feature = "std")]
+impl<L> LockLayer<L> for HierarchyLayer {
+    type Lock = Hierarchy<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Hierarchy::new(lock, self.0)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Leased`] lock with the given lease and action.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct LeaseLayer<F = fn(&LeaseExpired)> {
+    lease: Duration,
+    action: LeaseAction<F>,
+}
+
+#[cfg(feature = "std")]
+impl LeaseLayer {
+    /// Creates a layer panicking whenever a guard is held longer than `lease`.
+    pub const fn new(lease: Duration) -> Self {
+        Self::with_action(lease, LeaseAction::Panic)
+    }
+
+    /// Creates a layer flagging locks as expired whenever a guard is held longer than `lease`.
+    pub const fn poisoning(lease: Duration) -> Self {
+        Self::with_action(lease, LeaseAction::Poison)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F> LeaseLayer<F> {
+    /// Creates a layer triggering `action` whenever a guard is held longer than `lease`.
+    pub const fn with_action(lease: Duration, action: LeaseAction<F>) -> Self {
+        Self { lease, action }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L, F: Fn(&LeaseExpired) + Clone> LockLayer<L> for LeaseLayer<F> {
+    type Lock = Leased<L, F>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Leased::with_action(lock, self.lease, self.action.clone())
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Named`] lock of the given name.
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone, Copy)]
+pub struct NamedLayer(pub &'static str);
+
+#[cfg(feature = "diagnostics")]
+impl<L> LockLayer<L> for NamedLayer {
+    type Lock = Named<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Named::new(lock, self.0)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`NonReentrant`] lock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NonReentrantLayer;
+
+impl<L> LockLayer<L> for NonReentrantLayer {
+    type Lock = NonReentrant<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        NonReentrant::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Poisoning`] lock.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PoisoningLayer;
+
+#[cfg(feature = "std")]
+impl<L> LockLayer<L> for PoisoningLayer {
+    type Lock = Poisoning<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Poisoning::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Stats`] lock.
+#[cfg(feature = "stats")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StatsLayer;
+
+#[cfg(feature = "stats")]
+impl<L> LockLayer<L> for StatsLayer {
+    type Lock = Stats<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Stats::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Versioned`] lock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VersionedLayer;
+
+impl<L> LockLayer<L> for VersionedLayer {
+    type Lock = Versioned<L>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Versioned::new(lock)
+    }
+}
+
+/// A [`LockLayer`] wrapping locks in a [`Watchdog`] with the given threshold and callback.
+#[cfg(feature = "watchdog")]
+#[derive(Debug, Clone, Copy)]
+pub struct WatchdogLayer<F = fn(&HoldReport)> {
+    threshold: Duration,
+    callback: F,
+}
+
+#[cfg(feature = "watchdog")]
+impl WatchdogLayer {
+    /// Creates a layer discarding the reports of the guards held longer than `threshold`, as done by
+    /// [`Watchdog::new`].
+    pub const fn new(threshold: Duration) -> Self {
+        Self::with_callback(threshold, ignore_report)
+    }
+}
+
+#[cfg(feature = "watchdog")]
+impl<F> WatchdogLayer<F> {
+    /// Creates a layer invoking `callback` whenever a guard is held longer than `threshold`.
+    pub const fn with_callback(threshold: Duration, callback: F) -> Self {
+        Self {
+            threshold,
+            callback,
+        }
+    }
+}
+
+#[cfg(feature = "watchdog")]
+impl<L, F: Fn(&HoldReport) + Clone> LockLayer<L> for WatchdogLayer<F> {
+    type Lock = Watchdog<L, F>;
+
+    fn layer(&self, lock: L) -> Self::Lock {
+        Watchdog::with_callback(lock, self.threshold, self.callback.clone())
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn the_first_layer_is_the_outermost() {
+        let lock: SafeLock<NonReentrant<Versioned<Mutex<i32>>>> = LockBuilder::new()
+            .layer(NonReentrantLayer)
+            .layer(VersionedLayer)
+            .build(Mutex::new(0));
+        let guard = lock.by_ref().lock_blocking();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.by_ref().lock_blocking(), 1);
+    }
+
+    #[test]
+    fn closures_are_layers() {
+        let builder = LockBuilder::new()
+            .layer(layer_fn(|lock: Mutex<i32>| {
+                *lock.lock() += 1;
+                lock
+            }))
+            .layer(Identity);
+        let lock = builder.build(Mutex::new(0));
+        assert_eq!(*lock.by_ref().lock_blocking(), 1);
+        assert_eq!(*builder.build(Mutex::new(1)).lock_blocking_ref(), 2);
+    }
+}
@@ -0,0 +1,348 @@
+use ::core::{
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use ::std::{thread, time::Instant};
+
+use crate::{
+    LockError, LockErrorKind, SafeGuard, SafeLock, SafeReadGuard,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Leased`] lock, bounding how long its guards may be held.
+pub type LeasedSafeLock<L, F = fn(&LeaseExpired)> = SafeLock<Leased<L, F>>;
+/// A [`SafeGuard`] over a [`Leased`] lock, which can be [renewed](SafeGuard::renew).
+pub type LeasedSafeGuard<'a, L, G, F = fn(&LeaseExpired)> =
+    SafeGuard<&'a Leased<L, F>, LeasedGuard<'a, G, F>>;
+
+/// What a [`Leased`] lock does when a guard outlives its lease.
+#[derive(Debug, Clone, Copy)]
+pub enum LeaseAction<F = fn(&LeaseExpired)> {
+    /// Panics in the thread holding the guard the next time it uses it, unless it is already
+    /// panicking.
+    Panic,
+    /// Flags the lock as expired. Subsequent acquisitions fail with [`LeaseError::Expired`] until
+    /// the flag is [cleared](Leased::clear_expired).
+    Poison,
+    /// Invokes the callback with a [`LeaseExpired`] report.
+    Callback(F),
+}
+
+/// A lock decorator bounding how long the guards of the wrapped lock `L` may be held.
+///
+/// Every guard carries a lease, which starts when the lock is acquired and can be extended with
+/// [`renew`](SafeGuard::renew). The first time a guard is found to have outlived its lease, the
+/// [`LeaseAction`] of the lock is triggered.
+///
+/// Expiry is only detected when the guard is used: a lease is checked whenever its guard is
+/// dereferenced, renewed or released, and at no other time. There is no timer or background
+/// thread, and the lock is never released behind the back of its holder. A guard that is neither
+/// used nor released past its lease triggers nothing until it is, while the threads waiting for the
+/// lock stay blocked.
+#[derive(Debug)]
+pub struct Leased<L, F = fn(&LeaseExpired)> {
+    lock: L,
+    lease: Duration,
+    action: LeaseAction<F>,
+    expired: AtomicBool,
+}
+
+/// A report of a guard that outlived the lease of its [`Leased`] lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LeaseExpired {
+    /// The lease of the lock.
+    pub lease: Duration,
+    /// How long the guard was held since it was acquired or last renewed.
+    pub held: Duration,
+}
+
+/// A guard returned by a [`Leased`] lock, checking its lease whenever it is used.
+#[derive(Debug)]
+pub struct LeasedGuard<'a, G, F: Fn(&LeaseExpired) = fn(&LeaseExpired)> {
+    guard: G,
+    lease: Duration,
+    action: &'a LeaseAction<F>,
+    expired: &'a AtomicBool,
+    leased_at: Instant,
+    triggered: AtomicBool,
+}
+
+/// An error returned when acquiring a [`Leased`] lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeaseError<E> {
+    /// The wrapped lock could not be acquired.
+    Lock(E),
+    /// A previous guard outlived its lease while the action of the lock was
+    /// [`Poison`](LeaseAction::Poison). The lock is not held.
+    Expired,
+}
+
+impl fmt::Display for LeaseExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lock held for {:?}, exceeding its lease of {:?}",
+            self.held, self.lease
+        )
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for LeaseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lock(err) => err.fmt(f),
+            Self::Expired => f.write_str("expired lock: another task outlived its lease"),
+        }
+    }
+}
+
+impl<E: Error> Error for LeaseError<E> {}
+
+impl<E: Into<LockError>> From<LeaseError<E>> for LockError {
+    fn from(err: LeaseError<E>) -> Self {
+        match err {
+            LeaseError::Lock(err) => err.into(),
+            LeaseError::Expired => LockErrorKind::Poisoned.into(),
+        }
+    }
+}
+
+impl<L> Leased<L> {
+    /// Wraps `lock` to panic whenever a guard is held longer than `lease`.
+    pub const fn new(lock: L, lease: Duration) -> Self {
+        Self::with_action(lock, lease, LeaseAction::Panic)
+    }
+
+    /// Wraps `lock` to flag it as expired whenever a guard is held longer than `lease`.
+    pub const fn poisoning(lock: L, lease: Duration) -> Self {
+        Self::with_action(lock, lease, LeaseAction::Poison)
+    }
+}
+
+impl<L, F: Fn(&LeaseExpired)> Leased<L, F> {
+    /// Wraps `lock` to invoke `callback` whenever a guard is held longer than `lease`.
+    pub const fn with_callback(lock: L, lease: Duration, callback: F) -> Self {
+        Self::with_action(lock, lease, LeaseAction::Callback(callback))
+    }
+
+    /// Wraps `lock` to trigger `action` whenever a guard is held longer than `lease`.
+    pub const fn with_action(lock: L, lease: Duration, action: LeaseAction<F>) -> Self {
+        Self {
+            lock,
+            lease,
+            action,
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the lease of the lock.
+    pub const fn lease(&self) -> Duration {
+        self.lease
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns whether a guard outlived its lease while the action of the lock was
+    /// [`Poison`](LeaseAction::Poison).
+    pub fn is_expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    /// Clears the expired state of the lock.
+    ///
+    /// The data may be in an inconsistent state, which must be checked before upgrading the next
+    /// guard.
+    pub fn clear_expired(&self) {
+        self.expired.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<G, F: Fn(&LeaseExpired)> LeasedGuard<'_, G, F> {
+    /// Returns the time left before the lease expires.
+    pub fn remaining(&self) -> Duration {
+        self.lease.saturating_sub(self.leased_at.elapsed())
+    }
+
+    /// Checks the lease, then starts a new one.
+    #[track_caller]
+    pub fn renew(&mut self) {
+        self.check();
+        self.leased_at = Instant::now();
+        *self.triggered.get_mut() = false;
+    }
+
+    /// Triggers the action of the lock if the lease expired, at most once per lease.
+    #[track_caller]
+    fn check(&self) {
+        let held = self.leased_at.elapsed();
+        if held <= self.lease || self.triggered.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let report = LeaseExpired {
+            lease: self.lease,
+            held,
+        };
+        match self.action {
+            LeaseAction::Panic => {
+                if !thread::panicking() {
+                    panic!("{report}");
+                }
+            }
+            LeaseAction::Poison => self.expired.store(true, Ordering::Relaxed),
+            LeaseAction::Callback(callback) => callback(&report),
+        }
+    }
+}
+
+impl<L, G, F: Fn(&LeaseExpired)> SafeGuard<L, LeasedGuard<'_, G, F>> {
+    /// Checks the lease of the guard, then starts a new one.
+    ///
+    /// If the lease already expired, the action of the lock is triggered before renewing it.
+    #[track_caller]
+    pub fn renew(&mut self) {
+        self.guard.renew();
+    }
+
+    /// Returns the time left before the lease of the guard expires.
+    pub fn remaining(&self) -> Duration {
+        self.guard.remaining()
+    }
+}
+
+impl<L, G, F: Fn(&LeaseExpired)> SafeReadGuard<L, LeasedGuard<'_, G, F>> {
+    /// Checks the lease of the guard, then starts a new one.
+    ///
+    /// If the lease already expired, the action of the lock is triggered before renewing it.
+    #[track_caller]
+    pub fn renew(&mut self) {
+        self.guard.renew();
+    }
+
+    /// Returns the time left before the lease of the guard expires.
+    pub fn remaining(&self) -> Duration {
+        self.guard.remaining()
+    }
+}
+
+impl<G, F: Fn(&LeaseExpired)> Drop for LeasedGuard<'_, G, F> {
+    fn drop(&mut self) {
+        self.check();
+    }
+}
+
+impl<G: Deref, F: Fn(&LeaseExpired)> Deref for LeasedGuard<'_, G, F> {
+    type Target = G::Target;
+
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        self.check();
+        &self.guard
+    }
+}
+
+impl<G: DerefMut, F: Fn(&LeaseExpired)> DerefMut for LeasedGuard<'_, G, F> {
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.check();
+        &mut self.guard
+    }
+}
+
+impl<L, F: Fn(&LeaseExpired)> Layer for Leased<L, F> {
+    type Inner = L;
+    type Guard<'a, G>
+        = LeasedGuard<'a, G, F>
+    where
+        Self: 'a;
+    type Error<E> = LeaseError<E>;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        _exclusive: bool,
+    ) -> Result<LeasedGuard<'_, G, F>, LeaseError<E>> {
+        let guard = res.map_err(LeaseError::Lock)?;
+        if self.is_expired() {
+            return Err(LeaseError::Expired);
+        }
+        Ok(LeasedGuard {
+            guard,
+            lease: self.lease,
+            action: &self.action,
+            expired: &self.expired,
+            leased_at: Instant::now(),
+            triggered: AtomicBool::new(false),
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L, F: Fn(&LeaseExpired)] Leased<L, F> =>
+        LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = LeasedGuard<'a, _, F>;
+    type Error = LeaseError<_>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::cell::Cell;
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    const LEASE: Duration = Duration::from_millis(5);
+
+    #[test]
+    fn expiry_is_detected_on_use() {
+        let expirations = Cell::new(0);
+        let lock = Leased::with_callback(Mutex::new(0), LEASE, |_| {
+            expirations.set(expirations.get() + 1);
+        });
+        let Ok(guard) = SafeLock::new(&lock).try_lock_blocking() else {
+            panic!("the lock is free");
+        };
+        thread::sleep(LEASE * 2);
+        assert_eq!(expirations.get(), 0);
+
+        assert_eq!(*guard, 0);
+        assert_eq!(expirations.get(), 1);
+        drop(guard);
+        assert_eq!(expirations.get(), 1);
+    }
+
+    #[test]
+    fn renewing_starts_a_new_lease() {
+        let lock = Leased::new(Mutex::new(0), Duration::from_secs(60));
+        let mut guard = SafeLock::new(&lock).try_lock_blocking().unwrap();
+        thread::sleep(LEASE);
+        assert!(guard.remaining() < lock.lease());
+        guard.renew();
+        assert!(guard.remaining() > lock.lease() - LEASE);
+    }
+
+    #[test]
+    fn poisoning_leases_fail_the_next_acquisitions() {
+        let lock = Leased::poisoning(Mutex::new(0), LEASE);
+        let guard = SafeLock::new(&lock).try_lock_blocking().unwrap();
+        thread::sleep(LEASE * 2);
+        drop(guard);
+        assert!(lock.is_expired());
+        assert!(matches!(
+            SafeLock::new(&lock).try_lock_immediate_err(),
+            Err((_, LeaseError::Expired))
+        ));
+
+        lock.clear_expired();
+        assert!(SafeLock::new(&lock).try_lock_immediate().is_ok());
+    }
+}
@@ -1,29 +1,355 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![doc = include_str!("../README.md")]
 
-use ::core::{convert::Infallible, ops::Deref};
+#[cfg(feature = "std")]
+use ::core::time::Duration;
+use ::core::{
+    cmp,
+    convert::Infallible,
+    error::Error,
+    fmt,
+    future::Future,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut, Index},
+    panic::Location,
+};
+#[cfg(feature = "std")]
+use ::std::{
+    sync::{PoisonError, TryLockError},
+    time::Instant,
+};
 
+#[cfg(feature = "debug-checks")]
+use ::core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "arc-swap")]
+pub mod arc_swap;
+#[cfg(feature = "async-std")]
+pub mod async_std;
+pub mod backoff;
+pub mod cell;
+pub mod checked;
+pub mod cow;
+#[cfg(feature = "critical-section")]
+pub mod critical_section;
+#[cfg(feature = "dashmap")]
+pub mod dashmap;
+#[cfg(feature = "std")]
+pub mod deadline;
+#[cfg(feature = "std")]
+pub mod deferred;
+#[cfg(any(feature = "diagnostics", feature = "deadlock-detection"))]
+pub mod diagnostics;
+#[cfg(feature = "std")]
+pub mod dyn_lock;
+#[cfg(feature = "event-listener")]
+pub mod event_listener;
+pub mod exclusive;
+#[cfg(feature = "std")]
+pub mod fair;
+#[cfg(feature = "fs-lock")]
+pub mod fs_lock;
+pub mod future;
+pub mod group;
+#[cfg(feature = "std")]
+pub mod hierarchy;
+pub mod hooks;
+#[cfg(all(
+    feature = "interprocess",
+    any(target_os = "linux", target_os = "android", target_os = "freebsd")
+))]
+pub mod interprocess;
+pub mod layer;
+#[cfg(feature = "std")]
+pub mod lease;
+pub mod local;
+#[cfg(feature = "arc_lock")]
+pub mod lock_map;
+#[cfg(loom)]
+pub mod loom;
+mod macros;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod once;
 #[cfg(feature = "parking_lot")]
 pub mod parking_lot;
 #[cfg(feature = "std")]
+pub mod poison;
+#[cfg(all(feature = "libc", unix))]
+pub mod pthread;
+#[cfg(feature = "rayon")]
+mod rayon;
+pub mod reentrancy;
+pub mod seqlock;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "shuttle")]
+pub mod shuttle;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "std")]
 pub mod std;
+#[cfg(feature = "test-util")]
+pub mod stress;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+pub mod transaction;
+pub mod versioned;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watch;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
+
+/// Wraps selected fields of a struct into [`SafeLock`]s, generating accessors for them.
+#[cfg(feature = "derive")]
+pub use safe_lock_derive::safe_fields;
+
+// The derive macros refer to this crate as `::safe_lock`, which its own tests must resolve.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as safe_lock;
 
 /// A wrapper around a lock type `L` that ensures safe locking behavior.
 ///
 /// The `SafeLock` type provides methods for acquiring and releasing locks while
 /// enforcing safety against common locking mistakes like attempting to mutate data
 /// before confirming conditions or improperly handling the lock state across retries.
-#[derive(Debug)]
+///
+/// `SafeLock` is deliberately neither `Clone` nor `Copy`, even when wrapping a reference: the
+/// locking methods consume the handle, and unlocking a guard is the only way to get it back. A
+/// `SafeLock` owning its lock is acquired through `&self` instead, either with the `_ref` methods
+/// such as [`lock_blocking_ref`](Self::lock_blocking_ref), or by borrowing a fresh handle with
+/// [`by_ref`](Self::by_ref).
+#[derive(Debug, Default)]
 pub struct SafeLock<L>(L);
 
 /// A guard for a lock type `L` that holds the lock and allows inspection of the
 /// data through a guard type `G`. The guard prevents mutation until explicitly upgraded.
 ///
 /// This guard ensures that mutation is performed only after explicitly upgrading the lock.
+///
+/// A `SafeGuard` is [`Send`] or [`Sync`] exactly when both the handle and the underlying guard are.
+/// Whether a guard can be sent to another thread is therefore decided by the backend, e.g. by the
+/// `send_guard` feature for `parking_lot`, unless the lock is wrapped in a [`Local`](local::Local)
+/// decorator, whose guards are never sent.
 #[derive(Debug)]
 pub struct SafeGuard<L, G> {
     lock: SafeLock<L>,
     guard: G,
+    #[cfg(feature = "debug-checks")]
+    inspected: AtomicBool,
+}
+
+/// A marker returned by the operations that may release the lock for a while, such as
+/// [`bump`](SafeGuard::bump), telling that the data may have changed in between.
+///
+/// The data must be inspected again before relying on any earlier check.
+#[must_use = "the data may have changed and must be inspected again"]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MayHaveChanged;
+
+/// The guard returned by [`SafeGuard::get_or_insert_with`], dereferencing to the locked slot.
+#[derive(Debug)]
+pub enum GetOrInsert<S, G> {
+    /// The slot already held a value, and the guard was left read-only.
+    Present(S),
+    /// The slot was empty, and the value was inserted through the upgraded guard.
+    Inserted(G),
+}
+
+/// A guard for a lock type `L` holding the lock in upgradable read mode through a guard type `G`.
+///
+/// Unlike [`SafeGuard`], the inspection phase does not exclude other readers. Calling
+/// [`upgrade`](Self::upgrade) atomically converts the guard into a write guard.
+#[derive(Debug)]
+pub struct SafeUpgradableGuard<L, G> {
+    lock: SafeLock<L>,
+    guard: G,
+}
+
+/// A guard for a lock type `L` holding the lock in shared (read) mode through a guard type `G`.
+///
+/// This guard only provides read-only access to the data and cannot be upgraded.
+#[derive(Debug)]
+pub struct SafeReadGuard<L, G> {
+    lock: SafeLock<L>,
+    guard: G,
+}
+
+/// An error returned by the [`LockTimed`] acquisitions of the backends, telling whether the deadline
+/// expired or the lock failed with an error `E`.
+///
+/// It converts into a [`LockError`] of kind [`TimedOut`](LockErrorKind::TimedOut) when the deadline
+/// expired, and like `E` otherwise.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimedLockError<E = Infallible> {
+    /// The lock was still held when the deadline expired.
+    TimedOut,
+    /// The lock could not be acquired for another reason.
+    Lock(E),
+}
+
+/// A backend-independent error describing why a lock could not be acquired.
+///
+/// Every backend error converts into a [`LockError`], as does the `(SafeLock, error)` pair returned
+/// by the `_err` locking methods, so backend-generic code can propagate failures with `?`. The
+/// `()` error of backends that do not report why an acquisition failed converts to
+/// [`WouldBlock`](LockErrorKind::WouldBlock).
+///
+/// Besides its [`kind`](Self::kind), the error keeps the name of the lock and the location the
+/// acquisition was requested at when they are known, e.g. for the `Named` locks of the
+/// `diagnostics` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LockError {
+    kind: LockErrorKind,
+    name: Option<&'static str>,
+    location: Option<&'static Location<'static>>,
+}
+
+/// The reason a lock could not be acquired, as returned by [`LockError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LockErrorKind {
+    /// The lock is held, and the acquisition was not allowed to wait for it.
+    WouldBlock,
+    /// A thread panicked while holding the lock.
+    Poisoned,
+    /// The lock was still held when the deadline expired.
+    TimedOut,
+    /// The lock was dropped, which can only happen when it is referred to weakly.
+    Dropped,
+}
+
+impl LockError {
+    /// Creates an error of the given kind, without a name or a location.
+    pub const fn new(kind: LockErrorKind) -> Self {
+        Self {
+            kind,
+            name: None,
+            location: None,
+        }
+    }
+
+    /// Attaches the name of the lock and the location the acquisition was requested at.
+    pub const fn with_context(
+        self,
+        name: &'static str,
+        location: &'static Location<'static>,
+    ) -> Self {
+        Self {
+            kind: self.kind,
+            name: Some(name),
+            location: Some(location),
+        }
+    }
+
+    /// Returns why the lock could not be acquired.
+    pub const fn kind(&self) -> LockErrorKind {
+        self.kind
+    }
+
+    /// Returns the name of the lock, if known.
+    pub const fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Returns the location the acquisition was requested at, if known.
+    pub const fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+}
+
+impl fmt::Display for LockErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::WouldBlock => "the lock is held by someone else",
+            Self::Poisoned => "the lock is poisoned",
+            Self::TimedOut => "timed out waiting for the lock",
+            Self::Dropped => "the lock has been dropped",
+        })
+    }
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name {
+            write!(f, "lock '{name}': ")?;
+        }
+        write!(f, "{}", self.kind)?;
+        if let Some(location) = self.location {
+            write!(f, ", requested at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for LockError {}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Display> fmt::Display for TimedLockError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimedOut => LockErrorKind::TimedOut.fmt(f),
+            Self::Lock(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Error> Error for TimedLockError<E> {}
+
+impl From<LockErrorKind> for LockError {
+    fn from(kind: LockErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<Infallible> for LockError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl From<()> for LockError {
+    fn from((): ()) -> Self {
+        LockErrorKind::WouldBlock.into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G> From<PoisonError<G>> for LockError {
+    fn from(_: PoisonError<G>) -> Self {
+        LockErrorKind::Poisoned.into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G> From<TryLockError<G>> for LockError {
+    fn from(err: TryLockError<G>) -> Self {
+        match err {
+            TryLockError::Poisoned(_) => LockErrorKind::Poisoned.into(),
+            TryLockError::WouldBlock => LockErrorKind::WouldBlock.into(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Into<LockError>> From<TimedLockError<E>> for LockError {
+    fn from(err: TimedLockError<E>) -> Self {
+        match err {
+            TimedLockError::TimedOut => LockErrorKind::TimedOut.into(),
+            TimedLockError::Lock(err) => err.into(),
+        }
+    }
+}
+
+impl<L, E: Into<LockError>> From<(SafeLock<L>, E)> for LockError {
+    fn from((_, err): (SafeLock<L>, E)) -> Self {
+        err.into()
+    }
 }
 
 /// Trait for locks that support blocking behavior.
@@ -31,27 +357,216 @@ pub struct SafeGuard<L, G> {
 /// This trait provides a method to acquire the lock in a blocking manner and returns
 /// a guard to access the locked data. It is intended for types of locks that block
 /// the current thread until the lock becomes available.
+///
+/// The guard and error types are generic over the lifetime of the borrow of the lock, so the
+/// trait can be implemented directly on lock types such as `RwLock<T>`.
 pub trait LockBlocking {
-    type Error;
-    type Guard;
+    type Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
 
     /// Blocks the current thread until the lock can be acquired.
     ///
     /// Returns a guard that allows access to the data protected by the lock.
-    fn lock_blocking(&self) -> Result<Self::Guard, Self::Error>;
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>>;
 }
 
 /// Trait for locks that support immediate locking without blocking.
 ///
 /// This trait provides a method to try to acquire the lock without blocking.
 pub trait LockImmediate {
-    type Error;
-    type Guard;
+    type Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
 
     /// Attempts to acquire the lock immediately, without blocking.
     ///
     /// Returns a guard if successful, or an error if the lock is unavailable.
-    fn lock_immediate(&self) -> Result<Self::Guard, Self::Error>;
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>>;
+}
+
+/// Trait for locks that support blocking with a timeout.
+///
+/// This trait provides methods to acquire the lock while blocking the current thread for at
+/// most a bounded amount of time, sitting between [`LockBlocking`] and [`LockImmediate`].
+///
+/// Deadlines are [`Instant`]s, so this trait requires the `std` feature.
+#[cfg(feature = "std")]
+pub trait LockTimed {
+    type Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// Blocks the current thread until the lock can be acquired or the timeout expires.
+    ///
+    /// Returns a guard if successful, or an error if the lock could not be acquired in time. The
+    /// backends report an expired timeout with [`TimedLockError::TimedOut`].
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>>;
+
+    /// Blocks the current thread until the lock can be acquired or the deadline is reached.
+    ///
+    /// Returns a guard if successful, or an error if the lock could not be acquired in time. The
+    /// backends report an expired deadline with [`TimedLockError::TimedOut`].
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>>;
+}
+
+/// Trait for locks that support shared (read) locking.
+///
+/// This trait provides methods to acquire the lock in shared mode, either blocking the current
+/// thread until the lock becomes available or failing immediately if it is held exclusively.
+pub trait LockShared {
+    type Error<'a>
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// Blocks the current thread until the lock can be acquired in shared mode.
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>>;
+
+    /// Attempts to acquire the lock in shared mode immediately, without blocking.
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>>;
+}
+
+/// Trait for locks whose write guards can be downgraded into shared guards.
+///
+/// Downgrading keeps the lock held continuously: no writer can acquire the lock between the
+/// write guard being released and the shared guard being created.
+pub trait LockDowngrade {
+    type Guard<'a>
+    where
+        Self: 'a;
+    type DowngradedGuard<'a>
+    where
+        Self: 'a;
+
+    /// Atomically downgrades a write guard, returning the lock it belongs to and the shared guard.
+    fn downgrade<'a>(guard: Self::Guard<'a>) -> (&'a Self, Self::DowngradedGuard<'a>);
+}
+
+/// Trait for locks that support an upgradable read mode.
+///
+/// An upgradable read shares the lock with regular readers but excludes writers and other
+/// upgradable readers, so it can later be converted into a write lock atomically.
+pub trait LockUpgradable {
+    type Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
+    type UpgradedGuard<'a>
+    where
+        Self: 'a;
+
+    /// Blocks the current thread until the lock can be acquired in upgradable read mode.
+    fn lock_upgradable(&self) -> Result<Self::Guard<'_>, Self::Error<'_>>;
+
+    /// Atomically upgrades an upgradable read guard into a write guard.
+    fn upgrade<'a>(guard: Self::Guard<'a>) -> Self::UpgradedGuard<'a>;
+}
+
+/// Trait for locks that support asynchronous acquisition.
+///
+/// This trait provides a method returning a future that resolves once the lock has been
+/// acquired. It is intended for async-aware locks that suspend the current task instead of
+/// blocking the thread.
+pub trait LockAsync {
+    type Error<'a>
+    where
+        Self: 'a;
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// Waits asynchronously until the lock can be acquired.
+    ///
+    /// Returns a guard that allows access to the data protected by the lock.
+    fn lock_async(&self) -> impl Future<Output = Result<Self::Guard<'_>, Self::Error<'_>>>;
+}
+
+/// Trait for locks whose state can be probed without acquiring them.
+///
+/// Probing is only a hint, since the state may change right after: it allows skipping busy locks
+/// cheaply before committing to an acquisition.
+pub trait LockProbe {
+    /// Returns whether the lock is currently held, in any mode.
+    fn is_locked(&self) -> bool;
+
+    /// Returns whether the lock is currently held in exclusive mode.
+    fn is_locked_exclusive(&self) -> bool;
+}
+
+impl<'r, L: LockBlocking + ?Sized> LockBlocking for &'r L {
+    type Error<'a>
+        = L::Error<'r>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = L::Guard<'r>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        L::lock_blocking(self)
+    }
+}
+
+impl<'r, L: LockImmediate + ?Sized> LockImmediate for &'r L {
+    type Error<'a>
+        = L::Error<'r>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = L::Guard<'r>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        L::lock_immediate(self)
+    }
+}
+
+impl<L: LockProbe + ?Sized> LockProbe for &L {
+    fn is_locked(&self) -> bool {
+        L::is_locked(self)
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        L::is_locked_exclusive(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: LockProbe + ?Sized> LockProbe for ::std::sync::Arc<L> {
+    fn is_locked(&self) -> bool {
+        L::is_locked(self)
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        L::is_locked_exclusive(self)
+    }
+}
+
+impl<L> From<L> for SafeLock<L> {
+    fn from(lock: L) -> Self {
+        Self(lock)
+    }
 }
 
 impl<L> SafeLock<L> {
@@ -62,29 +577,201 @@ impl<L> SafeLock<L> {
         Self(lock)
     }
 
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.0
+    }
+
+    /// Consumes the [`SafeLock`], returning the wrapped lock.
+    pub fn into_inner(self) -> L {
+        self.0
+    }
+
+    /// Returns whether the lock is currently held, in any mode.
+    ///
+    /// This is only a hint, since the lock may be acquired or released right after.
+    pub fn is_locked(&self) -> bool
+    where
+        L: LockProbe,
+    {
+        self.0.is_locked()
+    }
+
+    /// Returns whether the lock is currently held in exclusive mode.
+    ///
+    /// This is only a hint, since the lock may be acquired or released right after.
+    pub fn is_locked_exclusive(&self) -> bool
+    where
+        L: LockProbe,
+    {
+        self.0.is_locked_exclusive()
+    }
+
+    /// Returns a [`SafeLock`] over a reference to the wrapped lock.
+    ///
+    /// This is how an owned lock enters the by-value locking workflow: the returned handle is
+    /// consumed by the locking methods and handed back by [`SafeGuard::unlock`].
+    pub const fn by_ref(&self) -> SafeLock<&L> {
+        SafeLock(&self.0)
+    }
+
+    /// Acquires the lock in write mode and returns a guard borrowing this [`SafeLock`].
+    ///
+    /// This is [`by_ref`](Self::by_ref) followed by [`lock_blocking`](SafeLock::lock_blocking), for
+    /// locks living in a struct field: dropping the guard releases the lock, with nothing to hand
+    /// back. Mutation is not possible until explicitly upgrading the guard.
+    #[track_caller]
+    pub fn lock_blocking_ref<'s>(&'s self) -> SafeGuard<&'s L, L::Guard<'s>>
+    where
+        L: LockBlocking<Error<'s> = Infallible>,
+    {
+        self.by_ref().lock_blocking()
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking and returns a guard borrowing
+    /// this [`SafeLock`] if successful.
+    ///
+    /// See [`lock_blocking_ref`](Self::lock_blocking_ref).
+    #[track_caller]
+    pub fn try_lock_immediate_ref<'s>(&'s self) -> Option<SafeGuard<&'s L, L::Guard<'s>>>
+    where
+        L: LockImmediate,
+    {
+        self.by_ref().try_lock_immediate().ok()
+    }
+
+    /// Acquires the lock in shared mode and returns a read-only guard borrowing this [`SafeLock`].
+    ///
+    /// See [`lock_blocking_ref`](Self::lock_blocking_ref).
+    #[track_caller]
+    pub fn read_blocking_ref<'s>(&'s self) -> SafeReadGuard<&'s L, L::Guard<'s>>
+    where
+        L: LockShared<Error<'s> = Infallible>,
+    {
+        self.by_ref().read_blocking()
+    }
+
+    /// Acquires the lock, and mutates the data with `mutate` only if `check` holds.
+    ///
+    /// This encapsulates the lock → inspect → upgrade → mutate workflow in a single call. The lock
+    /// is held in **write mode** throughout, so the data cannot change between the check and the
+    /// mutation. Returns whether the mutation was performed.
+    #[track_caller]
+    pub fn update_if<'s, C, M, T>(&'s self, check: C, mutate: M) -> bool
+    where
+        L: LockBlocking<Error<'s> = Infallible>,
+        L::Guard<'s>: DerefMut<Target = T>,
+        T: ?Sized,
+        C: FnOnce(&T) -> bool,
+        M: FnOnce(&mut T),
+    {
+        self.try_update(check, mutate).is_some()
+    }
+
+    /// Acquires the lock, and mutates the data with `mutate` only if `check` holds, returning its result.
+    ///
+    /// Behaves like [`update_if`](Self::update_if), but returns `Some` with the value produced by
+    /// `mutate` if the mutation was performed, or `None` otherwise.
+    #[track_caller]
+    pub fn try_update<'s, C, M, T, R>(&'s self, check: C, mutate: M) -> Option<R>
+    where
+        L: LockBlocking<Error<'s> = Infallible>,
+        L::Guard<'s>: DerefMut<Target = T>,
+        T: ?Sized,
+        C: FnOnce(&T) -> bool,
+        M: FnOnce(&mut T) -> R,
+    {
+        let mut guard = LockBlocking::lock_blocking(&self.0).unwrap();
+        if check(&guard) {
+            Some(mutate(&mut guard))
+        } else {
+            None
+        }
+    }
+
+    /// Acquires the lock, calls `f` with a reference to the data, and releases the lock.
+    ///
+    /// This is a one-shot consistent read, e.g. for metrics or assertions, that does not expose any
+    /// guard. The lock is acquired in **write mode**.
+    #[track_caller]
+    pub fn peek<'s, F, T, R>(&'s self, f: F) -> R
+    where
+        L: LockBlocking<Error<'s> = Infallible>,
+        L::Guard<'s>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&T) -> R,
+    {
+        f(&LockBlocking::lock_blocking(&self.0).unwrap())
+    }
+
+    /// Attempts to acquire the lock without blocking, and if successful, calls `f` with a reference
+    /// to the data and releases the lock.
+    ///
+    /// Returns `None` if the lock could not be acquired immediately.
+    #[track_caller]
+    pub fn try_peek<'s, F, T, R>(&'s self, f: F) -> Option<R>
+    where
+        L: LockImmediate,
+        L::Guard<'s>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&T) -> R,
+    {
+        LockImmediate::lock_immediate(&self.0)
+            .ok()
+            .map(|guard| f(&guard))
+    }
+
+    /// Updates the data optimistically, computing the new value without holding any lock.
+    ///
+    /// The data is first read in **shared mode** with `read`, and the lock is released before
+    /// running the expensive `compute` step on its output. The lock is then acquired in **write
+    /// mode**, and `write` must re-validate the data before applying the computed value, returning
+    /// whether it did. If the validation fails, the whole pipeline is retried.
+    #[track_caller]
+    pub fn modify_optimistic<'s, R, C, W, T, P, U>(&'s self, read: R, compute: C, write: W)
+    where
+        L: LockShared<Error<'s> = Infallible> + LockBlocking<Error<'s> = Infallible>,
+        <L as LockShared>::Guard<'s>: Deref<Target = T>,
+        <L as LockBlocking>::Guard<'s>: DerefMut<Target = T>,
+        T: ?Sized,
+        R: Fn(&T) -> P,
+        C: Fn(P) -> U,
+        W: Fn(&mut T, U) -> bool,
+    {
+        loop {
+            let snapshot = read(&LockShared::read_blocking(&self.0).unwrap());
+            let value = compute(snapshot);
+            if write(&mut LockBlocking::lock_blocking(&self.0).unwrap(), value) {
+                return;
+            }
+        }
+    }
+}
+
+impl<'a, L: ?Sized> SafeLock<&'a L> {
     /// Acquires the lock in write mode and returns a guard for the locked data.
     ///
     /// The lock is acquired in **write mode**, and the returned guard allows read-only access to the data.
     /// Mutation is not possible until explicitly upgrading the guard.
-    pub fn lock_blocking(self) -> SafeGuard<L, L::Guard>
+    #[track_caller]
+    pub fn lock_blocking(self) -> SafeGuard<&'a L, L::Guard<'a>>
     where
-        L: LockBlocking<Error = Infallible>,
+        L: LockBlocking<Error<'a> = Infallible>,
     {
-        SafeGuard {
-            guard: LockBlocking::lock_blocking(&self.0).unwrap(),
-            lock: self,
-        }
+        let guard = LockBlocking::lock_blocking(self.0).unwrap();
+        SafeGuard::new(self, guard)
     }
 
     /// Attempts to acquire the lock in write mode and returns a guard if successful.
     ///
     /// The lock is acquired in **write mode**. If the lock is already held, this method will return `Err(self)`.
-    pub fn try_lock_blocking(self) -> Result<SafeGuard<L, L::Guard>, Self>
+    #[track_caller]
+    pub fn try_lock_blocking(self) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
     where
         L: LockBlocking,
     {
-        match LockBlocking::lock_blocking(&self.0) {
-            Ok(guard) => Ok(SafeGuard { lock: self, guard }),
+        match LockBlocking::lock_blocking(self.0) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
             Err(_) => Err(self),
         }
     }
@@ -92,12 +779,16 @@ impl<L> SafeLock<L> {
     /// Attempts to acquire the lock in write mode and returns an error if it fails.
     ///
     /// The lock is acquired in **write mode**, and the method returns an error if the lock is unavailable.
-    pub fn try_lock_blocking_err(self) -> Result<SafeGuard<L, L::Guard>, (Self, L::Error)>
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_blocking_err(
+        self,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, (Self, L::Error<'a>)>
     where
         L: LockBlocking,
     {
-        match LockBlocking::lock_blocking(&self.0) {
-            Ok(guard) => Ok(SafeGuard { lock: self, guard }),
+        match LockBlocking::lock_blocking(self.0) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
             Err(err) => Err((self, err)),
         }
     }
@@ -106,57 +797,376 @@ impl<L> SafeLock<L> {
     ///
     /// This method tries to acquire the lock in **write mode** without blocking the current thread.
     /// The lock is either acquired successfully or the method returns an error.
-    pub fn lock_immediate(self) -> SafeGuard<L, L::Guard>
+    #[track_caller]
+    pub fn lock_immediate(self) -> SafeGuard<&'a L, L::Guard<'a>>
     where
-        L: LockImmediate<Error = Infallible>,
+        L: LockImmediate<Error<'a> = Infallible>,
     {
-        SafeGuard {
-            guard: LockImmediate::lock_immediate(&self.0).unwrap(),
-            lock: self,
-        }
+        let guard = LockImmediate::lock_immediate(self.0).unwrap();
+        SafeGuard::new(self, guard)
     }
 
     /// Attempts to acquire the lock in write mode without blocking and returns a guard if successful.
     ///
     /// If the lock is already held, this method will return `Err(self)` without blocking.
-    pub fn try_lock_immediate(self) -> Result<SafeGuard<L, L::Guard>, Self>
+    #[track_caller]
+    pub fn try_lock_immediate(self) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
     where
         L: LockImmediate,
     {
-        match LockImmediate::lock_immediate(&self.0) {
-            Ok(guard) => Ok(SafeGuard { lock: self, guard }),
+        match LockImmediate::lock_immediate(self.0) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
             Err(_) => Err(self),
         }
     }
 
     /// Attempts to acquire the lock in write mode immediately and returns an error if unsuccessful.
-    pub fn try_lock_immediate_err(self) -> Result<SafeGuard<L, L::Guard>, (Self, L::Error)>
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_immediate_err(
+        self,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, (Self, L::Error<'a>)>
     where
         L: LockImmediate,
     {
-        match LockImmediate::lock_immediate(&self.0) {
-            Ok(guard) => Ok(SafeGuard { lock: self, guard }),
+        match LockImmediate::lock_immediate(self.0) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
             Err(err) => Err((self, err)),
         }
     }
-}
 
-impl<L, G> SafeGuard<L, G> {
-    /// Upgrades the `SafeGuard` to the underlying guard, allowing mutation of the locked data.
+    /// Acquires the lock in write mode, trying without blocking first.
     ///
-    /// Even though the underlying lock was acquired in **write mode**, mutation of the data is only
-    /// possible after explicitly upgrading the guard. This ensures safety in concurrent code.
-    pub fn upgrade(self) -> G {
-        self.guard
+    /// If the lock is already held, `on_contention` is invoked (e.g. to log or count the event)
+    /// before blocking until the lock is acquired.
+    #[track_caller]
+    pub fn lock_immediate_or_blocking<F>(
+        self,
+        on_contention: F,
+    ) -> SafeGuard<&'a L, <L as LockBlocking>::Guard<'a>>
+    where
+        L: LockBlocking<Error<'a> = Infallible>
+            + LockImmediate<Guard<'a> = <L as LockBlocking>::Guard<'a>>,
+        F: FnOnce(),
+    {
+        match self.try_lock_immediate() {
+            Ok(guard) => guard,
+            Err(safe) => {
+                on_contention();
+                safe.lock_blocking()
+            }
+        }
     }
 
-    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts.
+    /// Attempts to acquire the lock in write mode, blocking for at most `timeout`.
     ///
-    /// This method is useful when retrying to acquire the lock under certain conditions.
-    pub fn unlock(self) -> SafeLock<L> {
+    /// If the lock could not be acquired in time, this method will return `Err(self)`.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_lock_blocking_for(
+        self,
+        timeout: Duration,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockTimed,
+    {
+        match LockTimed::lock_for(self.0, timeout) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode, blocking for at most `timeout`, and returns an error if unsuccessful.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_blocking_for_err(
+        self,
+        timeout: Duration,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, (Self, L::Error<'a>)>
+    where
+        L: LockTimed,
+    {
+        match LockTimed::lock_for(self.0, timeout) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err((self, err)),
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode, blocking until `deadline` at the latest.
+    ///
+    /// If the lock could not be acquired in time, this method will return `Err(self)`.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_lock_blocking_until(
+        self,
+        deadline: Instant,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockTimed,
+    {
+        match LockTimed::lock_until(self.0, deadline) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode, blocking until `deadline` at the latest, and returns an error if unsuccessful.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_blocking_until_err(
+        self,
+        deadline: Instant,
+    ) -> Result<SafeGuard<&'a L, L::Guard<'a>>, (Self, L::Error<'a>)>
+    where
+        L: LockTimed,
+    {
+        match LockTimed::lock_until(self.0, deadline) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err((self, err)),
+        }
+    }
+
+    /// Acquires the lock in shared mode and returns a read-only guard for the locked data.
+    ///
+    /// Other readers are not excluded while the guard is held, and the guard cannot be upgraded.
+    #[track_caller]
+    pub fn read_blocking(self) -> SafeReadGuard<&'a L, L::Guard<'a>>
+    where
+        L: LockShared<Error<'a> = Infallible>,
+    {
+        SafeReadGuard {
+            guard: LockShared::read_blocking(self.0).unwrap(),
+            lock: self,
+        }
+    }
+
+    /// Attempts to acquire the lock in shared mode and returns a read-only guard if successful.
+    ///
+    /// If the lock could not be acquired, this method will return `Err(self)`.
+    #[track_caller]
+    pub fn try_read_blocking(self) -> Result<SafeReadGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockShared,
+    {
+        match LockShared::read_blocking(self.0) {
+            Ok(guard) => Ok(SafeReadGuard { lock: self, guard }),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Attempts to acquire the lock in shared mode and returns an error if it fails.
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_read_blocking_err(
+        self,
+    ) -> Result<SafeReadGuard<&'a L, L::Guard<'a>>, (Self, L::Error<'a>)>
+    where
+        L: LockShared,
+    {
+        match LockShared::read_blocking(self.0) {
+            Ok(guard) => Ok(SafeReadGuard { lock: self, guard }),
+            Err(err) => Err((self, err)),
+        }
+    }
+
+    /// Attempts to acquire the lock in shared mode without blocking and returns a read-only guard if successful.
+    ///
+    /// If the lock is held exclusively, this method will return `Err(self)` without blocking.
+    #[track_caller]
+    pub fn try_read_immediate(self) -> Result<SafeReadGuard<&'a L, L::Guard<'a>>, Self>
+    where
+        L: LockShared,
+    {
+        match LockShared::read_immediate(self.0) {
+            Ok(guard) => Ok(SafeReadGuard { lock: self, guard }),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Attempts to acquire the lock in shared mode immediately and returns an error if unsuccessful.
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn try_read_immediate_err(
+        self,
+    ) -> Result<SafeReadGuard<&'a L, L::Guard<'a>>, (Self, L::ImmediateError<'a>)>
+    where
+        L: LockShared,
+    {
+        match LockShared::read_immediate(self.0) {
+            Ok(guard) => Ok(SafeReadGuard { lock: self, guard }),
+            Err(err) => Err((self, err)),
+        }
+    }
+
+    /// Acquires the lock in upgradable read mode and returns a guard for the locked data.
+    ///
+    /// Other readers are not excluded while the guard is held. The guard allows read-only access
+    /// to the data until it is explicitly upgraded, which atomically converts it into a write guard.
+    #[track_caller]
+    pub fn lock_upgradable(self) -> SafeUpgradableGuard<&'a L, L::Guard<'a>>
+    where
+        L: LockUpgradable<Error<'a> = Infallible>,
+    {
+        SafeUpgradableGuard {
+            guard: LockUpgradable::lock_upgradable(self.0).unwrap(),
+            lock: self,
+        }
+    }
+
+    /// Asynchronously acquires the lock in write mode and returns a guard for the locked data.
+    ///
+    /// The lock is acquired in **write mode**, and the returned guard allows read-only access to the data.
+    /// Mutation is not possible until explicitly upgrading the guard. The acquisition can be
+    /// cancelled while recovering the handle, see [`LockFuture`](future::LockFuture).
+    pub fn lock_async(
+        self,
+    ) -> future::LockFuture<&'a L, impl Future<Output = L::Guard<'a>> + use<'a, L>>
+    where
+        L: LockAsync<Error<'a> = Infallible>,
+    {
+        let lock = self.0;
+        future::LockFuture::new(
+            self,
+            async move { LockAsync::lock_async(lock).await.unwrap() },
+        )
+    }
+}
+
+impl<L, G> SafeGuard<L, G> {
+    pub(crate) const fn new(lock: SafeLock<L>, guard: G) -> Self {
+        SafeGuard {
+            lock,
+            guard,
+            #[cfg(feature = "debug-checks")]
+            inspected: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a guard over `guard` that inherits the inspection state of `self`.
+    fn with_guard<H>(lock: SafeLock<L>, guard: H, inspected: bool) -> SafeGuard<L, H> {
+        let safe = SafeGuard::new(lock, guard);
+        if inspected {
+            safe.mark_inspected();
+        }
+        safe
+    }
+
+    /// Records that the data has been inspected. This is a no-op without the `debug-checks` feature.
+    fn mark_inspected(&self) {
+        #[cfg(feature = "debug-checks")]
+        self.inspected.store(true, Ordering::Relaxed);
+    }
+
+    /// Records that the data must be inspected again. This is a no-op without the `debug-checks` feature.
+    #[cfg_attr(not(feature = "parking_lot"), allow(dead_code))]
+    fn reset_inspected(&self) {
+        #[cfg(feature = "debug-checks")]
+        self.inspected.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns whether the data has been inspected. Always `true` without the `debug-checks` feature.
+    fn is_inspected(&self) -> bool {
+        #[cfg(feature = "debug-checks")]
+        return self.inspected.load(Ordering::Relaxed);
+        #[cfg(not(feature = "debug-checks"))]
+        true
+    }
+
+    /// Panics if the guard is about to be upgraded without its data having ever been inspected.
+    #[track_caller]
+    fn assert_inspected(&self) {
+        assert!(
+            self.is_inspected(),
+            "`SafeGuard` upgraded without inspecting the locked data first"
+        );
+    }
+
+    /// Upgrades the `SafeGuard` to the underlying guard, allowing mutation of the locked data.
+    ///
+    /// Even though the underlying lock was acquired in **write mode**, mutation of the data is only
+    /// possible after explicitly upgrading the guard. This ensures safety in concurrent code.
+    ///
+    /// With the `debug-checks` feature, this panics if the data was never inspected through the guard.
+    #[track_caller]
+    pub fn upgrade(self) -> G {
+        self.assert_inspected();
+        self.guard
+    }
+
+    /// Returns a reference to the underlying guard.
+    ///
+    /// The guard only gives read-only access to the data, so this counts as inspecting it.
+    pub fn as_guard(&self) -> &G {
+        self.mark_inspected();
+        &self.guard
+    }
+
+    /// Temporarily upgrades the guard to run the mutation `f`, then gives the guard back.
+    ///
+    /// The lock stays held in **write mode** for the whole call, and the returned guard only allows
+    /// read-only access again, so subsequent inspection keeps going through the safe wrapper.
+    pub fn with_upgraded<F, R, T>(mut self, f: F) -> (Self, R)
+    where
+        G: DerefMut<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&mut T) -> R,
+    {
+        self.assert_inspected();
+        let res = f(&mut self.guard);
+        (self, res)
+    }
+
+    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts.
+    ///
+    /// This method is useful when retrying to acquire the lock under certain conditions.
+    pub fn unlock(self) -> SafeLock<L> {
         self.lock
     }
 
+    /// Keeps the guard if `pred` holds for the locked data, or releases the lock otherwise.
+    ///
+    /// This is the check-or-unlock branch of retry loops in a single call: on failure, the returned
+    /// [`SafeLock`] can be used for the next attempt. Evaluating `pred` counts as inspecting the data.
+    pub fn filter<F, T>(self, pred: F) -> Result<Self, SafeLock<L>>
+    where
+        G: Deref<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&T) -> bool,
+    {
+        if pred(&*self) {
+            Ok(self)
+        } else {
+            Err(self.unlock())
+        }
+    }
+
+    /// Calls `f` with a reference to the locked data, and returns the guard.
+    ///
+    /// This is meant for side-effect-only reads such as logging, and counts as inspecting the data.
+    pub fn inspect<F, T>(self, f: F) -> Self
+    where
+        G: Deref<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&T),
+    {
+        f(&*self);
+        self
+    }
+
+    /// Applies the fallible projection `f` to the guard, releasing the lock if it fails.
+    ///
+    /// `f` typically narrows the guard with one of the `try_map` methods, handing the original
+    /// guard back on failure. Like [`filter`](Self::filter), the [`SafeLock`] returned on failure
+    /// can be used for the next attempt, so both chain together.
+    pub fn and_then<F, R>(self, f: F) -> Result<R, SafeLock<L>>
+    where
+        F: FnOnce(Self) -> Result<R, Self>,
+    {
+        f(self).map_err(Self::unlock)
+    }
+
     /// Maps the guarded value to a different type, returning a new guard for the mapped data.
     ///
     /// The function `f` is applied to the underlying guard, transforming it into a new guard
@@ -165,10 +1175,8 @@ impl<L, G> SafeGuard<L, G> {
     where
         F: FnOnce(G) -> H,
     {
-        SafeGuard {
-            lock: self.lock,
-            guard: f(self.guard),
-        }
+        let inspected = self.is_inspected();
+        Self::with_guard(self.lock, f(self.guard), inspected)
     }
 
     /// Attempts to map the guarded value to a different type, returning an error if the mapping fails.
@@ -176,15 +1184,10 @@ impl<L, G> SafeGuard<L, G> {
     where
         F: FnOnce(G) -> Result<H, G>,
     {
+        let inspected = self.is_inspected();
         match f(self.guard) {
-            Ok(guard) => Ok(SafeGuard {
-                lock: self.lock,
-                guard,
-            }),
-            Err(guard) => Err(SafeGuard {
-                lock: self.lock,
-                guard,
-            }),
+            Ok(guard) => Ok(Self::with_guard(self.lock, guard, inspected)),
+            Err(guard) => Err(Self::with_guard(self.lock, guard, inspected)),
         }
     }
 
@@ -193,32 +1196,751 @@ impl<L, G> SafeGuard<L, G> {
     where
         F: FnOnce(G) -> Result<H, (G, E)>,
     {
+        let inspected = self.is_inspected();
         match f(self.guard) {
-            Ok(guard) => Ok(SafeGuard {
-                lock: self.lock,
-                guard,
-            }),
-            Err((guard, err)) => Err((
-                SafeGuard {
-                    lock: self.lock,
-                    guard,
-                },
-                err,
-            )),
+            Ok(guard) => Ok(Self::with_guard(self.lock, guard, inspected)),
+            Err((guard, err)) => Err((Self::with_guard(self.lock, guard, inspected), err)),
         }
     }
 }
 
+impl<L, G, T> SafeGuard<L, G>
+where
+    G: DerefMut<Target = Option<T>>,
+{
+    /// Takes the value out of the locked slot, leaving `None` in its place.
+    ///
+    /// The slot is checked through the read-only guard, which is only upgraded if it holds a value.
+    /// Returns the upgraded guard along with the value taken, or the read-only guard back if the
+    /// slot was empty.
+    #[track_caller]
+    pub fn take(self) -> Result<(G, T), Self> {
+        if self.is_none() {
+            return Err(self);
+        }
+        let mut guard = self.upgrade();
+        let value = guard.take().unwrap();
+        Ok((guard, value))
+    }
+
+    /// Upgrades the guard to put `value` in the locked slot.
+    ///
+    /// Returns the upgraded guard along with the previous value, if any. Since the slot is always
+    /// written, this is an [`upgrade`](Self::upgrade), and with the `debug-checks` feature it panics
+    /// if the slot was never inspected.
+    #[track_caller]
+    pub fn replace(self, value: T) -> (G, Option<T>) {
+        let mut guard = self.upgrade();
+        let previous = guard.replace(value);
+        (guard, previous)
+    }
+
+    /// Inserts the result of `init` in the locked slot if it is empty.
+    ///
+    /// The slot is checked through the read-only guard, which is only upgraded if it is empty.
+    #[track_caller]
+    pub fn get_or_insert_with<F>(self, init: F) -> GetOrInsert<Self, G>
+    where
+        F: FnOnce() -> T,
+    {
+        if self.is_some() {
+            return GetOrInsert::Present(self);
+        }
+        let mut guard = self.upgrade();
+        *guard = Some(init());
+        GetOrInsert::Inserted(guard)
+    }
+}
+
+impl<'a, L: ?Sized, G> SafeGuard<&'a L, G> {
+    /// Releases the lock, runs `f`, then re-acquires the lock in write mode, returning the new guard.
+    ///
+    /// This is useful to perform slow operations such as I/O without holding the lock. The returned
+    /// guard is in its read-only state, and since the data may have changed while the lock was
+    /// released, it must be inspected again before upgrading.
+    pub fn unlocked<F, R>(self, f: F) -> (Self, R)
+    where
+        L: LockBlocking<Error<'a> = Infallible, Guard<'a> = G>,
+        F: FnOnce() -> R,
+    {
+        let safe = self.unlock();
+        let res = f();
+        (safe.lock_blocking(), res)
+    }
+
+    /// Temporarily upgrades the guard to run the mutation `f`, then downgrades it into a [`SafeReadGuard`].
+    ///
+    /// The lock is never released in between, so the returned guard observes exactly the state left
+    /// by `f` while letting other readers in.
+    pub fn with_upgraded_downgrade<F, R, T>(
+        self,
+        f: F,
+    ) -> (SafeReadGuard<&'a L, L::DowngradedGuard<'a>>, R)
+    where
+        L: LockDowngrade<Guard<'a> = G>,
+        G: DerefMut<Target = T>,
+        T: ?Sized,
+        F: FnOnce(&mut T) -> R,
+    {
+        self.assert_inspected();
+        let mut guard = self.guard;
+        let res = f(&mut guard);
+        (SafeReadGuard::downgrade(guard), res)
+    }
+}
+
+impl<L1, G1> SafeGuard<L1, G1> {
+    /// Combines this guard with a guard of another lock into a single guard holding both locks.
+    ///
+    /// The combined guard inspects both values at once through [`get`](SafeGuard::get), upgrades
+    /// into both underlying guards, and unlocks into a [`SafeLock`] over both locks, which can be
+    /// split back with [`SafeLock::unzip`].
+    pub fn zip<L2, G2>(self, other: SafeGuard<L2, G2>) -> SafeGuard<(L1, L2), (G1, G2)> {
+        let inspected = self.is_inspected() && other.is_inspected();
+        let zipped = SafeGuard::new(
+            SafeLock((self.lock.0, other.lock.0)),
+            (self.guard, other.guard),
+        );
+        if inspected {
+            zipped.mark_inspected();
+        }
+        zipped
+    }
+}
+
+impl<L1, L2, G1, G2> SafeGuard<(L1, L2), (G1, G2)> {
+    /// Provides read-only access to both underlying values.
+    ///
+    /// Mutation is only possible after calling [`upgrade`](Self::upgrade).
+    pub fn get<T1, T2>(&self) -> (&T1, &T2)
+    where
+        G1: Deref<Target = T1>,
+        G2: Deref<Target = T2>,
+        T1: ?Sized,
+        T2: ?Sized,
+    {
+        self.mark_inspected();
+        (&*self.guard.0, &*self.guard.1)
+    }
+
+    /// Splits the combined guard back into the guards of both locks.
+    pub fn unzip(self) -> (SafeGuard<L1, G1>, SafeGuard<L2, G2>) {
+        let inspected = self.is_inspected();
+        let SafeLock((l1, l2)) = self.lock;
+        let (g1, g2) = self.guard;
+        let first = SafeGuard::new(SafeLock(l1), g1);
+        let second = SafeGuard::new(SafeLock(l2), g2);
+        if inspected {
+            first.mark_inspected();
+            second.mark_inspected();
+        }
+        (first, second)
+    }
+}
+
+impl<L1, L2> SafeLock<(L1, L2)> {
+    /// Splits a [`SafeLock`] over two locks, as returned by unlocking a [`zip`](SafeGuard::zip)ped
+    /// guard, into a [`SafeLock`] for each of them.
+    pub fn unzip(self) -> (SafeLock<L1>, SafeLock<L2>) {
+        let (l1, l2) = self.0;
+        (SafeLock(l1), SafeLock(l2))
+    }
+}
+
 impl<L, G, T> Deref for SafeGuard<L, G>
 where
     G: Deref<Target = T>,
+    T: ?Sized,
 {
     type Target = T;
 
     /// Provides read-only access to the underlying value.
     ///
     /// Mutation is only possible after calling [`upgrade`](Self::upgrade).
+    fn deref(&self) -> &Self::Target {
+        self.mark_inspected();
+        Deref::deref(&self.guard)
+    }
+}
+
+impl<L, G, T> AsRef<T> for SafeGuard<L, G>
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+{
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<L, G> fmt::Display for SafeGuard<L, G>
+where
+    G: Deref<Target: fmt::Display>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<L, G, U> PartialEq<U> for SafeGuard<L, G>
+where
+    G: Deref<Target: PartialEq<U>>,
+    U: ?Sized,
+{
+    fn eq(&self, other: &U) -> bool {
+        **self == *other
+    }
+}
+
+impl<L, G, U> PartialOrd<U> for SafeGuard<L, G>
+where
+    G: Deref<Target: PartialOrd<U>>,
+    U: ?Sized,
+{
+    fn partial_cmp(&self, other: &U) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<L, G> Hash for SafeGuard<L, G>
+where
+    G: Deref<Target: Hash>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+/// Indexes into the locked data, which counts as inspecting it.
+impl<L, G, I> Index<I> for SafeGuard<L, G>
+where
+    G: Deref<Target: Index<I>>,
+{
+    type Output = <G::Target as Index<I>>::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &(**self)[index]
+    }
+}
+
+impl<'a, L: ?Sized + LockUpgradable> SafeUpgradableGuard<&'a L, L::Guard<'a>> {
+    /// Atomically upgrades the guard into a write guard, allowing mutation of the locked data.
+    ///
+    /// Readers that acquired the lock alongside this guard are waited for, but no writer can
+    /// acquire the lock in between, so the data inspected so far is still valid after the upgrade.
+    pub fn upgrade(self) -> L::UpgradedGuard<'a> {
+        L::upgrade(self.guard)
+    }
+}
+
+impl<L, G> SafeUpgradableGuard<L, G> {
+    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts.
+    pub fn unlock(self) -> SafeLock<L> {
+        self.lock
+    }
+}
+
+impl<S, G, T> Deref for GetOrInsert<S, G>
+where
+    S: Deref<Target = T>,
+    G: Deref<Target = T>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Present(guard) => guard,
+            Self::Inserted(guard) => guard,
+        }
+    }
+}
+
+impl<L, G, T> Deref for SafeUpgradableGuard<L, G>
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+{
+    type Target = T;
+
+    /// Provides read-only access to the underlying value.
+    ///
+    /// Mutation is only possible after calling [`upgrade`](SafeUpgradableGuard::upgrade).
+    fn deref(&self) -> &Self::Target {
+        Deref::deref(&self.guard)
+    }
+}
+
+impl<'a, L: ?Sized + LockDowngrade> SafeReadGuard<&'a L, L::DowngradedGuard<'a>> {
+    /// Downgrades a write guard back into a read-only [`SafeReadGuard`] without releasing the lock.
+    ///
+    /// This is useful to verify post-conditions after a mutation within the same critical section,
+    /// while giving up the ability to mutate further.
+    pub fn downgrade(guard: L::Guard<'a>) -> Self {
+        let (lock, guard) = L::downgrade(guard);
+        SafeReadGuard {
+            lock: SafeLock(lock),
+            guard,
+        }
+    }
+}
+
+impl<L, G> SafeReadGuard<L, G> {
+    /// Releases the lock and returns the original [`SafeLock`], allowing further locking attempts.
+    pub fn unlock(self) -> SafeLock<L> {
+        self.lock
+    }
+}
+
+impl<L, G, T> Deref for SafeReadGuard<L, G>
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+{
+    type Target = T;
+
+    /// Provides read-only access to the underlying value.
     fn deref(&self) -> &Self::Target {
         Deref::deref(&self.guard)
     }
 }
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use ::parking_lot::{Mutex, RwLock};
+    use ::std::{thread, time::Duration};
+
+    use super::*;
+
+    crate::macros::assert_not_impl!(SafeLock<&'static Mutex<()>>: Clone);
+    crate::macros::assert_not_impl!(SafeLock<&'static Mutex<()>>: Copy);
+    crate::macros::assert_not_impl!(SafeLock<Mutex<()>>: Clone);
+
+    #[test]
+    fn option_helpers_only_upgrade_to_mutate() {
+        let lock = Mutex::new(None);
+        let guard = SafeLock::new(&lock).lock_blocking().take().unwrap_err();
+        let guard = match guard.get_or_insert_with(|| 1) {
+            GetOrInsert::Inserted(guard) => guard,
+            GetOrInsert::Present(_) => panic!("the slot was empty"),
+        };
+        assert_eq!(*guard, Some(1));
+        drop(guard);
+
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let guard = match guard.get_or_insert_with(|| unreachable!()) {
+            GetOrInsert::Present(guard) => guard,
+            GetOrInsert::Inserted(_) => panic!("the slot was filled"),
+        };
+        let (guard, previous) = guard.replace(2);
+        assert_eq!((*guard, previous), (Some(2), Some(1)));
+        drop(guard);
+
+        let (guard, taken) = SafeLock::new(&lock).lock_blocking().take().unwrap();
+        assert_eq!((*guard, taken), (None, 2));
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic = "without inspecting the locked data"]
+    fn replace_requires_inspecting_the_slot() {
+        let lock = Mutex::new(Some(0));
+        drop(SafeLock::new(&lock).lock_blocking().replace(1));
+    }
+
+    #[test]
+    fn owned_locks_are_acquired_through_a_borrow() {
+        let safe = SafeLock::new(RwLock::new(1));
+        let guard = safe.lock_blocking_ref();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*safe.by_ref().read_blocking(), 2);
+        assert_eq!(safe.into_inner().into_inner(), 2);
+    }
+
+    #[test]
+    fn owned_locks_can_live_in_a_static() {
+        static COUNTER: SafeLock<Mutex<u32>> = SafeLock::new(Mutex::new(0));
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let guard = COUNTER.lock_blocking_ref();
+                    let value = *guard;
+                    *guard.upgrade() = value + 1;
+                });
+            }
+        });
+        assert_eq!(*COUNTER.lock_blocking_ref(), 4);
+    }
+
+    #[test]
+    fn timed_acquisitions_give_up_while_the_lock_is_held() {
+        let lock = Mutex::new(0);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let safe = SafeLock::new(&lock)
+            .try_lock_blocking_for(Duration::from_millis(10))
+            .unwrap_err();
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let safe = safe.try_lock_blocking_until(deadline).unwrap_err();
+        drop(guard);
+        assert!(
+            safe.try_lock_blocking_for(Duration::from_millis(10))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn upgradable_guards_coexist_with_readers() {
+        let lock = RwLock::new(1);
+        let reader = SafeLock::new(&lock).read_blocking();
+        let guard = SafeLock::new(&lock).lock_upgradable();
+        assert_eq!((*reader, *guard), (1, 1));
+        let safe = SafeLock::new(&lock).try_read_immediate().unwrap().unlock();
+        drop(reader);
+        let mut write = guard.upgrade();
+        *write += 1;
+        assert!(safe.try_read_immediate().is_err());
+        drop(write);
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn read_guards_are_shared_and_exclude_writers() {
+        let lock = RwLock::new(1);
+        let first = SafeLock::new(&lock).read_blocking();
+        let second = SafeLock::new(&lock).try_read_immediate().unwrap();
+        assert_eq!(*first + *second, 2);
+        let safe = SafeLock::new(&lock).try_lock_immediate().unwrap_err();
+        drop((first, second));
+        let guard = safe.lock_blocking();
+        assert!(SafeLock::new(&lock).try_read_immediate().is_err());
+        drop(guard);
+    }
+
+    #[test]
+    fn downgraded_guards_let_readers_in() {
+        let lock = RwLock::new(1);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let value = *guard;
+        let mut write = guard.upgrade();
+        *write = value + 1;
+        let read = SafeReadGuard::<&RwLock<_>, _>::downgrade(write);
+        assert_eq!(*SafeLock::new(&lock).try_read_immediate().unwrap(), 2);
+        let safe = read.unlock();
+        assert!(safe.try_lock_immediate().is_ok());
+    }
+
+    #[test]
+    fn with_upgraded_keeps_the_safe_guard() {
+        let lock = RwLock::new(1);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let value = *guard;
+        let (guard, previous) = guard.with_upgraded(|data| ::core::mem::replace(data, value + 1));
+        assert_eq!((previous, *guard), (1, 2));
+        let (read, ()) = guard.with_upgraded_downgrade(|data| *data += 1);
+        assert_eq!(*read, 3);
+        assert_eq!(*SafeLock::new(&lock).try_read_immediate().unwrap(), 3);
+    }
+
+    #[test]
+    fn update_if_only_mutates_when_the_check_holds() {
+        let safe = SafeLock::new(Mutex::new(1));
+        assert!(!safe.update_if(|value| *value > 1, |value| *value = 0));
+        assert!(safe.update_if(|value| *value == 1, |value| *value += 1));
+        assert_eq!(
+            safe.try_update(|value| *value == 2, |value| *value * 10),
+            Some(20)
+        );
+        assert_eq!(
+            safe.try_update(|value| *value == 0, |_| unreachable!()),
+            None::<()>
+        );
+        assert_eq!(*safe.lock_blocking_ref(), 2);
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn upgrading_a_never_inspected_guard_panics() {
+        let lock = Mutex::new(0);
+        *SafeLock::new(&lock).lock_blocking().upgrade() = 1;
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn with_upgraded_on_a_never_inspected_guard_panics() {
+        let lock = Mutex::new(0);
+        drop(
+            SafeLock::new(&lock)
+                .lock_blocking()
+                .with_upgraded(|value| *value = 1),
+        );
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    fn inspections_before_mapping_are_kept() {
+        let lock = Mutex::new(0);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        *guard.map_guard(|guard| guard).upgrade() = 1;
+        assert_eq!(*lock.lock(), 1);
+    }
+
+    #[test]
+    fn lock_immediate_or_blocking_reports_contention() {
+        let lock = Mutex::new(0);
+        let guard = SafeLock::new(&lock).lock_immediate_or_blocking(|| unreachable!());
+        let contended = AtomicBool::new(false);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&lock)
+                    .lock_immediate_or_blocking(|| contended.store(true, Ordering::Relaxed));
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+            while !contended.load(Ordering::Relaxed) {
+                thread::yield_now();
+            }
+            drop(guard);
+        });
+        assert_eq!(*lock.lock(), 1);
+    }
+
+    #[test]
+    fn zipped_guards_are_upgraded_together() {
+        let (from, to) = (Mutex::new(10), Mutex::new(0));
+        let zipped = SafeLock::new(&from)
+            .lock_blocking()
+            .zip(SafeLock::new(&to).lock_blocking());
+        let (&balance, _) = zipped.get();
+        let (mut from_guard, mut to_guard) = zipped.upgrade();
+        *from_guard -= balance;
+        *to_guard += balance;
+        drop((from_guard, to_guard));
+        assert_eq!((*from.lock(), *to.lock()), (0, 10));
+    }
+
+    #[test]
+    fn unzipped_guards_release_their_own_lock() {
+        let (first, second) = (Mutex::new(1), Mutex::new(2));
+        let zipped = SafeLock::new(&first)
+            .lock_blocking()
+            .zip(SafeLock::new(&second).lock_blocking());
+        let (first_guard, second_guard) = zipped.unzip();
+        let safe = first_guard.unlock();
+        assert!(first.try_lock().is_some());
+        assert!(second.try_lock().is_none());
+        assert_eq!(*second_guard, 2);
+        drop(second_guard);
+        assert_eq!(*safe.lock_blocking(), 1);
+    }
+
+    #[test]
+    fn locks_convert_into_safe_locks() {
+        let lock = Mutex::new(1);
+        let safe: SafeLock<&Mutex<u32>> = (&lock).into();
+        assert_eq!(*safe.lock_blocking(), 1);
+
+        let owned = SafeLock::<RwLock<Vec<u32>>>::default();
+        assert!(owned.read_blocking_ref().is_empty());
+        let owned = crate::parking_lot::OwnedSafeMutex::from(2);
+        assert_eq!(*owned.lock_blocking_ref(), 2);
+    }
+
+    #[test]
+    fn accessors_reach_the_wrapped_lock() {
+        let mut safe = crate::parking_lot::OwnedSafeRwLock::new(RwLock::new(1));
+        assert!(!safe.inner().is_locked());
+        *safe.get_mut() += 1;
+        let guard = safe.read_blocking_ref();
+        assert!(safe.inner().is_locked());
+        drop(guard);
+        assert_eq!(safe.into_inner().into_inner(), 2);
+    }
+
+    #[test]
+    fn unlocked_releases_the_lock_while_running() {
+        let lock = Mutex::new(0);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let (guard, written) = guard.unlocked(|| {
+            let guard = SafeLock::new(&lock).try_lock_immediate().unwrap();
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+            value + 1
+        });
+        assert_eq!(*guard, written);
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.lock(), 2);
+    }
+
+    #[test]
+    fn modify_optimistic_retries_when_the_data_changed() {
+        let safe = SafeLock::new(RwLock::new(1));
+        let computations = AtomicUsize::new(0);
+        safe.modify_optimistic(
+            |value| *value,
+            |snapshot| {
+                if computations.fetch_add(1, Ordering::Relaxed) == 0 {
+                    *safe.inner().write() += 1;
+                }
+                (snapshot, snapshot * 10)
+            },
+            |value, (snapshot, computed)| {
+                let valid = *value == snapshot;
+                if valid {
+                    *value = computed;
+                }
+                valid
+            },
+        );
+        assert_eq!(computations.load(Ordering::Relaxed), 2);
+        assert_eq!(*safe.read_blocking_ref(), 20);
+    }
+
+    #[test]
+    fn safe_lock_macro_retries_until_the_check_holds() {
+        let lock = Mutex::new(0);
+        let mut retries = 0;
+        let previous = crate::safe_lock!(
+            SafeLock::new(&lock),
+            |value| *value >= 3 => |value| ::core::mem::replace(value, 0),
+            else {
+                retries += 1;
+                *lock.lock() += 1;
+            },
+        );
+        assert_eq!((previous, retries), (3, 3));
+        assert_eq!(*lock.lock(), 0);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn safe_fields_generate_accessors() {
+        #[crate::safe_fields]
+        struct Counters {
+            #[safe]
+            hits: Mutex<u32>,
+            misses: u32,
+        }
+
+        let counters = Counters {
+            hits: SafeLock::new(Mutex::new(1)),
+            misses: 0,
+        };
+        let guard = counters.hits();
+        let hits = *guard;
+        *guard.upgrade() = hits + 1;
+        assert_eq!((*counters.hits(), counters.misses), (2, 0));
+    }
+
+    #[test]
+    fn probes_report_the_lock_state() {
+        let safe = SafeLock::new(RwLock::new(0));
+        assert!(!safe.is_locked());
+        let read = safe.read_blocking_ref();
+        assert!(safe.is_locked() && !safe.is_locked_exclusive());
+        drop(read);
+        let guard = safe.lock_blocking_ref();
+        assert!(safe.is_locked_exclusive());
+        drop(guard);
+
+        let safe = SafeLock::new(::std::sync::Arc::new(Mutex::new(0)));
+        let guard = safe.inner().lock();
+        assert!(safe.is_locked() && safe.is_locked_exclusive());
+        drop(guard);
+        assert!(!safe.is_locked());
+    }
+
+    #[test]
+    fn filter_keeps_the_guard_or_releases_the_lock() {
+        let lock = Mutex::new(1);
+        let safe = SafeLock::new(&lock)
+            .lock_blocking()
+            .filter(|value| *value > 1)
+            .unwrap_err();
+        assert!(lock.try_lock().is_some());
+        let guard = safe.lock_blocking().filter(|value| *value == 1).unwrap();
+        *guard.upgrade() += 1;
+        assert_eq!(*lock.lock(), 2);
+    }
+
+    #[test]
+    fn inspect_and_and_then_chain_on_the_guard() {
+        let lock = Mutex::new(vec![1, 2]);
+        let mut seen = 0;
+        let guard = SafeLock::new(&lock)
+            .lock_blocking()
+            .inspect(|values| seen = values.len());
+        assert_eq!(seen, 2);
+        guard.upgrade().push(3);
+
+        let last = SafeLock::new(&lock)
+            .lock_blocking()
+            .and_then(|guard| match guard.last() {
+                Some(&last) => Ok(last),
+                None => Err(guard),
+            });
+        assert_eq!(last.ok(), Some(3));
+
+        lock.lock().clear();
+        let safe = SafeLock::new(&lock)
+            .lock_blocking()
+            .and_then(|guard| guard.first().copied().ok_or(guard))
+            .unwrap_err();
+        assert!(safe.try_lock_immediate().is_ok());
+    }
+
+    #[test]
+    fn peeks_release_the_lock_right_away() {
+        let safe = SafeLock::new(Mutex::new(vec![1, 2]));
+        assert_eq!(safe.peek(Vec::len), 2);
+        assert!(!safe.is_locked());
+        assert_eq!(safe.try_peek(|values| values[1]), Some(2));
+
+        let guard = safe.inner().lock();
+        assert_eq!(safe.try_peek(Vec::len), None);
+        drop(guard);
+    }
+
+    #[test]
+    fn guards_compare_and_format_like_their_target() {
+        use ::std::collections::hash_map::DefaultHasher;
+
+        fn hash(value: impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let lock = Mutex::new(2);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert!(guard == 2 && guard != 3);
+        assert!(guard < 3 && guard > 1);
+        assert_eq!(guard.to_string(), "2");
+        assert_eq!(hash(&guard), hash(2));
+    }
+
+    #[test]
+    fn guards_index_into_their_target() {
+        let lock = RwLock::new(vec![1, 2, 3]);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!((guard[0], &guard[1..]), (1, &[2, 3][..]));
+    }
+
+    #[test]
+    fn borrowed_acquisitions_leave_the_safe_lock_usable() {
+        let safe = SafeLock::new(RwLock::new(1));
+        let (first, second) = (safe.read_blocking_ref(), safe.read_blocking_ref());
+        assert_eq!(*first + *second, 2);
+        assert!(safe.try_lock_immediate_ref().is_none());
+        drop((first, second));
+
+        let guard = safe.try_lock_immediate_ref().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*safe.lock_blocking_ref(), 2);
+    }
+}
@@ -0,0 +1,209 @@
+use ::core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    SafeGuard, SafeLock, SafeReadGuard, SafeUpgradableGuard,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Local`] lock, whose guards cannot be sent to other threads.
+pub type LocalSafeLock<L> = SafeLock<Local<L>>;
+
+/// A lock decorator making the guards of the wrapped lock `L` `!Send`.
+///
+/// A future holding a `!Send` guard across an `.await` is `!Send` itself, so it is rejected at
+/// compile time by multi-threaded executors such as `tokio::spawn`. Wrapping a lock whose guards
+/// can be sent, such as `parking_lot` with its `send_guard` feature or `dashmap`, gives back
+/// [`HeldLocally`] guards instead, so they cannot be held across an `.await` by mistake.
+///
+/// A guard can still be turned back into a sendable one explicitly, with
+/// [`into_sendable`](SafeGuard::into_sendable).
+#[derive(Debug, Default)]
+pub struct Local<L>(L);
+
+/// A guard returned by a [`Local`] lock, which is `!Send`.
+#[derive(Debug)]
+pub struct HeldLocally<G> {
+    pub(crate) guard: G,
+    _not_send: PhantomData<*const ()>,
+}
+
+// SAFETY: the marker only opts out of `Send`, sharing the guard is as safe as sharing `G`.
+unsafe impl<G: Sync> Sync for HeldLocally<G> {}
+
+impl<L> Local<L> {
+    /// Wraps `lock` to make its guards `!Send`.
+    pub const fn new(lock: L) -> Self {
+        Self(lock)
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.0
+    }
+}
+
+impl<G> HeldLocally<G> {
+    pub(crate) const fn new(guard: G) -> Self {
+        Self {
+            guard,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns the guard of the wrapped lock, which is `Send` if the backend allows it.
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+}
+
+impl<L, G> SafeGuard<L, HeldLocally<G>> {
+    /// Opts out of the `!Send` marker, returning the guard of the wrapped lock.
+    ///
+    /// The inspection state of the guard is kept.
+    pub fn into_sendable(self) -> SafeGuard<L, G> {
+        self.map_guard(HeldLocally::into_inner)
+    }
+}
+
+impl<L, G> SafeUpgradableGuard<L, HeldLocally<G>> {
+    /// Opts out of the `!Send` marker, returning the guard of the wrapped lock.
+    pub fn into_sendable(self) -> SafeUpgradableGuard<L, G> {
+        let SafeUpgradableGuard { lock, guard } = self;
+        SafeUpgradableGuard {
+            lock,
+            guard: guard.into_inner(),
+        }
+    }
+}
+
+impl<L, G> SafeReadGuard<L, HeldLocally<G>> {
+    /// Opts out of the `!Send` marker, returning the guard of the wrapped lock.
+    pub fn into_sendable(self) -> SafeReadGuard<L, G> {
+        let SafeReadGuard { lock, guard } = self;
+        SafeReadGuard {
+            lock,
+            guard: guard.into_inner(),
+        }
+    }
+}
+
+impl<G: Deref> Deref for HeldLocally<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for HeldLocally<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Local<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = HeldLocally<G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.0
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<HeldLocally<G>, E> {
+        res.map(HeldLocally::new)
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Local<L> => LockBlocking, LockImmediate, LockTimed, LockShared, LockAsync;
+    type Guard = HeldLocally<_>;
+}
+
+/// Without the `send_guard` feature, a `parking_lot` guard held across an `.await` is rejected by
+/// `Send` executors:
+///
+/// ```compile_fail,E0277
+/// use parking_lot::Mutex;
+/// use safe_lock::SafeLock;
+///
+/// fn spawn<F: Future + Send>(_: F) {}
+///
+/// let lock = Mutex::new(0);
+/// spawn(async {
+///     let guard = SafeLock::new(&lock).lock_blocking();
+///     std::future::ready(()).await;
+///     drop(guard);
+/// });
+/// ```
+#[cfg(all(doctest, feature = "parking_lot", not(feature = "send_guard")))]
+pub struct BlockingGuardsAcrossAwait;
+
+/// A [`Local`] guard of an async backend held across an `.await` is rejected as well:
+///
+/// ```compile_fail,E0277
+/// use safe_lock::{SafeLock, local::Local};
+/// use tokio::sync::RwLock;
+///
+/// fn spawn<F: Future + Send>(_: F) {}
+///
+/// let lock = Local::new(RwLock::new(0));
+/// spawn(async {
+///     let guard = SafeLock::new(&lock).lock_async().await;
+///     std::future::ready(()).await;
+///     drop(guard);
+/// });
+/// ```
+#[cfg(all(doctest, feature = "tokio"))]
+pub struct LocalGuardsAcrossAwait;
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::{Mutex, RwLock};
+
+    use super::*;
+    use crate::macros::assert_not_impl;
+
+    assert_not_impl!(HeldLocally<()>: Send);
+    assert_not_impl!(SafeGuard<&'static Local<Mutex<()>>, HeldLocally<()>>: Send);
+
+    #[test]
+    fn sendable_guards_keep_their_inspection_state() {
+        let lock = Local::new(RwLock::new(0));
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        *guard.into_sendable().upgrade() += 1;
+        assert_eq!(*lock.inner().read(), 1);
+    }
+
+    #[cfg(feature = "send_guard")]
+    #[test]
+    fn sendable_guards_can_be_sent() {
+        let lock = Local::new(Mutex::new(0));
+        let guard = SafeLock::new(&lock).lock_blocking().into_sendable();
+        ::std::thread::scope(|s| {
+            s.spawn(move || {
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+        });
+        assert_eq!(*lock.inner().lock(), 1);
+    }
+
+    #[test]
+    fn local_locks_hand_out_local_guards() {
+        let lock = Local::new(Mutex::new(0));
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        let mut guard = guard.upgrade();
+        *guard += 1;
+        drop(guard);
+        assert_eq!(*lock.inner().lock(), 1);
+    }
+}
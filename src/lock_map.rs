@@ -0,0 +1,159 @@
+use ::core::{borrow::Borrow, hash::Hash};
+use ::std::{collections::HashMap, sync::Arc};
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::{
+    SafeLock,
+    parking_lot::{SafeArcRwLock, SafeArcRwLockGuard},
+};
+
+/// The number of entries below which a [`SafeLockMap`] never collects unused locks on its own.
+const MIN_GC_THRESHOLD: usize = 16;
+
+/// A map of per-key [`RwLock`]s, created lazily and collected once unused.
+///
+/// Each key gets its own lock, handed out as a [`SafeArcRwLock`] whose guards do not borrow the
+/// map. An entry is unused once no handle, weak handle nor guard over its lock is alive anymore.
+/// Unused entries are collected by [`gc`](Self::gc), and automatically whenever the map doubles in
+/// size.
+#[derive(Debug)]
+pub struct SafeLockMap<K, T> {
+    inner: Mutex<Entries<K, T>>,
+}
+
+#[derive(Debug)]
+struct Entries<K, T> {
+    locks: HashMap<K, Arc<RwLock<T>>>,
+    gc_threshold: usize,
+}
+
+impl<K, T> SafeLockMap<K, T> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Entries {
+                locks: HashMap::new(),
+                gc_threshold: MIN_GC_THRESHOLD,
+            }),
+        }
+    }
+
+    /// Returns the number of entries, including unused ones not collected yet.
+    pub fn len(&self) -> usize {
+        self.inner.lock().locks.len()
+    }
+
+    /// Returns whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().locks.is_empty()
+    }
+
+    /// Removes the entries whose lock is not used anymore, returning how many were removed.
+    pub fn gc(&self) -> usize {
+        self.inner.lock().gc()
+    }
+}
+
+impl<K: Eq + Hash, T> SafeLockMap<K, T> {
+    /// Returns a handle to the lock of `key`, creating it with `init` if it does not exist.
+    pub fn get_or_insert_with<F>(&self, key: K, init: F) -> SafeArcRwLock<T>
+    where
+        F: FnOnce() -> T,
+    {
+        let mut entries = self.inner.lock();
+        if entries.locks.len() >= entries.gc_threshold {
+            entries.gc();
+            entries.gc_threshold = (entries.locks.len() * 2).max(MIN_GC_THRESHOLD);
+        }
+        let lock = entries
+            .locks
+            .entry(key)
+            .or_insert_with(|| Arc::new(RwLock::new(init())));
+        SafeLock(Arc::clone(lock))
+    }
+
+    /// Returns a handle to the lock of `key`, if it exists.
+    pub fn get<Q>(&self, key: &Q) -> Option<SafeArcRwLock<T>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.inner.lock().locks.get(key).cloned().map(SafeLock)
+    }
+
+    /// Acquires the lock of `key` in write mode, creating it with the default value if it does not
+    /// exist, and returns a guard for the locked data.
+    pub fn lock_blocking(&self, key: K) -> SafeArcRwLockGuard<T>
+    where
+        T: Default,
+    {
+        self.get_or_insert_with(key, T::default).lock_blocking()
+    }
+}
+
+impl<K, T> Entries<K, T> {
+    fn gc(&mut self) -> usize {
+        let len = self.locks.len();
+        // Strong handles are only created under the map lock, but a weak handle (see
+        // `SafeArcRwLock::downgrade`) can be upgraded without it. Entries with weak handles are
+        // therefore kept, since collecting them would hand out a second lock for the same key.
+        self.locks
+            .retain(|_, lock| Arc::strong_count(lock) > 1 || Arc::weak_count(lock) > 0);
+        len - self.locks.len()
+    }
+}
+
+impl<K, T> Default for SafeLockMap<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_entries_are_collected() {
+        let map = SafeLockMap::new();
+        let handle = map.get_or_insert_with("a", || 0);
+        map.get_or_insert_with("b", || 0);
+        assert_eq!(map.gc(), 1);
+        assert!(map.get("b").is_none());
+        drop(handle);
+        assert_eq!(map.gc(), 1);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn guards_keep_their_entry() {
+        let map = SafeLockMap::<_, u32>::new();
+        let guard = map.lock_blocking("a");
+        assert_eq!(map.gc(), 0);
+        drop(guard);
+        assert_eq!(map.gc(), 1);
+    }
+
+    #[test]
+    fn weak_handles_keep_their_entry() {
+        let map = SafeLockMap::new();
+        let weak = map.get_or_insert_with("a", || 0).downgrade();
+        assert_eq!(map.gc(), 0);
+
+        let guard = weak.try_lock_blocking().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*map.lock_blocking("a"), 1);
+        assert_eq!(map.gc(), 1);
+    }
+
+    #[test]
+    fn keys_share_a_single_lock() {
+        let map = SafeLockMap::new();
+        let a = map.get_or_insert_with("a", || 0);
+        let guard = map.lock_blocking("a");
+        assert!(a.try_lock_immediate().is_err());
+        drop(guard);
+    }
+}
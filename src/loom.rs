@@ -0,0 +1,142 @@
+use ::std::sync::{PoisonError, TryLockError};
+
+use loom::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{LockBlocking, LockImmediate, LockShared, SafeGuard, SafeLock, SafeReadGuard};
+
+/// A wrapper around [`RwLock`](RwLock) from `loom`, providing safe locking behavior in model-checked tests.
+pub type SafeRwLock<'a, T> = SafeLock<&'a RwLock<T>>;
+pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a RwLock<T>, RwLockWriteGuard<'a, T>>;
+pub type SafeRwLockReadGuard<'a, T> = SafeReadGuard<&'a RwLock<T>, RwLockReadGuard<'a, T>>;
+/// A wrapper around [`Mutex`](Mutex) from `loom`, providing safe locking behavior in model-checked tests.
+pub type SafeMutex<'a, T> = SafeLock<&'a Mutex<T>>;
+pub type SafeMutexGuard<'a, T> = SafeGuard<&'a Mutex<T>, MutexGuard<'a, T>>;
+
+impl<T> LockBlocking for RwLock<T> {
+    type Error<'a>
+        = PoisonError<RwLockWriteGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.write()
+    }
+}
+
+impl<T> LockImmediate for RwLock<T> {
+    type Error<'a>
+        = TryLockError<RwLockWriteGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_write()
+    }
+}
+
+impl<T> LockShared for RwLock<T> {
+    type Error<'a>
+        = PoisonError<RwLockReadGuard<'a, T>>
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = TryLockError<RwLockReadGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.read()
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.try_read()
+    }
+}
+
+impl<T> LockBlocking for Mutex<T> {
+    type Error<'a>
+        = PoisonError<MutexGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.lock()
+    }
+}
+
+impl<T> LockImmediate for Mutex<T> {
+    type Error<'a>
+        = TryLockError<MutexGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use loom::{sync::Arc, thread};
+
+    use super::*;
+
+    fn increment(lock: &Mutex<u32>) {
+        let Ok(guard) = SafeMutex::new(lock).try_lock_blocking() else {
+            panic!("the mutex is poisoned");
+        };
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    #[test]
+    fn concurrent_increments_are_not_lost() {
+        loom::model(|| {
+            let lock = Arc::new(Mutex::new(0));
+            let other = Arc::clone(&lock);
+            let handle = thread::spawn(move || increment(&other));
+            increment(&lock);
+            handle.join().unwrap();
+            assert_eq!(*lock.lock().unwrap(), 2);
+        });
+    }
+
+    #[test]
+    fn readers_never_observe_partial_writes() {
+        loom::model(|| {
+            let lock = Arc::new(RwLock::new((0, 0)));
+            let other = Arc::clone(&lock);
+            let handle = thread::spawn(move || {
+                let Ok(guard) = SafeRwLock::new(&*other).try_lock_blocking() else {
+                    panic!("the lock is poisoned");
+                };
+                let (a, b) = *guard;
+                *guard.upgrade() = (a + 1, b + 1);
+            });
+            if let Ok(guard) = SafeRwLock::new(&*lock).try_read_blocking() {
+                assert_eq!(guard.0, guard.1);
+            }
+            handle.join().unwrap();
+        });
+    }
+}
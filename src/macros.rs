@@ -0,0 +1,58 @@
+/// Expands to the canonical loop locking `lock` until a condition holds, and then mutating the data
+/// exactly once.
+///
+/// `safe_lock!(lock, |data| check => |data_mut| mutate, else retry)` repeatedly acquires the lock
+/// with `lock_blocking` and evaluates `check` with `data` bound to a shared reference to the data.
+/// If it is `false`, the lock is released and `retry` is evaluated before trying again. Otherwise,
+/// the guard is upgraded and `mutate` is evaluated with `data_mut` bound to a mutable reference to
+/// the data, and its value is the value of the whole macro.
+///
+/// `lock` must be a [`SafeLock`](crate::SafeLock) over a reference to a lock whose blocking
+/// acquisitions are infallible.
+#[macro_export]
+macro_rules! safe_lock {
+    ($lock:expr, |$data:pat_param| $check:expr => |$data_mut:pat_param| $mutate:expr, else $retry:expr $(,)?) => {{
+        let mut safe = $lock;
+        loop {
+            let guard = safe.lock_blocking();
+            let $data = &*guard;
+            if $check {
+                let mut guard = guard.upgrade();
+                let $data_mut = &mut *guard;
+                break $mutate;
+            }
+            safe = guard.unlock();
+            $retry;
+        }
+    }};
+}
+
+/// Fails to compile if the type implements the trait, which `assert!` cannot check.
+#[cfg(test)]
+macro_rules! assert_not_impl {
+    ($ty:ty: $trait:path) => {
+        const _: fn() = || {
+            trait AmbiguousIfImpl<A> {
+                fn some_item() {}
+            }
+            impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+            struct Invalid;
+            impl<T: ?Sized + $trait> AmbiguousIfImpl<Invalid> for T {}
+            let _some_item = <$ty as AmbiguousIfImpl<_>>::some_item;
+        };
+    };
+}
+
+/// Fails to compile unless the type implements the trait.
+#[cfg(test)]
+macro_rules! assert_impl {
+    ($ty:ty: $trait:path) => {
+        const _: fn() = || {
+            fn assert_impl<T: ?Sized + $trait>() {}
+            assert_impl::<$ty>();
+        };
+    };
+}
+
+#[cfg(test)]
+pub(crate) use {assert_impl, assert_not_impl};
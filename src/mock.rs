@@ -0,0 +1,179 @@
+use ::core::cell::{Cell, RefCell, RefMut};
+
+use crate::{LockBlocking, LockError, LockErrorKind, LockImmediate, SafeGuard, SafeLock};
+
+/// A [`SafeLock`] over a [`MockLock`].
+pub type SafeMockLock<'a, T> = SafeLock<&'a MockLock<T>>;
+pub type SafeMockLockGuard<'a, T> = SafeGuard<&'a MockLock<T>, RefMut<'a, T>>;
+
+/// The error returned by a [`MockLock`] when an acquisition fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockError {
+    /// The lock is already held, or a failure was scripted with
+    /// [`fail_immediate`](MockLock::fail_immediate).
+    WouldBlock,
+    /// The lock was poisoned with [`poison`](MockLock::poison).
+    Poisoned,
+}
+
+impl From<MockError> for LockError {
+    fn from(err: MockError) -> Self {
+        match err {
+            MockError::WouldBlock => LockErrorKind::WouldBlock.into(),
+            MockError::Poisoned => LockErrorKind::Poisoned.into(),
+        }
+    }
+}
+
+/// A deterministic, single-threaded lock with scripted behavior, meant for testing retry logic.
+///
+/// The behavior of the lock is scripted through `&self`, so it can be altered while a test runs:
+/// - [`fail_immediate`](Self::fail_immediate) makes the next `n` immediate acquisitions fail;
+/// - [`poison`](Self::poison) makes every acquisition fail until the poison is cleared.
+///
+/// Blocking on a lock that is already held would deadlock, so it panics instead.
+#[derive(Debug, Default)]
+pub struct MockLock<T> {
+    data: RefCell<T>,
+    immediate_failures: Cell<usize>,
+    poisoned: Cell<bool>,
+    attempts: Cell<usize>,
+}
+
+impl<T> MockLock<T> {
+    /// Creates a new mock lock protecting `value`, whose acquisitions all succeed.
+    pub const fn new(value: T) -> Self {
+        Self {
+            data: RefCell::new(value),
+            immediate_failures: Cell::new(0),
+            poisoned: Cell::new(false),
+            attempts: Cell::new(0),
+        }
+    }
+
+    /// Makes the next `n` immediate acquisitions fail with [`MockError::WouldBlock`].
+    pub fn fail_immediate(&self, n: usize) {
+        self.immediate_failures.set(n);
+    }
+
+    /// Poisons the lock, making every acquisition fail with [`MockError::Poisoned`].
+    pub fn poison(&self) {
+        self.poisoned.set(true);
+    }
+
+    /// Clears the poison set by [`poison`](Self::poison).
+    pub fn clear_poison(&self) {
+        self.poisoned.set(false);
+    }
+
+    /// Returns whether the lock is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.get()
+    }
+
+    /// Returns the number of acquisitions attempted so far, in any mode, successful or not.
+    pub fn attempts(&self) -> usize {
+        self.attempts.get()
+    }
+
+    /// Consumes the lock, returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    fn check_poison(&self) -> Result<(), MockError> {
+        self.attempts.set(self.attempts.get() + 1);
+        if self.poisoned.get() {
+            Err(MockError::Poisoned)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T> LockBlocking for MockLock<T> {
+    type Error<'a>
+        = MockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.check_poison()?;
+        match self.data.try_borrow_mut() {
+            Ok(guard) => Ok(guard),
+            Err(_) => panic!("blocking on a `MockLock` that is already held would deadlock"),
+        }
+    }
+}
+
+impl<T> LockImmediate for MockLock<T> {
+    type Error<'a>
+        = MockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.check_poison()?;
+        let failures = self.immediate_failures.get();
+        if failures > 0 {
+            self.immediate_failures.set(failures - 1);
+            return Err(MockError::WouldBlock);
+        }
+        self.data
+            .try_borrow_mut()
+            .map_err(|_| MockError::WouldBlock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_failures_are_consumed_in_order() {
+        let lock = MockLock::new(0);
+        lock.fail_immediate(2);
+        let mut safe = SafeMockLock::new(&lock);
+        for _ in 0..2 {
+            let (unlocked, err) = safe.try_lock_immediate_err().unwrap_err();
+            assert_eq!(err, MockError::WouldBlock);
+            safe = unlocked;
+        }
+        let guard = safe.try_lock_immediate().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(lock.attempts(), 3);
+        assert_eq!(lock.into_inner(), 1);
+    }
+
+    #[test]
+    fn poisoned_locks_fail_every_acquisition() {
+        let lock = MockLock::new(0);
+        lock.poison();
+        let (safe, err) = SafeMockLock::new(&lock)
+            .try_lock_blocking_err()
+            .unwrap_err();
+        assert_eq!(LockError::from(err).kind(), LockErrorKind::Poisoned);
+        let safe = safe.try_lock_immediate().unwrap_err();
+        lock.clear_poison();
+        assert!(!lock.is_poisoned());
+        assert!(safe.try_lock_blocking().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "would deadlock")]
+    fn blocking_on_a_held_lock_panics() {
+        let lock = MockLock::new(0);
+        let _guard = SafeMockLock::new(&lock).try_lock_immediate().unwrap();
+        let _ = SafeMockLock::new(&lock).try_lock_blocking();
+    }
+}
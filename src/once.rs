@@ -0,0 +1,197 @@
+use ::core::{
+    convert::Infallible,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{LockBlocking, LockShared, SafeLock, SafeReadGuard};
+
+/// A cell initialized at most once, modeling fallible and racy initialization on top of a lock `L`
+/// guarding an `Option<T>`, such as a `RwLock<Option<T>>`.
+///
+/// Readers [`inspect`](Self::inspect) whether the value exists, holding the lock in shared mode.
+/// Initializing it goes through an explicit [`upgrade`](SafeOnceInit::upgrade) of the
+/// uninitialized state, which acquires the lock in write mode, checks the slot again, and only
+/// upgrades the guard if it is still empty. The value is computed without holding the lock, so
+/// concurrent initializations may compute values that are then discarded.
+#[derive(Debug, Default)]
+pub struct SafeOnce<L>(L);
+
+/// The state of a [`SafeOnce`], as returned by [`SafeOnce::inspect`].
+#[derive(Debug)]
+pub enum OnceEntry<'a, L: LockShared + 'a> {
+    /// The cell is initialized: this is a read guard over its value.
+    Initialized(OnceGuard<SafeReadGuard<&'a L, L::Guard<'a>>>),
+    /// The cell is not initialized yet.
+    Uninitialized(SafeOnceInit<'a, L>),
+}
+
+/// An uninitialized [`SafeOnce`], which can be upgraded to set its value.
+#[derive(Debug)]
+pub struct SafeOnceInit<'a, L>(SafeLock<&'a L>);
+
+/// A guard over the value of an initialized [`SafeOnce`], dereferencing to the value itself.
+#[derive(Debug)]
+pub struct OnceGuard<G>(G);
+
+impl<L> SafeOnce<L> {
+    /// Wraps `lock`, whose slot is initialized if it already holds a value.
+    pub const fn new(lock: L) -> Self {
+        Self(lock)
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.0
+    }
+
+    /// Consumes the cell, returning the wrapped lock.
+    pub fn into_inner(self) -> L {
+        self.0
+    }
+
+    /// Inspects whether the value is initialized, holding the lock in shared mode if it is.
+    pub fn inspect<'a, T>(&'a self) -> OnceEntry<'a, L>
+    where
+        L: LockShared<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = Option<T>>,
+    {
+        let guard = SafeLock::new(&self.0).read_blocking();
+        if guard.is_some() {
+            OnceEntry::Initialized(OnceGuard(guard))
+        } else {
+            OnceEntry::Uninitialized(SafeOnceInit(guard.unlock()))
+        }
+    }
+
+    /// Returns a guard over the value, if initialized.
+    pub fn get<'a, T>(&'a self) -> Option<OnceGuard<SafeReadGuard<&'a L, L::Guard<'a>>>>
+    where
+        L: LockShared<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = Option<T>>,
+    {
+        match self.inspect() {
+            OnceEntry::Initialized(guard) => Some(guard),
+            OnceEntry::Uninitialized(_) => None,
+        }
+    }
+
+    /// Returns a guard over the value, initializing it with `f` if needed.
+    ///
+    /// `f` is run without holding the lock. If another thread initialized the cell in the meantime,
+    /// the value computed by `f` is dropped and the cell is inspected again, until it is
+    /// initialized. If `f` fails, the cell is left uninitialized and the error is returned.
+    pub fn get_or_try_init<'a, T, E>(
+        &'a self,
+        mut f: impl FnMut() -> Result<T, E>,
+    ) -> Result<OnceGuard<SafeReadGuard<&'a L, <L as LockShared>::Guard<'a>>>, E>
+    where
+        L: LockShared<Error<'a> = Infallible> + LockBlocking<Error<'a> = Infallible>,
+        <L as LockShared>::Guard<'a>: Deref<Target = Option<T>>,
+        <L as LockBlocking>::Guard<'a>: Deref<Target = Option<T>> + DerefMut,
+    {
+        loop {
+            match self.inspect() {
+                OnceEntry::Initialized(guard) => return Ok(guard),
+                OnceEntry::Uninitialized(init) => {
+                    // Losing the race is fine: the next inspection finds the winning value.
+                    let _ = init.upgrade(f()?);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, L> SafeOnceInit<'a, L> {
+    /// Initializes the cell with `value`, provided no other thread did in the meantime.
+    ///
+    /// The lock is acquired in write mode, and the guard is only upgraded if the slot is still
+    /// empty. Otherwise, the rejected `value` is handed back. The lock is released in both cases.
+    pub fn upgrade<T>(self, value: T) -> Result<(), T>
+    where
+        L: LockBlocking<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = Option<T>> + DerefMut,
+    {
+        let guard = self.0.lock_blocking();
+        if guard.is_some() {
+            return Err(value);
+        }
+        *guard.upgrade() = Some(value);
+        Ok(())
+    }
+
+    /// Releases the uninitialized cell, returning the [`SafeLock`] over its lock.
+    pub fn unlock(self) -> SafeLock<&'a L> {
+        self.0
+    }
+}
+
+impl<G, T> Deref for OnceGuard<G>
+where
+    G: Deref<Target = Option<T>>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match &*self.0 {
+            Some(value) => value,
+            None => unreachable!("`OnceGuard` over an uninitialized `SafeOnce`"),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::sync::atomic::{AtomicUsize, Ordering};
+    use ::parking_lot::RwLock;
+    use ::std::thread;
+
+    use super::*;
+
+    #[test]
+    fn failed_initializations_leave_the_cell_empty() {
+        let once = SafeOnce::new(RwLock::new(None));
+        assert_eq!(
+            once.get_or_try_init(|| Err::<u32, _>("nope")).unwrap_err(),
+            "nope"
+        );
+        assert!(once.get().is_none());
+        assert_eq!(*once.get_or_try_init(|| Ok::<_, ()>(1)).unwrap(), 1);
+        assert_eq!(*once.get_or_try_init(|| Ok::<_, ()>(2)).unwrap(), 1);
+    }
+
+    #[test]
+    fn upgrades_losing_the_race_hand_the_value_back() {
+        let once = SafeOnce::new(RwLock::new(None));
+        let OnceEntry::Uninitialized(first) = once.inspect() else {
+            panic!("the cell is empty");
+        };
+        let OnceEntry::Uninitialized(second) = once.inspect() else {
+            panic!("the cell is empty");
+        };
+        assert_eq!(first.upgrade(1), Ok(()));
+        assert_eq!(second.upgrade(2), Err(2));
+        assert_eq!(once.get().as_deref(), Some(&1));
+    }
+
+    #[test]
+    fn concurrent_initializations_agree_on_the_value() {
+        let once = SafeOnce::new(RwLock::new(None));
+        let attempts = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for id in 0..4 {
+                let (once, attempts) = (&once, &attempts);
+                s.spawn(move || {
+                    let value = *once
+                        .get_or_try_init(|| {
+                            attempts.fetch_add(1, Ordering::Relaxed);
+                            Ok::<_, ()>(id)
+                        })
+                        .unwrap();
+                    assert_eq!(*once.get().unwrap(), value);
+                });
+            }
+        });
+        assert!(attempts.load(Ordering::Relaxed) >= 1);
+        assert!(once.into_inner().into_inner().is_some());
+    }
+}
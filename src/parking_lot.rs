@@ -1,32 +1,663 @@
-use ::core::convert::Infallible;
+use ::core::{
+    borrow::Borrow,
+    convert::Infallible,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    time::Duration,
+};
+use ::std::{collections::HashMap, rc::Rc, time::Instant};
 
-use parking_lot::{MappedRwLockWriteGuard, RwLock, RwLockWriteGuard};
+#[cfg(feature = "debug-checks")]
+use ::core::sync::atomic::{AtomicBool, Ordering};
 
-use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+use parking_lot::{
+    Condvar, MappedRwLockWriteGuard, Mutex, MutexGuard, RwLock, RwLockReadGuard,
+    RwLockUpgradableReadGuard, RwLockWriteGuard, const_mutex, const_rwlock,
+};
+
+use crate::{
+    LockBlocking, LockDowngrade, LockImmediate, LockProbe, LockShared, LockTimed, LockUpgradable,
+    MayHaveChanged, SafeGuard, SafeLock, SafeReadGuard, SafeUpgradableGuard, TimedLockError,
+};
+#[cfg(feature = "arc_lock")]
+use crate::{LockError, LockErrorKind};
+#[cfg(feature = "arc_lock")]
+use ::std::sync::{Arc, Weak};
+#[cfg(feature = "arc_lock")]
+use parking_lot::{ArcRwLockWriteGuard, RawRwLock};
+
+/// Derives field projections on the [`SafeRwLockGuard`]s of a struct.
+#[cfg(feature = "derive")]
+pub use safe_lock_derive::SafeProject;
 
 /// A wrapper around [`RwLock`](RwLock) from `parking_lot`, providing safe locking behavior.
 pub type SafeRwLock<'a, T> = SafeLock<&'a RwLock<T>>;
 pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a RwLock<T>, RwLockWriteGuard<'a, T>>;
 pub type SafeMappedRwLockGuard<'a, T, U> = SafeGuard<&'a RwLock<T>, MappedRwLockWriteGuard<'a, U>>;
+pub type SafeSplitRwLockGuard<'a, T, U> = SafeGuard<&'a RwLock<T>, SplitRwLockWriteGuard<'a, T, U>>;
+pub type SafeRwLockReadGuard<'a, T> = SafeReadGuard<&'a RwLock<T>, RwLockReadGuard<'a, T>>;
+pub type SafeRwLockUpgradableGuard<'a, T> =
+    SafeUpgradableGuard<&'a RwLock<T>, RwLockUpgradableReadGuard<'a, T>>;
+
+/// A wrapper around [`Mutex`](Mutex) from `parking_lot`, providing safe locking behavior.
+pub type SafeMutex<'a, T> = SafeLock<&'a Mutex<T>>;
+pub type SafeMutexGuard<'a, T> = SafeGuard<&'a Mutex<T>, MutexGuard<'a, T>>;
+
+// The guards can be shared between threads, but are only sent to another one with the `send_guard`
+// feature of `parking_lot`: without it, holding one across an `.await` makes the future `!Send`.
+const _: () = {
+    const fn assert_sync<T: Sync>() {}
+    assert_sync::<SafeRwLockGuard<'static, ()>>();
+    assert_sync::<SafeMutexGuard<'static, ()>>();
+
+    #[cfg(feature = "send_guard")]
+    {
+        const fn assert_send<T: Send>() {}
+        assert_send::<SafeRwLockGuard<'static, ()>>();
+        assert_send::<SafeRwLockReadGuard<'static, ()>>();
+        assert_send::<SafeMutexGuard<'static, ()>>();
+    }
+};
+
+#[cfg(all(test, not(feature = "send_guard")))]
+const _: () = {
+    crate::macros::assert_not_impl!(SafeRwLockGuard<'static, ()>: Send);
+    crate::macros::assert_not_impl!(SafeRwLockReadGuard<'static, ()>: Send);
+    crate::macros::assert_not_impl!(SafeMutexGuard<'static, ()>: Send);
+};
+
+/// The delay the `lock_when_fair` variants wait after the first failed check.
+const LOCK_WHEN_MIN_DELAY: Duration = Duration::from_micros(1);
+/// The maximum delay the `lock_when_fair` variants wait between two checks.
+const LOCK_WHEN_MAX_DELAY: Duration = Duration::from_millis(1);
+
+/// Polls `pred` until it holds, acquiring with `lock` and releasing with `unlock`, and sleeping with
+/// an exponential backoff between two checks. This is the loop shared by the `lock_when_fair`
+/// variants.
+fn poll_when<S, G, T, F>(
+    mut safe: S,
+    lock: impl Fn(S) -> G,
+    unlock: impl Fn(G) -> S,
+    mut pred: F,
+) -> G
+where
+    G: Deref<Target = T>,
+    T: ?Sized,
+    F: FnMut(&T) -> bool,
+{
+    let mut delay = LOCK_WHEN_MIN_DELAY;
+    loop {
+        let guard = lock(safe);
+        if pred(&guard) {
+            return guard;
+        }
+        safe = unlock(guard);
+        ::std::thread::sleep(delay);
+        delay = (delay * 2).min(LOCK_WHEN_MAX_DELAY);
+    }
+}
+
+/// A [`SafeLock`] owning a [`RwLock`] from `parking_lot`, which can be declared as a `static`.
+pub type OwnedSafeRwLock<T> = SafeLock<RwLock<T>>;
+/// A [`SafeLock`] owning a [`Mutex`] from `parking_lot`, which can be declared as a `static`.
+pub type OwnedSafeMutex<T> = SafeLock<Mutex<T>>;
+
+/// An entry of a [`HashMap`] protected by a [`SafeRwLock`], as returned by
+/// [`SafeRwLock::entry`].
+#[derive(Debug)]
+pub enum SafeEntry<'a, K, V, S> {
+    /// The key is in the map: this is a guard over its value.
+    Occupied(SafeMappedRwLockGuard<'a, HashMap<K, V, S>, V>),
+    /// The key is not in the map.
+    Vacant(SafeVacantEntry<'a, K, V, S>),
+}
+
+/// A vacant entry of a [`HashMap`] protected by a [`SafeRwLock`], holding the lock in write mode.
+#[derive(Debug)]
+pub struct SafeVacantEntry<'a, K, V, S> {
+    guard: SafeRwLockGuard<'a, HashMap<K, V, S>>,
+    key: K,
+}
+
+/// A wrapper around an [`Arc`]-shared [`RwLock`] from `parking_lot`, whose guards are `'static`.
+#[cfg(feature = "arc_lock")]
+pub type SafeArcRwLock<T> = SafeLock<Arc<RwLock<T>>>;
+#[cfg(feature = "arc_lock")]
+pub type SafeArcRwLockGuard<T> = SafeGuard<Arc<RwLock<T>>, ArcRwLockWriteGuard<RawRwLock, T>>;
+
+/// A wrapper around a [`Weak`] reference to an [`Arc`]-shared [`RwLock`] from `parking_lot`.
+///
+/// Acquiring the lock first upgrades the reference, and fails with [`LockErrorKind::Dropped`] if the
+/// lock has been dropped. Guards keep the lock alive until they are released.
+#[cfg(feature = "arc_lock")]
+pub type SafeWeakRwLock<T> = SafeLock<Weak<RwLock<T>>>;
+#[cfg(feature = "arc_lock")]
+pub type SafeWeakRwLockGuard<T> = SafeGuard<Weak<RwLock<T>>, ArcRwLockWriteGuard<RawRwLock, T>>;
+
+/// A [`RwLock`] from `parking_lot` whose shared acquisitions are recursive.
+///
+/// A shared acquisition of a [`RwLock`] waits when a writer is queued, so a thread already holding
+/// a read guard deadlocks if it takes another one while a writer is waiting. Through this lock, the
+/// [`LockShared`] acquisitions use [`read_recursive`](RwLock::read_recursive) instead, which
+/// succeeds as long as a read guard is held, at the cost of possibly starving writers. Exclusive
+/// acquisitions are unchanged.
+#[derive(Debug, Default)]
+pub struct RecursiveRwLock<T: ?Sized>(RwLock<T>);
+
+/// A [`SafeLock`] over a [`RecursiveRwLock`].
+pub type SafeRecursiveRwLock<'a, T> = SafeLock<&'a RecursiveRwLock<T>>;
+pub type SafeRecursiveRwLockReadGuard<'a, T> =
+    SafeReadGuard<&'a RecursiveRwLock<T>, RwLockReadGuard<'a, T>>;
+
+/// A [`Mutex`] bundled with the [`Condvar`] used to wait for changes of its data.
+///
+/// Waiting goes through [`wait_until`](Self::wait_until), which returns a [`SafeMutexGuard`] once
+/// the predicate holds. Mutations meant to wake waiters go through
+/// [`notify_after_upgrade`](Self::notify_after_upgrade), whose guard notifies the waiters only
+/// after the mutex has been released, so they do not wake up just to block on it again.
+#[derive(Debug, Default)]
+pub struct SafeCondvar<T: ?Sized> {
+    condvar: Condvar,
+    mutex: Mutex<T>,
+}
+
+/// An upgraded guard over the data of a [`SafeCondvar`], as returned by
+/// [`notify_after_upgrade`](SafeCondvar::notify_after_upgrade).
+///
+/// When dropped, the mutex is released first, then the waiters of the condition variable are notified.
+#[derive(Debug)]
+pub struct SafeCondvarWriteGuard<'a, T: ?Sized> {
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    condvar: &'a Condvar,
+    notify_all: bool,
+}
+
+/// A write guard over one of the disjoint parts of the data of a [`RwLock`], as returned by
+/// [`map_split`](SafeRwLockGuard::map_split).
+///
+/// The lock is released once the guards over every part have been dropped.
+#[derive(Debug)]
+pub struct SplitRwLockWriteGuard<'a, T: ?Sized, U: ?Sized> {
+    /// Keeps the lock held, shared with the guards over the other parts.
+    _guard: Rc<RwLockWriteGuard<'a, T>>,
+    value: NonNull<U>,
+    /// Makes the guard invariant in `U`, like the `&mut U` it hands out.
+    _marker: PhantomData<&'a mut U>,
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for SplitRwLockWriteGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `value` points into the data of the lock, which is held for as long as `guard`
+        // lives, and is disjoint from the parts given to the other guards.
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> DerefMut for SplitRwLockWriteGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: see `deref`; `value` is only reachable through this guard.
+        unsafe { self.value.as_mut() }
+    }
+}
+
+/// A compact alternative to [`SafeRwLockGuard`], holding nothing but the write guard.
+///
+/// A [`SafeGuard`] stores the [`SafeLock`] it hands back on [`unlock`](SafeGuard::unlock) next to
+/// the guard. Since a `parking_lot` write guard knows its lock, this guard recovers the
+/// [`SafeLock`] from it instead, and is half the size. It is obtained with
+/// [`compact`](SafeRwLockGuard::compact), and converted back with [`expand`](Self::expand) to
+/// access the rest of the [`SafeGuard`] API.
+#[derive(Debug)]
+pub struct CompactSafeRwLockGuard<'a, T: ?Sized> {
+    guard: RwLockWriteGuard<'a, T>,
+    #[cfg(feature = "debug-checks")]
+    inspected: AtomicBool,
+}
+
+impl<'a, T: ?Sized> CompactSafeRwLockGuard<'a, T> {
+    /// Upgrades the guard to the underlying write guard, allowing mutation of the locked data.
+    ///
+    /// With the `debug-checks` feature, this panics if the data was never inspected through the guard.
+    #[track_caller]
+    pub fn upgrade(self) -> RwLockWriteGuard<'a, T> {
+        #[cfg(feature = "debug-checks")]
+        assert!(
+            self.inspected.load(Ordering::Relaxed),
+            "`SafeGuard` upgraded without inspecting the locked data first"
+        );
+        self.guard
+    }
 
-impl<'a, T> LockBlocking for &'a RwLock<T> {
-    type Error = Infallible;
-    type Guard = RwLockWriteGuard<'a, T>;
+    /// Releases the lock and returns the [`SafeLock`] recovered from the guard.
+    pub fn unlock(self) -> SafeRwLock<'a, T> {
+        SafeLock(RwLockWriteGuard::rwlock(&self.guard))
+    }
+
+    /// Converts the guard back into a [`SafeRwLockGuard`].
+    pub fn expand(self) -> SafeRwLockGuard<'a, T> {
+        let lock = SafeLock(RwLockWriteGuard::rwlock(&self.guard));
+        let expanded = SafeGuard::new(lock, self.guard);
+        #[cfg(feature = "debug-checks")]
+        if self.inspected.into_inner() {
+            expanded.mark_inspected();
+        }
+        expanded
+    }
+}
 
-    fn lock_blocking(&self) -> Result<Self::Guard, Self::Error> {
+impl<T: ?Sized> Deref for CompactSafeRwLockGuard<'_, T> {
+    type Target = T;
+
+    /// Provides read-only access to the underlying value.
+    ///
+    /// Mutation is only possible after calling [`upgrade`](Self::upgrade).
+    fn deref(&self) -> &Self::Target {
+        #[cfg(feature = "debug-checks")]
+        self.inspected.store(true, Ordering::Relaxed);
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> LockBlocking for RwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
         Ok(self.write())
     }
 }
 
-impl<'a, T> LockImmediate for &'a RwLock<T> {
-    type Error = ();
-    type Guard = RwLockWriteGuard<'a, T>;
+impl<T: ?Sized> LockImmediate for RwLock<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
 
-    fn lock_immediate(&self) -> Result<Self::Guard, Self::Error> {
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
         self.try_write().ok_or(())
     }
 }
 
+impl<T: ?Sized> LockTimed for RwLock<T> {
+    type Error<'a>
+        = TimedLockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_write_for(timeout).ok_or(TimedLockError::TimedOut)
+    }
+
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_write_until(deadline)
+            .ok_or(TimedLockError::TimedOut)
+    }
+}
+
+impl<T: ?Sized> LockProbe for RwLock<T> {
+    fn is_locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked_exclusive()
+    }
+}
+
+impl<T: ?Sized> LockShared for RwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.read())
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.try_read().ok_or(())
+    }
+}
+
+impl<T: ?Sized> LockDowngrade for RwLock<T> {
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+    type DowngradedGuard<'a>
+        = RwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn downgrade<'a>(guard: Self::Guard<'a>) -> (&'a Self, Self::DowngradedGuard<'a>) {
+        let lock = RwLockWriteGuard::rwlock(&guard);
+        (lock, RwLockWriteGuard::downgrade(guard))
+    }
+}
+
+impl<T: ?Sized> LockUpgradable for RwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockUpgradableReadGuard<'a, T>
+    where
+        Self: 'a;
+    type UpgradedGuard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_upgradable(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.upgradable_read())
+    }
+
+    fn upgrade<'a>(guard: Self::Guard<'a>) -> Self::UpgradedGuard<'a> {
+        RwLockUpgradableReadGuard::upgrade(guard)
+    }
+}
+
+impl<T: ?Sized> LockProbe for Mutex<T> {
+    fn is_locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked()
+    }
+}
+
+impl<T: ?Sized> LockBlocking for Mutex<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.lock())
+    }
+}
+
+impl<T: ?Sized> LockImmediate for Mutex<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock().ok_or(())
+    }
+}
+
+impl<T: ?Sized> LockTimed for Mutex<T> {
+    type Error<'a>
+        = TimedLockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock_for(timeout).ok_or(TimedLockError::TimedOut)
+    }
+
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock_until(deadline)
+            .ok_or(TimedLockError::TimedOut)
+    }
+}
+
+impl<T: ?Sized> LockBlocking for RecursiveRwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.0.write())
+    }
+}
+
+impl<T: ?Sized> LockImmediate for RecursiveRwLock<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.0.try_write().ok_or(())
+    }
+}
+
+impl<T: ?Sized> LockTimed for RecursiveRwLock<T> {
+    type Error<'a>
+        = TimedLockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.0
+            .try_write_for(timeout)
+            .ok_or(TimedLockError::TimedOut)
+    }
+
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.0
+            .try_write_until(deadline)
+            .ok_or(TimedLockError::TimedOut)
+    }
+}
+
+impl<T: ?Sized> LockProbe for RecursiveRwLock<T> {
+    fn is_locked(&self) -> bool {
+        self.0.is_locked()
+    }
+
+    fn is_locked_exclusive(&self) -> bool {
+        self.0.is_locked_exclusive()
+    }
+}
+
+impl<T: ?Sized> LockShared for RecursiveRwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.0.read_recursive())
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.0.try_read_recursive().ok_or(())
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for SafeRwLockGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for SafeMutexGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T> From<T> for OwnedSafeRwLock<T> {
+    fn from(value: T) -> Self {
+        Self::const_new(value)
+    }
+}
+
+impl<T> From<T> for OwnedSafeMutex<T> {
+    fn from(value: T) -> Self {
+        Self::const_new(value)
+    }
+}
+
+impl<T> OwnedSafeRwLock<T> {
+    /// Creates a new [`SafeLock`] owning a [`RwLock`] protecting `value`, in a `const` context.
+    pub const fn const_new(value: T) -> Self {
+        SafeLock(const_rwlock(value))
+    }
+
+    /// Returns a mutable reference to the protected data.
+    ///
+    /// No locking is needed since the mutable borrow guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut()
+    }
+}
+
+impl<T> OwnedSafeMutex<T> {
+    /// Creates a new [`SafeLock`] owning a [`Mutex`] protecting `value`, in a `const` context.
+    pub const fn const_new(value: T) -> Self {
+        SafeLock(const_mutex(value))
+    }
+
+    /// Returns a mutable reference to the protected data.
+    ///
+    /// No locking is needed since the mutable borrow guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut()
+    }
+}
+
+impl<'a, T: ?Sized> SafeRwLockGuard<'a, T> {
+    /// Converts the guard into a [`CompactSafeRwLockGuard`], which does not store the lock handle.
+    pub fn compact(self) -> CompactSafeRwLockGuard<'a, T> {
+        #[cfg(feature = "debug-checks")]
+        let inspected = AtomicBool::new(self.is_inspected());
+        CompactSafeRwLockGuard {
+            guard: self.guard,
+            #[cfg(feature = "debug-checks")]
+            inspected,
+        }
+    }
+
+    /// Temporarily releases the lock if other threads are waiting for it, then re-acquires it.
+    ///
+    /// This prevents long inspection phases from starving other threads. Since another thread may
+    /// have mutated the data in between, the data must be inspected again before upgrading, which
+    /// the returned [`MayHaveChanged`] marker is a reminder of.
+    pub fn bump(&mut self) -> MayHaveChanged {
+        RwLockWriteGuard::bump(&mut self.guard);
+        self.reset_inspected();
+        MayHaveChanged
+    }
+
+    /// Releases the lock fairly, runs `f`, then re-acquires it, returning the guard.
+    ///
+    /// This behaves like [`unlocked`](SafeGuard::unlocked), but hands the lock over to a waiting
+    /// thread if there is one. The data must be inspected again before upgrading.
+    pub fn unlocked_fair<F, R>(mut self, f: F) -> (Self, R)
+    where
+        F: FnOnce() -> R,
+    {
+        let res = RwLockWriteGuard::unlocked_fair(&mut self.guard, f);
+        self.reset_inspected();
+        (self, res)
+    }
+
+    /// Releases the lock fairly and returns the original [`SafeLock`].
+    ///
+    /// This behaves like [`unlock`](SafeGuard::unlock), but hands the lock over to a waiting thread
+    /// if there is one, so that a retry loop does not starve the waiters by re-acquiring it right away.
+    pub fn unlock_fair(self) -> SafeRwLock<'a, T> {
+        let SafeGuard { lock, guard, .. } = self;
+        RwLockWriteGuard::unlock_fair(guard);
+        lock
+    }
+
+    /// Leaks the guard, returning a shared reference to the locked data for the lifetime of the lock.
+    ///
+    /// The lock is downgraded to read mode and never released, so other readers can still acquire
+    /// it but no writer ever will. This is meant for locks living in a `static`, whose data is
+    /// only ever read once initialized.
+    pub fn leak(self) -> &'a T {
+        let guard = RwLockWriteGuard::downgrade(self.guard);
+        let value: *const T = &*guard;
+        mem::forget(guard);
+        // SAFETY: the read lock is never released, so the data outlives `'a` and is never mutated.
+        unsafe { &*value }
+    }
+
+    /// Upgrades and leaks the guard, returning a mutable reference to the locked data for the
+    /// lifetime of the lock.
+    ///
+    /// The lock stays held in write mode forever. With the `debug-checks` feature, this panics if
+    /// the data was never inspected through the guard.
+    #[track_caller]
+    pub fn leak_upgraded(self) -> &'a mut T {
+        let mut guard = self.upgrade();
+        let value: *mut T = &mut *guard;
+        mem::forget(guard);
+        // SAFETY: the write lock is never released, so the data outlives `'a` and is only
+        // reachable through the returned reference.
+        unsafe { &mut *value }
+    }
+}
+
 impl<'a, T> SafeRwLockGuard<'a, T> {
     /// Maps the guarded value to a different type and returns a new guard for that type.
     ///
@@ -51,4 +682,921 @@ impl<'a, T> SafeRwLockGuard<'a, T> {
     {
         self.try_map_guard(|guard| RwLockWriteGuard::try_map(guard, f))
     }
+
+    /// Attempts to map the guarded value to a different type, returning the original guard
+    /// alongside an error if the mapping fails.
+    ///
+    /// This method works like [`try_map`](Self::try_map), but lets the mapping function explain
+    /// why the mapping failed.
+    #[allow(clippy::type_complexity)]
+    pub fn try_map_err<U, E, F>(self, f: F) -> Result<SafeMappedRwLockGuard<'a, T, U>, (Self, E)>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> Result<&mut U, E>,
+    {
+        self.try_map_guard_err(|guard| RwLockWriteGuard::try_map_or_err(guard, f))
+    }
+
+    /// Splits the guarded value into two disjoint parts, returning a guard for each of them.
+    ///
+    /// The function `f` typically borrows two distinct fields or two halves of a slice. Both guards
+    /// keep the lock held in write mode until they are both dropped, and can be inspected and
+    /// upgraded independently. Unlocking one of them while the other is alive hands back a
+    /// [`SafeLock`] that cannot be acquired before the other guard is dropped.
+    #[allow(clippy::type_complexity)]
+    pub fn map_split<U, V, F>(
+        self,
+        f: F,
+    ) -> (
+        SafeSplitRwLockGuard<'a, T, U>,
+        SafeSplitRwLockGuard<'a, T, V>,
+    )
+    where
+        U: ?Sized,
+        V: ?Sized,
+        F: FnOnce(&mut T) -> (&mut U, &mut V),
+    {
+        let inspected = self.is_inspected();
+        let lock = self.lock.0;
+        let mut guard = self.guard;
+        let (first, second) = f(&mut guard);
+        let (first, second) = (NonNull::from(first), NonNull::from(second));
+        let guard = Rc::new(guard);
+
+        let first = SafeGuard::new(
+            SafeLock(lock),
+            SplitRwLockWriteGuard {
+                _guard: Rc::clone(&guard),
+                value: first,
+                _marker: PhantomData,
+            },
+        );
+        let second = SafeGuard::new(
+            SafeLock(lock),
+            SplitRwLockWriteGuard {
+                _guard: guard,
+                value: second,
+                _marker: PhantomData,
+            },
+        );
+        if inspected {
+            first.mark_inspected();
+            second.mark_inspected();
+        }
+        (first, second)
+    }
+}
+
+impl<'a, T: ?Sized, U: ?Sized> SafeMappedRwLockGuard<'a, T, U> {
+    /// Maps the guarded value further, returning a new guard for the narrowed data.
+    ///
+    /// This behaves like [`SafeRwLockGuard::map`], so that projections compose.
+    pub fn map<V, F>(self, f: F) -> SafeMappedRwLockGuard<'a, T, V>
+    where
+        V: ?Sized,
+        F: FnOnce(&mut U) -> &mut V,
+    {
+        self.map_guard(|guard| MappedRwLockWriteGuard::map(guard, f))
+    }
+
+    /// Attempts to map the guarded value further, returning a guard for the narrowed data.
+    ///
+    /// This behaves like [`SafeRwLockGuard::try_map`]: if `f` returns `None`, the original guard is
+    /// returned instead.
+    pub fn try_map<V, F>(self, f: F) -> Result<SafeMappedRwLockGuard<'a, T, V>, Self>
+    where
+        V: ?Sized,
+        F: FnOnce(&mut U) -> Option<&mut V>,
+    {
+        self.try_map_guard(|guard| MappedRwLockWriteGuard::try_map(guard, f))
+    }
+
+    /// Releases the lock fairly and returns the original [`SafeLock`].
+    ///
+    /// This behaves like [`SafeRwLockGuard::unlock_fair`].
+    pub fn unlock_fair(self) -> SafeRwLock<'a, T> {
+        let SafeGuard { lock, guard, .. } = self;
+        MappedRwLockWriteGuard::unlock_fair(guard);
+        lock
+    }
+}
+
+impl<'a, T: ?Sized> SafeMutexGuard<'a, T> {
+    /// Temporarily releases the mutex if other threads are waiting for it, then re-acquires it.
+    ///
+    /// This prevents long inspection phases from starving other threads. Since another thread may
+    /// have mutated the data in between, the data must be inspected again before upgrading, which
+    /// the returned [`MayHaveChanged`] marker is a reminder of.
+    pub fn bump(&mut self) -> MayHaveChanged {
+        MutexGuard::bump(&mut self.guard);
+        self.reset_inspected();
+        MayHaveChanged
+    }
+
+    /// Releases the mutex fairly, runs `f`, then re-acquires it, returning the guard.
+    ///
+    /// This behaves like [`unlocked`](SafeGuard::unlocked), but hands the mutex over to a waiting
+    /// thread if there is one. The data must be inspected again before upgrading.
+    pub fn unlocked_fair<F, R>(mut self, f: F) -> (Self, R)
+    where
+        F: FnOnce() -> R,
+    {
+        let res = MutexGuard::unlocked_fair(&mut self.guard, f);
+        self.reset_inspected();
+        (self, res)
+    }
+
+    /// Releases the mutex fairly and returns the original [`SafeLock`].
+    ///
+    /// This behaves like [`unlock`](SafeGuard::unlock), but hands the mutex over to a waiting thread
+    /// if there is one, so that a retry loop does not starve the waiters by re-acquiring it right away.
+    pub fn unlock_fair(self) -> SafeMutex<'a, T> {
+        let SafeGuard { lock, guard, .. } = self;
+        MutexGuard::unlock_fair(guard);
+        lock
+    }
+
+    /// Leaks the guard, returning a shared reference to the locked data for the lifetime of the mutex.
+    ///
+    /// The mutex is never released. This is meant for mutexes living in a `static`, whose data is
+    /// only ever read once initialized.
+    pub fn leak(self) -> &'a T {
+        MutexGuard::leak(self.guard)
+    }
+
+    /// Upgrades and leaks the guard, returning a mutable reference to the locked data for the
+    /// lifetime of the mutex.
+    ///
+    /// With the `debug-checks` feature, this panics if the data was never inspected through the guard.
+    #[track_caller]
+    pub fn leak_upgraded(self) -> &'a mut T {
+        MutexGuard::leak(self.upgrade())
+    }
+
+    /// Blocks the current thread until the condition variable is notified.
+    ///
+    /// The lock is released while waiting and re-acquired before returning. The guard stays in its
+    /// read-only state, so the data must be inspected again before upgrading.
+    pub fn wait(&mut self, condvar: &Condvar) {
+        condvar.wait(&mut self.guard);
+        self.reset_inspected();
+    }
+
+    /// Blocks the current thread until `condition` returns `false`, waiting on the condition variable.
+    ///
+    /// The condition only gets read-only access to the data, which is checked before the first wait
+    /// and after every wakeup. Evaluating it counts as inspecting the data, so the guard is ready to
+    /// be upgraded afterwards.
+    pub fn wait_while<F>(&mut self, condvar: &Condvar, mut condition: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        condvar.wait_while(&mut self.guard, |value| condition(value));
+        self.mark_inspected();
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T> SafeArcRwLock<T> {
+    /// Acquires the lock in write mode and returns a `'static` guard for the locked data.
+    ///
+    /// The returned guard keeps its own reference to the [`Arc`], so it does not borrow anything
+    /// and can be stored or moved freely. Mutation is not possible until explicitly upgrading the guard.
+    pub fn lock_blocking(self) -> SafeArcRwLockGuard<T> {
+        let guard = self.0.write_arc();
+        SafeGuard::new(self, guard)
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking and returns a `'static` guard if successful.
+    ///
+    /// If the lock is already held, this method will return `Err(self)` without blocking.
+    pub fn try_lock_immediate(self) -> Result<SafeArcRwLockGuard<T>, Self> {
+        match self.0.try_write_arc() {
+            Some(guard) => Ok(SafeGuard::new(self, guard)),
+            None => Err(self),
+        }
+    }
+
+    /// Returns a [`SafeWeakRwLock`] referring to the same lock, without keeping it alive.
+    pub fn downgrade(&self) -> SafeWeakRwLock<T> {
+        SafeLock(Arc::downgrade(&self.0))
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T> SafeWeakRwLock<T> {
+    /// Attempts to acquire the lock in write mode, blocking until it is available.
+    ///
+    /// If the lock has been dropped, this method will return `Err(self)`.
+    pub fn try_lock_blocking(self) -> Result<SafeWeakRwLockGuard<T>, Self> {
+        self.try_lock_blocking_err().map_err(|(safe, _)| safe)
+    }
+
+    /// Attempts to acquire the lock in write mode, blocking until it is available, and returns an
+    /// error if the lock has been dropped.
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_blocking_err(self) -> Result<SafeWeakRwLockGuard<T>, (Self, LockError)> {
+        match self.0.upgrade() {
+            Some(lock) => Ok(SafeGuard::new(self, lock.write_arc())),
+            None => Err((self, LockErrorKind::Dropped.into())),
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking and returns a guard if successful.
+    ///
+    /// If the lock has been dropped or is already held, this method will return `Err(self)`.
+    pub fn try_lock_immediate(self) -> Result<SafeWeakRwLockGuard<T>, Self> {
+        self.try_lock_immediate_err().map_err(|(safe, _)| safe)
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking and returns an error if the lock
+    /// has been dropped or is already held.
+    #[allow(clippy::type_complexity)]
+    pub fn try_lock_immediate_err(self) -> Result<SafeWeakRwLockGuard<T>, (Self, LockError)> {
+        let Some(lock) = self.0.upgrade() else {
+            return Err((self, LockErrorKind::Dropped.into()));
+        };
+        match lock.try_write_arc() {
+            Some(guard) => Ok(SafeGuard::new(self, guard)),
+            None => Err((self, LockErrorKind::WouldBlock.into())),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> SafeRwLock<'a, T> {
+    /// Acquires the lock in shared mode, recursively, and returns a read-only guard for the locked data.
+    ///
+    /// Unlike [`read_blocking`](SafeLock::read_blocking), this succeeds even if a writer is waiting,
+    /// as long as a read guard is held, so a thread already holding one cannot deadlock by taking
+    /// another. See [`RecursiveRwLock`] to make every shared acquisition recursive.
+    pub fn read_recursive(self) -> SafeRwLockReadGuard<'a, T> {
+        SafeReadGuard {
+            guard: self.0.read_recursive(),
+            lock: self,
+        }
+    }
+
+    /// Attempts to acquire the lock in shared mode, recursively, without blocking, and returns a
+    /// read-only guard if successful.
+    ///
+    /// If the lock is held in write mode, this method will return `Err(self)`.
+    pub fn try_read_recursive(self) -> Result<SafeRwLockReadGuard<'a, T>, Self> {
+        match self.0.try_read_recursive() {
+            Some(guard) => Ok(SafeReadGuard { lock: self, guard }),
+            None => Err(self),
+        }
+    }
+
+    /// Acquires the lock in write mode once `pred` holds for the locked data, and returns a guard for it.
+    ///
+    /// Whenever the predicate does not hold, the lock is released [fairly](SafeRwLockGuard::unlock_fair), so
+    /// the threads waiting for it get to run, and the current thread sleeps with an exponentially
+    /// increasing delay, capped at one millisecond, before checking again. To be woken up by the
+    /// writers instead, wrap the lock in a [`Watch`](crate::watch::Watch) decorator and use
+    /// [`lock_when`](SafeLock::lock_when).
+    pub fn lock_when_fair<F>(self, pred: F) -> SafeRwLockGuard<'a, T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        poll_when(
+            self,
+            Self::lock_blocking,
+            SafeRwLockGuard::unlock_fair,
+            pred,
+        )
+    }
+}
+
+impl<'a, T: ?Sized> SafeMutex<'a, T> {
+    /// Acquires the mutex once `pred` holds for the locked data, and returns a guard for it.
+    ///
+    /// Whenever the predicate does not hold, the mutex is released [fairly](SafeMutexGuard::unlock_fair), so
+    /// the threads waiting for it get to run, and the current thread sleeps with an exponentially
+    /// increasing delay, capped at one millisecond, before checking again. To be woken up by the
+    /// writers instead, wrap the lock in a [`Watch`](crate::watch::Watch) decorator and use
+    /// [`lock_when`](SafeLock::lock_when).
+    pub fn lock_when_fair<F>(self, pred: F) -> SafeMutexGuard<'a, T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        poll_when(self, Self::lock_blocking, SafeMutexGuard::unlock_fair, pred)
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> SafeRwLock<'a, HashMap<K, V, S>> {
+    /// Acquires the lock in write mode, checks whether `key` is in the map, and if not, upgrades the
+    /// guard to insert the value returned by `init`. Returns a guard over the value of `key`.
+    ///
+    /// The returned guard allows read-only access to the value until explicitly upgraded.
+    pub fn get_or_insert_with<F>(
+        self,
+        key: K,
+        init: F,
+    ) -> SafeMappedRwLockGuard<'a, HashMap<K, V, S>, V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self.entry(key) {
+            SafeEntry::Occupied(value) => value,
+            SafeEntry::Vacant(entry) => entry.insert(init()),
+        }
+    }
+
+    /// Acquires the lock in write mode, and returns the entry of `key`.
+    ///
+    /// The value of an occupied entry must be inspected before upgrading, like any other guard.
+    pub fn entry(self, key: K) -> SafeEntry<'a, K, V, S> {
+        let guard = self.lock_blocking();
+        if guard.contains_key(&key) {
+            let occupied = guard.map(|map| map.get_mut(&key).unwrap());
+            occupied.reset_inspected();
+            SafeEntry::Occupied(occupied)
+        } else {
+            SafeEntry::Vacant(SafeVacantEntry { guard, key })
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> SafeVacantEntry<'a, K, V, S> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Upgrades the guard to insert `value` for the key of the entry, and returns a guard over it.
+    ///
+    /// The returned guard allows read-only access to the value until explicitly upgraded.
+    pub fn insert(self, value: V) -> SafeMappedRwLockGuard<'a, HashMap<K, V, S>, V> {
+        let Self { guard, key } = self;
+        let lock = SafeLock(guard.lock.0);
+        let guard = RwLockWriteGuard::map(guard.upgrade(), |map| map.entry(key).or_insert(value));
+        SafeGuard::new(lock, guard)
+    }
+
+    /// Releases the lock without inserting anything, and returns the original [`SafeLock`].
+    pub fn unlock(self) -> SafeRwLock<'a, HashMap<K, V, S>> {
+        self.guard.unlock()
+    }
+}
+
+impl<T> RecursiveRwLock<T> {
+    /// Creates a new lock protecting `value`.
+    pub const fn new(value: T) -> Self {
+        Self(const_rwlock(value))
+    }
+
+    /// Consumes the lock, returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ?Sized> RecursiveRwLock<T> {
+    /// Returns a reference to the wrapped [`RwLock`].
+    pub const fn inner(&self) -> &RwLock<T> {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the protected value, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut()
+    }
+}
+
+impl<T> From<RwLock<T>> for RecursiveRwLock<T> {
+    fn from(lock: RwLock<T>) -> Self {
+        Self(lock)
+    }
+}
+
+impl<T> SafeCondvar<T> {
+    /// Creates a new mutex protecting `value`, along with its condition variable.
+    pub const fn new(value: T) -> Self {
+        Self {
+            condvar: Condvar::new(),
+            mutex: const_mutex(value),
+        }
+    }
+
+    /// Consumes the [`SafeCondvar`], returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.mutex.into_inner()
+    }
+}
+
+impl<T: ?Sized> SafeCondvar<T> {
+    /// Returns a [`SafeMutex`] over the bundled mutex.
+    pub const fn lock(&self) -> SafeMutex<'_, T> {
+        SafeLock(&self.mutex)
+    }
+
+    /// Returns a reference to the bundled condition variable.
+    pub const fn condvar(&self) -> &Condvar {
+        &self.condvar
+    }
+
+    /// Returns a mutable reference to the protected value, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.mutex.get_mut()
+    }
+
+    /// Acquires the mutex and blocks until `pred` holds for the locked data, returning a guard for it.
+    ///
+    /// The predicate is checked before the first wait and after every wakeup, so spurious wakeups
+    /// are handled. Evaluating it counts as inspecting the data, so the returned guard is ready to
+    /// be upgraded.
+    pub fn wait_until<F>(&self, mut pred: F) -> SafeMutexGuard<'_, T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut guard = self.lock().lock_blocking();
+        guard.wait_while(&self.condvar, |value| !pred(value));
+        guard
+    }
+
+    /// Upgrades a guard over the bundled mutex, notifying all the waiters once it is released.
+    ///
+    /// With the `debug-checks` feature, this panics if the data was never inspected through the
+    /// guard. This also panics if `guard` does not come from this [`SafeCondvar`].
+    #[track_caller]
+    pub fn notify_after_upgrade<'a>(
+        &'a self,
+        guard: SafeMutexGuard<'a, T>,
+    ) -> SafeCondvarWriteGuard<'a, T> {
+        self.notifying(guard, true)
+    }
+
+    /// Upgrades a guard over the bundled mutex, notifying a single waiter once it is released.
+    ///
+    /// See [`notify_after_upgrade`](Self::notify_after_upgrade).
+    #[track_caller]
+    pub fn notify_one_after_upgrade<'a>(
+        &'a self,
+        guard: SafeMutexGuard<'a, T>,
+    ) -> SafeCondvarWriteGuard<'a, T> {
+        self.notifying(guard, false)
+    }
+
+    /// Wakes up all the threads waiting in [`wait_until`](Self::wait_until), without mutating the data.
+    pub fn notify_all(&self) -> usize {
+        self.condvar.notify_all()
+    }
+
+    #[track_caller]
+    fn notifying<'a>(
+        &'a self,
+        guard: SafeMutexGuard<'a, T>,
+        notify_all: bool,
+    ) -> SafeCondvarWriteGuard<'a, T> {
+        assert!(
+            ptr::eq(guard.lock.0, &self.mutex),
+            "guard upgraded with a `SafeCondvar` it does not come from"
+        );
+        SafeCondvarWriteGuard {
+            guard: ManuallyDrop::new(guard.upgrade()),
+            condvar: &self.condvar,
+            notify_all,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for SafeCondvarWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if self.notify_all {
+            self.condvar.notify_all();
+        } else {
+            self.condvar.notify_one();
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for SafeCondvarWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for SafeCondvarWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+/// A [`SplitRwLockWriteGuard`] hands out `&mut U`, so it cannot be coerced to a shorter-lived `U`,
+/// which would let a short-lived reference be written through it and read back once freed:
+///
+/// ```compile_fail
+/// use safe_lock::parking_lot::SplitRwLockWriteGuard;
+///
+/// fn shorten<'a, 'b>(
+///     guard: SplitRwLockWriteGuard<'a, (), &'static str>,
+/// ) -> SplitRwLockWriteGuard<'a, (), &'b str> {
+///     guard
+/// }
+/// ```
+#[cfg(doctest)]
+pub struct SplitGuardsAreInvariant;
+
+#[cfg(test)]
+mod tests {
+    use ::std::collections::HashMap;
+
+    use super::*;
+    use crate::{LockError, LockErrorKind};
+
+    #[test]
+    fn get_or_insert_with_only_inserts_missing_keys() {
+        let lock = RwLock::new(HashMap::from([("a", 1)]));
+        assert_eq!(*SafeLock::new(&lock).get_or_insert_with("a", || 2), 1);
+        assert_eq!(*SafeLock::new(&lock).get_or_insert_with("b", || 3), 3);
+        assert_eq!(*lock.read(), HashMap::from([("a", 1), ("b", 3)]));
+    }
+
+    #[test]
+    fn get_or_insert_with_returns_a_read_only_guard() {
+        let lock = RwLock::new(HashMap::new());
+        let guard = SafeLock::new(&lock).get_or_insert_with("a", || 1);
+        if *guard == 1 {
+            *guard.upgrade() += 1;
+        }
+        assert_eq!(lock.read()["a"], 2);
+    }
+
+    #[test]
+    fn vacant_entries_can_be_released_without_inserting() {
+        let lock = RwLock::new(HashMap::<_, u32>::new());
+        let SafeEntry::Vacant(entry) = SafeLock::new(&lock).entry("a") else {
+            panic!("the map is empty");
+        };
+        assert_eq!(*entry.key(), "a");
+        entry.unlock();
+        assert!(lock.read().is_empty());
+    }
+
+    #[cfg(feature = "arc_lock")]
+    #[test]
+    fn arc_guards_do_not_borrow_the_lock() {
+        fn acquire(lock: &Arc<RwLock<u32>>) -> SafeArcRwLockGuard<u32> {
+            SafeLock::new(Arc::clone(lock)).lock_blocking()
+        }
+
+        let lock = Arc::new(RwLock::new(1));
+        let guard = acquire(&lock);
+        assert!(
+            SafeLock::new(Arc::clone(&lock))
+                .try_lock_immediate()
+                .is_err()
+        );
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn wait_while_releases_the_mutex_until_notified() {
+        let (mutex, condvar) = (Mutex::new(false), Condvar::new());
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&mutex).lock_blocking();
+                let ready = *guard;
+                *guard.upgrade() = !ready;
+                condvar.notify_one();
+            });
+            let mut guard = SafeLock::new(&mutex).lock_blocking();
+            guard.wait_while(&condvar, |ready| !*ready);
+            assert!(*guard);
+        });
+    }
+
+    #[test]
+    fn split_guards_are_upgraded_independently() {
+        let lock = RwLock::new((1, 2));
+        let (first, second) = SafeLock::new(&lock)
+            .lock_blocking()
+            .map_split(|(first, second)| (first, second));
+        let value = *first;
+        *first.upgrade() = value * 10;
+        assert!(lock.try_read().is_none());
+        let safe = second.unlock();
+        assert_eq!(*safe.lock_blocking(), (10, 2));
+    }
+
+    #[test]
+    fn mapped_guards_compose() {
+        let lock = RwLock::new((0, Some(1)));
+        let guard = SafeLock::new(&lock)
+            .lock_blocking()
+            .map(|(_, second)| second)
+            .try_map(Option::as_mut)
+            .unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.read(), (0, Some(2)));
+
+        let guard = SafeLock::new(&lock)
+            .lock_blocking()
+            .map(|(first, _)| first)
+            .map(|first| first)
+            .try_map(|_| None::<&mut u32>)
+            .unwrap_err();
+        assert_eq!(*guard, 0);
+    }
+
+    #[test]
+    fn try_map_err_hands_the_guard_back_with_the_error() {
+        let lock = RwLock::new(vec![1, 2]);
+        let (guard, err) = SafeLock::new(&lock)
+            .lock_blocking()
+            .try_map_err(|values| values.get_mut(5).ok_or("out of bounds"))
+            .unwrap_err();
+        assert_eq!(err, "out of bounds");
+        let guard = guard
+            .try_map_err(|values| values.get_mut(1).ok_or("out of bounds"))
+            .unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.read(), [1, 3]);
+    }
+
+    #[test]
+    fn expired_timeouts_convert_to_timed_out_errors() {
+        let lock = RwLock::new(0);
+        let _guard = lock.read();
+        let err = LockError::from(
+            SafeLock::new(&lock)
+                .try_lock_blocking_until_err(Instant::now() + Duration::from_millis(10))
+                .unwrap_err(),
+        );
+        assert_eq!(err.kind(), LockErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn compact_guards_are_smaller_and_recover_the_lock() {
+        assert!(
+            mem::size_of::<CompactSafeRwLockGuard<'static, u32>>()
+                < mem::size_of::<SafeRwLockGuard<'static, u32>>()
+        );
+
+        let lock = RwLock::new(1);
+        let guard = SafeLock::new(&lock).lock_blocking().compact();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        let guard = SafeLock::new(&lock).lock_blocking().compact().expand();
+        assert_eq!(*guard, 2);
+        let safe = guard.compact().unlock();
+        assert!(ptr::eq(*safe.inner(), &lock));
+        assert!(safe.try_lock_immediate().is_ok());
+    }
+
+    #[test]
+    fn owned_locks_are_const_constructible() {
+        static CONFIG: OwnedSafeRwLock<Option<&str>> = OwnedSafeRwLock::const_new(None);
+        let guard = CONFIG.lock_blocking_ref();
+        if guard.is_none() {
+            *guard.upgrade() = Some("configured");
+        }
+        assert_eq!(*CONFIG.read_blocking_ref(), Some("configured"));
+
+        let mut counter = OwnedSafeMutex::const_new(0);
+        *counter.get_mut() += 1;
+        assert_eq!(*counter.lock_blocking_ref(), 1);
+    }
+
+    #[test]
+    fn guards_expose_the_backend_guard_and_borrow_the_data() {
+        fn sum(values: &[impl Borrow<u32>]) -> u32 {
+            values.iter().map(Borrow::borrow).sum()
+        }
+
+        fn len(value: impl AsRef<String>) -> usize {
+            value.as_ref().len()
+        }
+
+        let (first, second) = (RwLock::new(1), Mutex::new(2));
+        let guards = [SafeLock::new(&first).lock_blocking()];
+        assert_eq!(sum(&guards), 1);
+        assert_eq!(sum(&[SafeLock::new(&second).lock_blocking()]), 2);
+
+        let lock = RwLock::new(String::from("abc"));
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert!(RwLockWriteGuard::rwlock(guard.as_guard()).is_locked_exclusive());
+        guard.upgrade().push('d');
+        assert_eq!(len(SafeLock::new(&lock).lock_blocking()), 4);
+    }
+
+    #[test]
+    fn leaked_guards_keep_the_lock_held() {
+        let lock: &'static RwLock<u32> = Box::leak(Box::new(RwLock::new(1)));
+        let value: &'static u32 = SafeLock::new(lock).lock_blocking().leak();
+        assert_eq!(*value, 1);
+        assert_eq!(*lock.try_read().unwrap(), 1);
+        assert!(lock.try_write().is_none());
+
+        let mutex: &'static Mutex<u32> = Box::leak(Box::new(Mutex::new(1)));
+        let guard = SafeLock::new(mutex).lock_blocking();
+        let previous = *guard;
+        let value: &'static mut u32 = guard.leak_upgraded();
+        *value = previous + 1;
+        assert!(mutex.try_lock().is_none());
+    }
+
+    #[test]
+    fn bumping_lets_waiting_threads_in() {
+        let lock = Mutex::new(0);
+        let mut guard = SafeLock::new(&lock).lock_blocking();
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&lock).lock_blocking();
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+            while *guard == 0 {
+                ::std::thread::sleep(Duration::from_millis(1));
+                let MayHaveChanged = guard.bump();
+            }
+        });
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.lock(), 2);
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn bumped_guards_must_be_inspected_again() {
+        let lock = RwLock::new(0);
+        let mut guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        let _ = guard.bump();
+        *guard.upgrade() = 1;
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn woken_guards_must_be_inspected_again() {
+        let (lock, condvar) = (Mutex::new(0), Condvar::new());
+        let mut guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                // Only acquired once the waiter released the mutex.
+                let _guard = lock.lock();
+                condvar.notify_one();
+            });
+            guard.wait(&condvar);
+        });
+        *guard.upgrade() = 1;
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    fn guards_waited_on_with_a_condition_can_be_upgraded() {
+        let (lock, condvar) = (Mutex::new(false), Condvar::new());
+        let mut guard = SafeLock::new(&lock).lock_blocking();
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                *lock.lock() = true;
+                condvar.notify_one();
+            });
+            guard.wait_while(&condvar, |ready| !*ready);
+        });
+        *guard.upgrade() = false;
+        assert!(!*lock.lock());
+    }
+
+    #[test]
+    fn unlocked_fair_releases_the_lock_while_running() {
+        let lock = RwLock::new(0);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let (guard, value) = guard.unlocked_fair(|| *lock.try_read().unwrap());
+        assert_eq!(*guard, value);
+        assert!(lock.try_read().is_none());
+    }
+
+    #[cfg(feature = "arc_lock")]
+    #[test]
+    fn weak_locks_fail_once_the_lock_is_dropped() {
+        let safe = SafeLock::new(Arc::new(RwLock::new(1)));
+        let guard = safe.downgrade().try_lock_blocking().unwrap();
+        let (weak, err) = safe.downgrade().try_lock_immediate_err().unwrap_err();
+        assert_eq!(err.kind(), LockErrorKind::WouldBlock);
+
+        // The guard keeps the lock alive until it is released.
+        drop(safe);
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        let (_, err) = weak.try_lock_blocking_err().unwrap_err();
+        assert_eq!(err.kind(), LockErrorKind::Dropped);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_projections_map_each_field() {
+        #[derive(SafeProject)]
+        struct Config {
+            name: String,
+            retries: u32,
+        }
+
+        #[derive(SafeProject)]
+        struct Pair(u32, u32);
+
+        let lock = RwLock::new(Config {
+            name: String::from("config"),
+            retries: 1,
+        });
+        let guard = SafeLock::new(&lock).lock_blocking().map_retries();
+        let retries = *guard;
+        *guard.upgrade() = retries + 1;
+        let guard = SafeLock::new(&lock).lock_blocking().map_name();
+        assert_eq!(*guard, "config");
+        drop(guard);
+        assert_eq!(lock.read().retries, 2);
+
+        let lock = RwLock::new(Pair(1, 2));
+        assert_eq!(*SafeLock::new(&lock).lock_blocking().map_1(), 2);
+        assert_eq!(*SafeLock::new(&lock).lock_blocking().map_0(), 1);
+    }
+
+    #[test]
+    fn condvar_waiters_wake_up_once_the_predicate_holds() {
+        let condvar = SafeCondvar::new(Vec::new());
+        ::std::thread::scope(|s| {
+            let consumer = s.spawn(|| {
+                let guard = condvar.wait_until(|queue| !queue.is_empty());
+                guard.upgrade().pop()
+            });
+            for value in [1, 2] {
+                let guard = condvar.lock().lock_blocking();
+                assert!(guard.len() < 2);
+                condvar.notify_one_after_upgrade(guard).push(value);
+            }
+            assert!(consumer.join().unwrap().is_some());
+        });
+        assert_eq!(condvar.into_inner().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not come from")]
+    fn condvars_reject_foreign_guards() {
+        let (condvar, other) = (SafeCondvar::new(0), Mutex::new(0));
+        let guard = SafeLock::new(&other).lock_blocking();
+        assert_eq!(*guard, 0);
+        let _ = condvar.notify_after_upgrade(guard);
+    }
+
+    #[test]
+    fn lock_when_fair_waits_for_the_predicate() {
+        let lock = Mutex::new(0);
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..3 {
+                    let guard = SafeLock::new(&lock).lock_blocking();
+                    let value = *guard;
+                    *guard.upgrade() = value + 1;
+                }
+            });
+            let guard = SafeLock::new(&lock).lock_when_fair(|value| *value == 3);
+            assert_eq!(*guard, 3);
+            let safe = guard.unlock_fair();
+            assert!(safe.try_lock_immediate().is_ok());
+        });
+
+        let lock = RwLock::new((0, 1));
+        let guard = SafeLock::new(&lock).lock_when_fair(|(first, _)| *first == 0);
+        let safe = guard.map(|(_, second)| second).unlock_fair();
+        assert!(
+            safe.lock_blocking()
+                .unlock_fair()
+                .try_lock_immediate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn recursive_reads_succeed_while_a_writer_waits() {
+        let lock = RecursiveRwLock::new(0);
+        let read = SafeLock::new(&lock).read_blocking();
+        ::std::thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&lock).lock_blocking();
+                let value = *guard;
+                *guard.upgrade() = value + 1;
+            });
+            // A waiting writer blocks the non-recursive shared acquisitions.
+            while lock.inner().try_read().is_some() {
+                ::std::thread::yield_now();
+            }
+            assert!(SafeLock::new(lock.inner()).try_read_immediate().is_err());
+            let recursive = SafeLock::new(lock.inner()).try_read_recursive().unwrap();
+            assert_eq!(*SafeLock::new(&lock).read_blocking() + *recursive, 0);
+            drop((read, recursive));
+        });
+        assert_eq!(lock.into_inner(), 1);
+    }
 }
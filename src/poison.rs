@@ -0,0 +1,200 @@
+use ::core::{
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use ::std::thread;
+
+use crate::{
+    LockError, LockErrorKind, SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Poisoning`] lock, reporting panics that happened during mutations.
+pub type PoisoningSafeLock<L> = SafeLock<Poisoning<L>>;
+
+/// A lock decorator poisoning the wrapped lock `L` when a thread panics while mutating its data.
+///
+/// The lock is poisoned when a guard is dropped during a panic after the data was accessed mutably
+/// through it, which is after it was upgraded. Subsequent acquisitions then fail with
+/// [`PoisoningError::Poisoned`] until the poison is [cleared](Self::clear_poison). This brings the
+/// poisoning semantics of `std` to locks that do not poison, such as the `parking_lot` ones.
+#[derive(Debug, Default)]
+pub struct Poisoning<L> {
+    lock: L,
+    poisoned: AtomicBool,
+}
+
+/// A guard returned by a [`Poisoning`] lock, poisoning it if dropped during a panic after a mutation.
+#[derive(Debug)]
+pub struct PoisoningGuard<'a, G> {
+    guard: G,
+    poisoned: &'a AtomicBool,
+    panicking: bool,
+    written: bool,
+}
+
+/// An error returned when acquiring a [`Poisoning`] lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoisoningError<E> {
+    /// The wrapped lock could not be acquired.
+    Lock(E),
+    /// The lock is poisoned. It is not held.
+    Poisoned,
+}
+
+impl<E: fmt::Display> fmt::Display for PoisoningError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lock(err) => err.fmt(f),
+            Self::Poisoned => f.write_str("poisoned lock: another task failed inside"),
+        }
+    }
+}
+
+impl<E: Error> Error for PoisoningError<E> {}
+
+impl<E: Into<LockError>> From<PoisoningError<E>> for LockError {
+    fn from(err: PoisoningError<E>) -> Self {
+        match err {
+            PoisoningError::Lock(err) => err.into(),
+            PoisoningError::Poisoned => LockErrorKind::Poisoned.into(),
+        }
+    }
+}
+
+impl<L> Poisoning<L> {
+    /// Wraps `lock` to poison it when a thread panics while mutating its data.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns whether the lock is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Clears the poisoned state of the lock.
+    ///
+    /// The data may be in an inconsistent state, which must be checked before upgrading the next
+    /// guard.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<G> Drop for PoisoningGuard<'_, G> {
+    fn drop(&mut self) {
+        if self.written && !self.panicking && thread::panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<G: Deref> Deref for PoisoningGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for PoisoningGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.written = true;
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Poisoning<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = PoisoningGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = PoisoningError<E>;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        _exclusive: bool,
+    ) -> Result<PoisoningGuard<'_, G>, PoisoningError<E>> {
+        let guard = res.map_err(PoisoningError::Lock)?;
+        if self.is_poisoned() {
+            return Err(PoisoningError::Poisoned);
+        }
+        Ok(PoisoningGuard {
+            guard,
+            poisoned: &self.poisoned,
+            panicking: thread::panicking(),
+            written: false,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Poisoning<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = PoisoningGuard<'a, _>;
+    type Error = PoisoningError<_>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    fn panic_while_held(lock: &Poisoning<Mutex<i32>>, mutate: bool) {
+        let res = thread::scope(|s| {
+            s.spawn(|| {
+                let Ok(guard) = SafeLock::new(lock).try_lock_blocking() else {
+                    panic!("the lock is poisoned");
+                };
+                if mutate {
+                    let value = *guard;
+                    let mut data = guard.upgrade();
+                    *data = value + 1;
+                    panic!("mutation failed");
+                }
+                panic!("inspection failed");
+            })
+            .join()
+        });
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn panics_after_a_mutation_poison_the_lock() {
+        let lock = Poisoning::new(Mutex::new(0));
+        panic_while_held(&lock, true);
+        assert!(lock.is_poisoned());
+        let (safe, err) = SafeLock::new(&lock).try_lock_blocking_err().unwrap_err();
+        assert_eq!(err, PoisoningError::Poisoned);
+        assert!(!lock.inner().is_locked());
+
+        lock.clear_poison();
+        let guard = safe.try_lock_immediate().unwrap();
+        assert_eq!(*guard, 1);
+    }
+
+    #[test]
+    fn panics_without_a_mutation_do_not_poison_the_lock() {
+        let lock = Poisoning::new(Mutex::new(0));
+        panic_while_held(&lock, false);
+        assert!(!lock.is_poisoned());
+        assert!(SafeLock::new(&lock).try_lock_immediate().is_ok());
+    }
+}
@@ -0,0 +1,364 @@
+use ::core::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+use ::std::io;
+
+use crate::{LockBlocking, LockImmediate, LockShared, SafeGuard, SafeLock, SafeReadGuard};
+
+/// A wrapper around a [`PthreadRwLock`], providing safe locking behavior.
+pub type SafeRwLock<'a, T> = SafeLock<&'a PthreadRwLock<T>>;
+pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a PthreadRwLock<T>, PthreadRwLockWriteGuard<'a, T>>;
+pub type SafeRwLockReadGuard<'a, T> =
+    SafeReadGuard<&'a PthreadRwLock<T>, PthreadRwLockReadGuard<'a, T>>;
+
+/// A thin RAII wrapper around a raw `pthread_rwlock_t`.
+///
+/// The lock is either allocated and initialized by this wrapper, in which case it is destroyed on
+/// drop, or borrowed from foreign code through [`from_raw`](RawPthreadRwLock::from_raw).
+pub struct RawPthreadRwLock {
+    raw: NonNull<libc::pthread_rwlock_t>,
+    owned: bool,
+}
+
+/// A reader-writer lock protecting a value of type `T` with a [`RawPthreadRwLock`].
+pub struct PthreadRwLock<T: ?Sized> {
+    raw: RawPthreadRwLock,
+    data: UnsafeCell<T>,
+}
+
+/// A guard holding a [`PthreadRwLock`] in write mode.
+pub struct PthreadRwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a PthreadRwLock<T>,
+    // Releasing a `pthread_rwlock_t` from another thread is undefined behavior.
+    _not_send: PhantomData<*mut ()>,
+}
+
+/// A guard holding a [`PthreadRwLock`] in read mode.
+pub struct PthreadRwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a PthreadRwLock<T>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+// SAFETY: `pthread_rwlock_t` is designed to be shared between threads.
+unsafe impl Send for RawPthreadRwLock {}
+unsafe impl Sync for RawPthreadRwLock {}
+
+// SAFETY: access to the data is synchronized by the lock, as for `std::sync::RwLock`.
+unsafe impl<T: ?Sized + Send> Send for PthreadRwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for PthreadRwLock<T> {}
+
+// SAFETY: a shared reference to a guard only hands out `&T`, which is fine to share when `T` is
+// `Sync`. Releasing the lock requires owning the guard, so it still happens on the locking thread.
+unsafe impl<T: ?Sized + Sync> Sync for PthreadRwLockWriteGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for PthreadRwLockReadGuard<'_, T> {}
+
+fn check(code: libc::c_int) -> io::Result<()> {
+    match code {
+        0 => Ok(()),
+        code => Err(io::Error::from_raw_os_error(code)),
+    }
+}
+
+impl RawPthreadRwLock {
+    /// Allocates and initializes a new lock with the default attributes.
+    pub fn new() -> Self {
+        // The lock is boxed since a `pthread_rwlock_t` must not be moved once used.
+        let raw = Box::new(libc::PTHREAD_RWLOCK_INITIALIZER);
+        Self {
+            raw: NonNull::from(Box::leak(raw)),
+            owned: true,
+        }
+    }
+
+    /// Wraps a lock owned by foreign code, such as a lock shared with C code.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to an initialized `pthread_rwlock_t` that outlives the returned value and is
+    /// not destroyed while it is in use. The lock is not destroyed when the returned value is dropped.
+    pub const unsafe fn from_raw(raw: NonNull<libc::pthread_rwlock_t>) -> Self {
+        Self { raw, owned: false }
+    }
+
+    /// Returns a pointer to the underlying `pthread_rwlock_t`.
+    pub const fn as_ptr(&self) -> *mut libc::pthread_rwlock_t {
+        self.raw.as_ptr()
+    }
+
+    fn write(&self) -> io::Result<()> {
+        // SAFETY: the lock is initialized and alive.
+        check(unsafe { libc::pthread_rwlock_wrlock(self.as_ptr()) })
+    }
+
+    fn try_write(&self) -> io::Result<()> {
+        // SAFETY: the lock is initialized and alive.
+        check(unsafe { libc::pthread_rwlock_trywrlock(self.as_ptr()) })
+    }
+
+    fn read(&self) -> io::Result<()> {
+        // SAFETY: the lock is initialized and alive.
+        check(unsafe { libc::pthread_rwlock_rdlock(self.as_ptr()) })
+    }
+
+    fn try_read(&self) -> io::Result<()> {
+        // SAFETY: the lock is initialized and alive.
+        check(unsafe { libc::pthread_rwlock_tryrdlock(self.as_ptr()) })
+    }
+
+    /// # Safety
+    ///
+    /// The lock must be held by the current thread.
+    unsafe fn unlock(&self) {
+        // SAFETY: guaranteed by the caller.
+        let code = unsafe { libc::pthread_rwlock_unlock(self.as_ptr()) };
+        debug_assert_eq!(code, 0, "failed to unlock a pthread rwlock");
+    }
+}
+
+impl Default for RawPthreadRwLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RawPthreadRwLock {
+    fn drop(&mut self) {
+        if self.owned {
+            // SAFETY: the lock was allocated in `new` and cannot be held anymore.
+            unsafe {
+                libc::pthread_rwlock_destroy(self.as_ptr());
+                drop(Box::from_raw(self.as_ptr()));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RawPthreadRwLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawPthreadRwLock")
+            .field("raw", &self.raw)
+            .field("owned", &self.owned)
+            .finish()
+    }
+}
+
+impl<T> PthreadRwLock<T> {
+    /// Creates a new lock protecting `value`.
+    pub fn new(value: T) -> Self {
+        Self::with_raw(RawPthreadRwLock::new(), value)
+    }
+
+    /// Creates a new lock protecting `value` with the provided raw lock.
+    pub const fn with_raw(raw: RawPthreadRwLock, value: T) -> Self {
+        Self {
+            raw,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> PthreadRwLock<T> {
+    /// Returns a reference to the raw lock.
+    pub const fn raw(&self) -> &RawPthreadRwLock {
+        &self.raw
+    }
+
+    /// Returns a mutable reference to the protected value, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: Default> Default for PthreadRwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PthreadRwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PthreadRwLock")
+            .field("raw", &self.raw)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> Drop for PthreadRwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the lock.
+        unsafe { self.lock.raw.unlock() }
+    }
+}
+
+impl<T: ?Sized> Drop for PthreadRwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the lock.
+        unsafe { self.lock.raw.unlock() }
+    }
+}
+
+impl<T: ?Sized> Deref for PthreadRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the guard holds the lock in write mode.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PthreadRwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the guard holds the lock in write mode.
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Deref for PthreadRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the guard holds the lock in read mode.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PthreadRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PthreadRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> LockBlocking for PthreadRwLock<T> {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = PthreadRwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.raw.write().map(|()| PthreadRwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl<T: ?Sized> LockImmediate for PthreadRwLock<T> {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = PthreadRwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.raw.try_write().map(|()| PthreadRwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl<T: ?Sized> LockShared for PthreadRwLock<T> {
+    type Error<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = io::Error
+    where
+        Self: 'a;
+    type Guard<'a>
+        = PthreadRwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.raw.read().map(|()| PthreadRwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.raw.try_read().map(|()| PthreadRwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::thread;
+
+    use super::*;
+    use crate::macros::{assert_impl, assert_not_impl};
+
+    assert_impl!(PthreadRwLock<u32>: Send);
+    assert_impl!(PthreadRwLock<u32>: Sync);
+    assert_not_impl!(PthreadRwLockWriteGuard<'static, u32>: Send);
+    assert_not_impl!(PthreadRwLockReadGuard<'static, u32>: Send);
+
+    fn increment(lock: &PthreadRwLock<u32>) {
+        let guard = SafeRwLock::new(lock).try_lock_blocking().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    #[test]
+    fn concurrent_increments_are_not_lost() {
+        let lock = PthreadRwLock::new(0);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| (0..100).for_each(|_| increment(&lock)));
+            }
+        });
+        assert_eq!(lock.into_inner(), 400);
+    }
+
+    #[test]
+    fn readers_exclude_writers() {
+        let lock = PthreadRwLock::new(0);
+        let reader = SafeRwLock::new(&lock).try_read_immediate().unwrap();
+        let other = SafeRwLock::new(&lock).try_read_immediate().unwrap();
+        let (safe, err) = SafeRwLock::new(&lock).try_lock_immediate_err().unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBUSY));
+        drop((reader, other));
+        assert!(safe.try_lock_immediate().is_ok());
+    }
+
+    #[test]
+    fn borrowed_raw_locks_are_not_destroyed() {
+        let mut raw = libc::PTHREAD_RWLOCK_INITIALIZER;
+        // SAFETY: the lock is initialized, and outlives the wrapper.
+        let lock = PthreadRwLock::with_raw(
+            unsafe { RawPthreadRwLock::from_raw(NonNull::from(&mut raw)) },
+            0,
+        );
+        increment(&lock);
+        assert_eq!(lock.into_inner(), 1);
+        // SAFETY: the lock is not held, and not used anymore.
+        assert_eq!(unsafe { libc::pthread_rwlock_destroy(&mut raw) }, 0);
+    }
+}
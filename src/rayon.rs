@@ -0,0 +1,47 @@
+use ::core::ops::Deref;
+
+use ::rayon::iter::IntoParallelRefIterator;
+
+use crate::{SafeGuard, SafeReadGuard};
+
+impl<L, G: Deref> SafeGuard<L, G> {
+    /// Returns a parallel iterator over the guarded collection, which counts as inspecting it.
+    ///
+    /// The data is only read, from the threads of the `rayon` pool, while the guard is held. The
+    /// guard can later be upgraded based on the outcome of the traversal.
+    pub fn par_iter<'s>(&'s self) -> <G::Target as IntoParallelRefIterator<'s>>::Iter
+    where
+        G::Target: IntoParallelRefIterator<'s>,
+    {
+        self.deref().par_iter()
+    }
+}
+
+impl<L, G: Deref> SafeReadGuard<L, G> {
+    /// Returns a parallel iterator over the guarded collection.
+    pub fn par_iter<'s>(&'s self) -> <G::Target as IntoParallelRefIterator<'s>>::Iter
+    where
+        G::Target: IntoParallelRefIterator<'s>,
+    {
+        self.deref().par_iter()
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+    use ::rayon::iter::ParallelIterator;
+
+    use crate::SafeLock;
+
+    #[test]
+    fn parallel_traversals_inspect_the_data() {
+        let lock = RwLock::new((1..=100).collect::<Vec<u32>>());
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let sum = guard.par_iter().sum::<u32>();
+        guard.upgrade().push(sum);
+
+        let guard = SafeLock::new(&lock).read_blocking();
+        assert_eq!(guard.par_iter().max(), Some(&5050));
+    }
+}
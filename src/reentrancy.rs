@@ -0,0 +1,194 @@
+#[cfg(feature = "reentrancy-checks")]
+use ::core::{any::type_name, cell::RefCell};
+use ::core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`NonReentrant`] lock, detecting same-thread re-entrancy.
+pub type NonReentrantSafeLock<L> = SafeLock<NonReentrant<L>>;
+
+#[cfg(feature = "reentrancy-checks")]
+::std::thread_local! {
+    static HELD_LOCKS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A lock decorator detecting recursive acquisitions of the wrapped lock `L` from the same thread.
+///
+/// Re-acquiring a `std::sync::RwLock` or `Mutex` from the thread already holding it deadlocks or
+/// panics depending on the platform. With the `reentrancy-checks` feature, every acquisition made through
+/// a [`NonReentrant`] is recorded for the current thread, and a recursive blocking acquisition panics
+/// with a message identifying the offending lock. Without the feature, this decorator is a plain
+/// passthrough.
+///
+/// Ownership is tracked per thread, so [`NonReentrantGuard`] is not `Send`: guards are released on the
+/// thread that acquired them.
+#[derive(Debug, Default)]
+pub struct NonReentrant<L>(L);
+
+/// A guard returned by a [`NonReentrant`] lock, releasing its ownership record when dropped.
+#[derive(Debug)]
+pub struct NonReentrantGuard<G> {
+    guard: G,
+    #[cfg(feature = "reentrancy-checks")]
+    addr: usize,
+    // The ownership record is released from the thread-local record of the thread dropping the guard.
+    _not_send: PhantomData<*const ()>,
+}
+
+// SAFETY: the marker only opts out of `Send`, sharing the guard is as safe as sharing `G`.
+unsafe impl<G: Sync> Sync for NonReentrantGuard<G> {}
+
+impl<L> NonReentrant<L> {
+    /// Wraps `lock` to detect recursive acquisitions.
+    pub const fn new(lock: L) -> Self {
+        Self(lock)
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.0
+    }
+
+    /// Panics if the current thread already holds this lock.
+    #[cfg(feature = "reentrancy-checks")]
+    #[track_caller]
+    fn check_not_held(&self) {
+        let addr = self as *const Self as usize;
+        let held = HELD_LOCKS.with_borrow(|held| held.contains(&addr));
+        assert!(
+            !held,
+            "recursive acquisition of lock `{}` at {:#x} from the thread already holding it",
+            type_name::<L>(),
+            addr
+        );
+    }
+
+    #[cfg(not(feature = "reentrancy-checks"))]
+    fn check_not_held(&self) {}
+}
+
+#[cfg(feature = "reentrancy-checks")]
+impl<G> Drop for NonReentrantGuard<G> {
+    fn drop(&mut self) {
+        let _ = HELD_LOCKS.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(index) = held.iter().rposition(|&addr| addr == self.addr) {
+                held.remove(index);
+            }
+        });
+    }
+}
+
+impl<G: Deref> Deref for NonReentrantGuard<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for NonReentrantGuard<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for NonReentrant<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = NonReentrantGuard<G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.0
+    }
+
+    #[track_caller]
+    fn before_blocking(&self) {
+        self.check_not_held();
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<NonReentrantGuard<G>, E> {
+        res.map(|guard| {
+            #[cfg(feature = "reentrancy-checks")]
+            {
+                let addr = self as *const Self as usize;
+                HELD_LOCKS.with_borrow_mut(|held| held.push(addr));
+                NonReentrantGuard {
+                    guard,
+                    addr,
+                    _not_send: PhantomData,
+                }
+            }
+            #[cfg(not(feature = "reentrancy-checks"))]
+            NonReentrantGuard {
+                guard,
+                _not_send: PhantomData,
+            }
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] NonReentrant<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = NonReentrantGuard<_>;
+}
+
+#[cfg(all(test, feature = "reentrancy-checks", feature = "std"))]
+mod tests {
+    use ::core::time::Duration;
+    use ::std::sync::Mutex;
+
+    use super::*;
+    use crate::{LockBlocking, LockImmediate, LockTimed, macros::assert_not_impl};
+
+    assert_not_impl!(NonReentrantGuard<()>: Send);
+
+    #[test]
+    #[should_panic(expected = "recursive acquisition")]
+    fn recursive_blocking_acquisitions_panic() {
+        let lock = NonReentrant::new(Mutex::new(()));
+        let _guard = lock.lock_blocking().unwrap();
+        let _ = lock.lock_blocking();
+    }
+
+    #[test]
+    #[should_panic(expected = "recursive acquisition")]
+    fn recursive_timed_acquisitions_panic() {
+        let lock = NonReentrant::new(Mutex::new(()));
+        let _guard = lock.lock_blocking().unwrap();
+        let _ = lock.lock_for(Duration::from_millis(10));
+    }
+
+    #[test]
+    fn sequential_acquisitions_are_allowed() {
+        let lock = NonReentrant::new(Mutex::new(()));
+        drop(lock.lock_blocking().unwrap());
+        drop(lock.lock_blocking().unwrap());
+    }
+
+    #[test]
+    fn distinct_locks_can_be_held_together() {
+        let (a, b) = (
+            NonReentrant::new(Mutex::new(())),
+            NonReentrant::new(Mutex::new(())),
+        );
+        let _a = a.lock_blocking().unwrap();
+        let _b = b.lock_blocking().unwrap();
+    }
+
+    #[test]
+    fn immediate_acquisitions_are_not_checked() {
+        let lock = NonReentrant::new(Mutex::new(()));
+        let _guard = lock.lock_blocking().unwrap();
+        assert!(lock.lock_immediate().is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use ::core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering, fence},
+};
+
+use crate::SafeLock;
+
+/// A [`SafeLock`] over a [`SeqLock`].
+pub type SafeSeqLock<'a, T> = SafeLock<&'a SeqLock<T>>;
+
+/// A sequence lock, whose readers never block writers nor each other.
+///
+/// Reading copies the data optimistically, without taking any lock, and validates the copy with a
+/// sequence counter that writers bump around their mutations. This suits small `Copy` data that is
+/// read much more often than it is written.
+///
+/// Like other sequence locks, a read may race with a writer, in which case its copy is discarded
+/// and the read is retried; the copy is never observed unless it is consistent.
+#[derive(Debug)]
+pub struct SeqLock<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: the data is only mutated through a `SeqLockWriteGuard`, of which there is at most one at a
+// time. Readers get copies of it, which requires `Send`, and the write guard can be sent to another
+// thread and hand out `&T` there, which requires `Sync`.
+unsafe impl<T: Copy + Send + Sync> Sync for SeqLock<T> {}
+
+/// A guard over an optimistic snapshot of the data of a [`SeqLock`], as returned by
+/// [`SafeSeqLock::read`].
+///
+/// Inspecting the snapshot does not hold any lock. [`upgrade`](Self::upgrade) takes the writer
+/// lock only if no writer intervened since the snapshot was taken, so the checks made on the
+/// snapshot are still valid once upgraded.
+#[derive(Debug)]
+pub struct SafeSeqLockGuard<'a, T> {
+    lock: SafeSeqLock<'a, T>,
+    value: T,
+    seq: usize,
+}
+
+/// A write guard over the data of a [`SeqLock`], allowing mutation.
+#[derive(Debug)]
+pub struct SeqLockWriteGuard<'a, T> {
+    lock: &'a SeqLock<T>,
+    seq: usize,
+}
+
+impl<T> SeqLock<T> {
+    /// Creates a new sequence lock protecting `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Returns a mutable reference to the protected data.
+    ///
+    /// No locking is needed since the mutable borrow guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: Copy> SeqLock<T> {
+    /// Returns a consistent copy of the data along with the sequence it was read at.
+    fn snapshot(&self) -> (T, usize) {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq & 1 == 1 {
+                spin_loop();
+                continue;
+            }
+            // SAFETY: the pointer is valid and `T: Copy` can be duplicated bitwise. The copy may be
+            // torn by a concurrent writer, so it is kept as a `MaybeUninit`, which can hold any bit
+            // pattern, until the sequence check below proves it consistent.
+            let value = unsafe { ptr::read_volatile(self.data.get().cast::<MaybeUninit<T>>()) };
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                // SAFETY: no writer intervened, so the copy is a valid `T` written by the last one.
+                return (unsafe { value.assume_init() }, seq);
+            }
+        }
+    }
+}
+
+impl<'a, T: Copy> SafeSeqLock<'a, T> {
+    /// Takes an optimistic snapshot of the data, without blocking writers.
+    ///
+    /// The returned guard allows read-only access to the snapshot. Mutation is not possible until
+    /// explicitly upgrading the guard, which fails if a writer intervened in between.
+    pub fn read(self) -> SafeSeqLockGuard<'a, T> {
+        let (value, seq) = self.0.snapshot();
+        SafeSeqLockGuard {
+            lock: self,
+            value,
+            seq,
+        }
+    }
+}
+
+impl<'a, T> SafeSeqLockGuard<'a, T> {
+    /// Takes the writer lock, provided the data did not change since the snapshot was taken.
+    ///
+    /// If another writer intervened, the snapshot is stale and this method returns `Err` with the
+    /// original [`SafeLock`], so that the data can be read and checked again.
+    pub fn upgrade(self) -> Result<SeqLockWriteGuard<'a, T>, SafeSeqLock<'a, T>> {
+        let lock = self.lock.0;
+        match lock.seq.compare_exchange(
+            self.seq,
+            self.seq.wrapping_add(1),
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                fence(Ordering::Release);
+                Ok(SeqLockWriteGuard {
+                    lock,
+                    seq: self.seq.wrapping_add(2),
+                })
+            }
+            Err(_) => Err(self.lock),
+        }
+    }
+
+    /// Discards the snapshot and returns the original [`SafeLock`], allowing further attempts.
+    pub fn unlock(self) -> SafeSeqLock<'a, T> {
+        self.lock
+    }
+}
+
+impl<T> Deref for SafeSeqLockGuard<'_, T> {
+    type Target = T;
+
+    /// Provides read-only access to the snapshot.
+    ///
+    /// Mutation is only possible after calling [`upgrade`](Self::upgrade).
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> Drop for SeqLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.seq.store(self.seq, Ordering::Release);
+    }
+}
+
+impl<T> Deref for SeqLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the writer lock is held, so no one else mutates the data.
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for SeqLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the writer lock is held, so this is the only reference allowed to mutate the data.
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::core::cell::Cell;
+    #[cfg(feature = "std")]
+    use ::std::thread;
+
+    use super::*;
+    use crate::macros::{assert_impl, assert_not_impl};
+
+    assert_impl!(SeqLock<u32>: Sync);
+    assert_not_impl!(SeqLock<Cell<u32>>: Sync);
+
+    #[test]
+    fn upgrade_succeeds_without_intervening_writer() {
+        let lock = SeqLock::new(1);
+        let guard = SafeLock::new(&lock).read();
+        assert_eq!(*guard, 1);
+        *guard.upgrade().ok().unwrap() += 1;
+        assert_eq!(lock.into_inner(), 2);
+    }
+
+    #[test]
+    fn upgrade_fails_after_intervening_writer() {
+        let lock = SeqLock::new(1);
+        let stale = SafeLock::new(&lock).read();
+        *SafeLock::new(&lock).read().upgrade().ok().unwrap() = 5;
+        let safe = stale.upgrade().err().unwrap();
+        assert_eq!(*safe.read(), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn snapshots_are_never_torn() {
+        let lock = SeqLock::new((0u64, 0u64));
+        thread::scope(|s| {
+            s.spawn(|| {
+                let mut written = 0;
+                while written < 10_000 {
+                    if let Ok(mut guard) = SafeLock::new(&lock).read().upgrade() {
+                        guard.0 += 1;
+                        guard.1 += 1;
+                        written += 1;
+                    }
+                }
+            });
+            for _ in 0..10_000 {
+                let (a, b) = *SafeLock::new(&lock).read();
+                assert_eq!(a, b);
+            }
+        });
+        assert_eq!(lock.into_inner(), (10_000, 10_000));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn snapshots_of_types_with_invalid_bit_patterns_are_valid() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Idle,
+            Busy(char),
+        }
+
+        let lock = SeqLock::new((State::Idle, false));
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..10_000u32 {
+                    let guard = SafeLock::new(&lock).read();
+                    let next = match *guard {
+                        (State::Idle, _) => {
+                            (State::Busy(char::from_digit(i % 10, 10).unwrap()), true)
+                        }
+                        (State::Busy(_), _) => (State::Idle, false),
+                    };
+                    if let Ok(mut guard) = guard.upgrade() {
+                        *guard = next;
+                    }
+                }
+            });
+            for _ in 0..10_000 {
+                let (state, busy) = *SafeLock::new(&lock).read();
+                assert_eq!(
+                    matches!(state, State::Busy(digit) if digit.is_ascii_digit()),
+                    busy
+                );
+            }
+        });
+    }
+}
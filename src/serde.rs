@@ -0,0 +1,147 @@
+use ::core::ops::Deref;
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{SafeGuard, SafeLock, SafeReadGuard};
+
+/// Serializes the value protected by the lock.
+///
+/// The lock is acquired internally, in shared mode for reader-writer locks, by the [`Serialize`]
+/// implementation of the backend.
+impl<L: Serialize> Serialize for SafeLock<L> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Deserializes an owned lock, such as an [`OwnedSafeRwLock`](crate::parking_lot::OwnedSafeRwLock).
+impl<'de, L: Deserialize<'de>> Deserialize<'de> for SafeLock<L> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        L::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Serializes the data held by the guard, which counts as inspecting it.
+impl<L, G: Deref<Target: Serialize>> Serialize for SafeGuard<L, G> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.deref().serialize(serializer)
+    }
+}
+
+impl<L, G: Deref<Target: Serialize>> Serialize for SafeReadGuard<L, G> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.deref().serialize(serializer)
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+    use ::serde::{
+        de::{IntoDeserializer, value::Error},
+        ser::{self, Impossible},
+    };
+
+    use super::*;
+    use crate::parking_lot::OwnedSafeRwLock;
+
+    /// A serializer only supporting `u32`s, which it returns.
+    struct U32Serializer;
+
+    macro_rules! unsupported {
+        ($($method:ident($($arg:ty),*) -> $ok:ty;)*) => {$(
+            fn $method(self, $(_: $arg),*) -> Result<$ok, Error> {
+                Err(ser::Error::custom("unsupported"))
+            }
+        )*};
+    }
+
+    impl Serializer for U32Serializer {
+        type Ok = u32;
+        type Error = Error;
+        type SerializeSeq = Impossible<u32, Error>;
+        type SerializeTuple = Impossible<u32, Error>;
+        type SerializeTupleStruct = Impossible<u32, Error>;
+        type SerializeTupleVariant = Impossible<u32, Error>;
+        type SerializeMap = Impossible<u32, Error>;
+        type SerializeStruct = Impossible<u32, Error>;
+        type SerializeStructVariant = Impossible<u32, Error>;
+
+        fn serialize_u32(self, value: u32) -> Result<u32, Error> {
+            Ok(value)
+        }
+
+        unsupported! {
+            serialize_bool(bool) -> u32;
+            serialize_i8(i8) -> u32;
+            serialize_i16(i16) -> u32;
+            serialize_i32(i32) -> u32;
+            serialize_i64(i64) -> u32;
+            serialize_u8(u8) -> u32;
+            serialize_u16(u16) -> u32;
+            serialize_u64(u64) -> u32;
+            serialize_f32(f32) -> u32;
+            serialize_f64(f64) -> u32;
+            serialize_char(char) -> u32;
+            serialize_str(&str) -> u32;
+            serialize_bytes(&[u8]) -> u32;
+            serialize_none() -> u32;
+            serialize_unit() -> u32;
+            serialize_unit_struct(&'static str) -> u32;
+            serialize_unit_variant(&'static str, u32, &'static str) -> u32;
+            serialize_seq(Option<usize>) -> Self::SerializeSeq;
+            serialize_tuple(usize) -> Self::SerializeTuple;
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+            serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<u32, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            value: &T,
+        ) -> Result<u32, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<u32, Error> {
+            Err(ser::Error::custom("unsupported"))
+        }
+    }
+
+    #[test]
+    fn locks_serialize_their_value() {
+        let lock = RwLock::new(1u32);
+        assert_eq!(SafeLock::new(&lock).serialize(U32Serializer), Ok(1));
+        let guard = SafeLock::new(&lock).read_blocking();
+        assert_eq!(guard.serialize(U32Serializer), Ok(1));
+    }
+
+    #[test]
+    fn serializing_a_guard_inspects_it() {
+        let lock = RwLock::new(1u32);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let value = guard.serialize(U32Serializer).unwrap();
+        *guard.upgrade() = value + 1;
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn owned_locks_deserialize() {
+        let deserializer = IntoDeserializer::<Error>::into_deserializer(3u32);
+        let lock = OwnedSafeRwLock::<u32>::deserialize(deserializer).unwrap();
+        assert_eq!(*lock.by_ref().read_blocking(), 3);
+    }
+}
@@ -0,0 +1,223 @@
+use ::core::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use ::std::time::Instant;
+
+use crate::{
+    SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// The number of buckets of the hold-time histogram of [`LockStats`].
+///
+/// Bucket `i` counts the guards held for less than `2^i` microseconds (and at least `2^(i - 1)`
+/// microseconds), the last bucket also counting every longer hold.
+pub const HOLD_TIME_BUCKETS: usize = 32;
+
+/// A [`SafeLock`] over a [`Stats`] lock, accumulating contention metrics.
+pub type StatsSafeLock<L> = SafeLock<Stats<L>>;
+
+/// A lock decorator accumulating contention metrics and hold-time statistics for the wrapped lock `L`.
+///
+/// Counters are updated with relaxed atomic operations and can be read at any time through
+/// [`stats`](Stats::stats).
+#[derive(Debug)]
+pub struct Stats<L> {
+    lock: L,
+    counters: Counters,
+}
+
+#[derive(Debug)]
+struct Counters {
+    acquisitions: AtomicU64,
+    immediate_failures: AtomicU64,
+    retries: AtomicU64,
+    hold_times: [AtomicU64; HOLD_TIME_BUCKETS],
+}
+
+/// A snapshot of the metrics accumulated by a [`Stats`] lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockStats {
+    /// The number of successful acquisitions, in any mode.
+    pub acquisitions: u64,
+    /// The number of immediate acquisitions that failed because the lock was unavailable.
+    pub immediate_failures: u64,
+    /// The number of exclusive acquisitions released without the data ever being mutated, which is
+    /// what happens when a guard is unlocked to retry later.
+    pub retries: u64,
+    /// The hold-time histogram, see [`HOLD_TIME_BUCKETS`].
+    pub hold_times: [u64; HOLD_TIME_BUCKETS],
+}
+
+/// A guard returned by a [`Stats`] lock, recording its hold time when dropped.
+#[derive(Debug)]
+pub struct StatsGuard<'a, G> {
+    guard: G,
+    counters: &'a Counters,
+    acquired_at: Instant,
+    exclusive: bool,
+    written: bool,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            acquisitions: AtomicU64::new(0),
+            immediate_failures: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            hold_times: [const { AtomicU64::new(0) }; HOLD_TIME_BUCKETS],
+        }
+    }
+
+    fn record_hold_time(&self, held: Duration) {
+        let micros = held.as_micros();
+        let bucket = (u128::BITS - micros.leading_zeros()) as usize;
+        self.hold_times[bucket.min(HOLD_TIME_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<L> Stats<L> {
+    /// Wraps `lock` to accumulate metrics about its usage.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            counters: Counters::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns a snapshot of the metrics accumulated so far.
+    pub fn stats(&self) -> LockStats {
+        let counters = &self.counters;
+        LockStats {
+            acquisitions: counters.acquisitions.load(Ordering::Relaxed),
+            immediate_failures: counters.immediate_failures.load(Ordering::Relaxed),
+            retries: counters.retries.load(Ordering::Relaxed),
+            hold_times: ::core::array::from_fn(|i| counters.hold_times[i].load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Resets every metric to zero.
+    pub fn reset(&self) {
+        let counters = &self.counters;
+        counters.acquisitions.store(0, Ordering::Relaxed);
+        counters.immediate_failures.store(0, Ordering::Relaxed);
+        counters.retries.store(0, Ordering::Relaxed);
+        for bucket in &counters.hold_times {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<G> Drop for StatsGuard<'_, G> {
+    fn drop(&mut self) {
+        self.counters.record_hold_time(self.acquired_at.elapsed());
+        if self.exclusive && !self.written {
+            self.counters.retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<G: Deref> Deref for StatsGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for StatsGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.written = true;
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Stats<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = StatsGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, exclusive: bool) -> Result<StatsGuard<'_, G>, E> {
+        res.map(|guard| {
+            self.counters.acquisitions.fetch_add(1, Ordering::Relaxed);
+            StatsGuard {
+                guard,
+                counters: &self.counters,
+                acquired_at: Instant::now(),
+                exclusive,
+                written: false,
+            }
+        })
+    }
+
+    fn wrap_immediate<G, E>(
+        &self,
+        res: Result<G, E>,
+        exclusive: bool,
+    ) -> Result<StatsGuard<'_, G>, E> {
+        if res.is_err() {
+            self.counters
+                .immediate_failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        self.wrap(res, exclusive)
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Stats<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = StatsGuard<'a, _>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+
+    use super::*;
+
+    #[test]
+    fn acquisitions_are_counted() {
+        let lock = SafeLock::new(Stats::new(RwLock::new(0)));
+        let guard = lock.by_ref().lock_blocking();
+        assert!(lock.by_ref().try_lock_immediate().is_err());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        drop(lock.by_ref().lock_blocking());
+        drop(lock.by_ref().read_blocking());
+
+        let stats = lock.0.stats();
+        assert_eq!(stats.acquisitions, 3);
+        assert_eq!(stats.immediate_failures, 1);
+        assert_eq!(stats.retries, 1);
+        assert_eq!(stats.hold_times.iter().sum::<u64>(), 3);
+
+        lock.0.reset();
+        assert_eq!(lock.0.stats().acquisitions, 0);
+    }
+
+    #[test]
+    fn hold_times_are_bucketed_by_powers_of_two() {
+        let counters = Counters::new();
+        counters.record_hold_time(Duration::ZERO);
+        counters.record_hold_time(Duration::from_micros(3));
+        counters.record_hold_time(Duration::MAX);
+        let buckets = counters.hold_times.map(AtomicU64::into_inner);
+        assert_eq!(buckets[0], 1);
+        assert_eq!(buckets[2], 1);
+        assert_eq!(buckets[HOLD_TIME_BUCKETS - 1], 1);
+    }
+}
@@ -1,24 +1,693 @@
-use ::std::sync::{PoisonError, RwLock, RwLockWriteGuard, TryLockError};
+use ::core::{
+    borrow::Borrow,
+    fmt,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+use ::std::{
+    sync::{
+        Arc, Condvar, LockResult, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard,
+        RwLockWriteGuard, TryLockError, TryLockResult, WaitTimeoutResult,
+    },
+    thread,
+    time::Instant,
+};
 
-use crate::{LockBlocking, LockImmediate, SafeGuard, SafeLock};
+use crate::{
+    LockBlocking, LockError, LockImmediate, LockShared, LockTimed, SafeGuard, SafeLock,
+    SafeReadGuard, TimedLockError,
+};
 
 pub type SafeRwLock<'a, T> = SafeLock<&'a RwLock<T>>;
 pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a RwLock<T>, RwLockWriteGuard<'a, T>>;
+pub type SafeRwLockReadGuard<'a, T> = SafeReadGuard<&'a RwLock<T>, RwLockReadGuard<'a, T>>;
+pub type SafeMutex<'a, T> = SafeLock<&'a Mutex<T>>;
+pub type SafeMutexGuard<'a, T> = SafeGuard<&'a Mutex<T>, MutexGuard<'a, T>>;
 
-impl<'a, T> LockBlocking for &'a RwLock<T> {
-    type Error = PoisonError<RwLockWriteGuard<'a, T>>;
-    type Guard = RwLockWriteGuard<'a, T>;
+/// A [`SafeLock`] owning a [`RwLock`] from `std`, which can be declared as a `static`.
+pub type OwnedSafeRwLock<T> = SafeLock<RwLock<T>>;
+/// A [`SafeLock`] owning a [`Mutex`] from `std`, which can be declared as a `static`.
+pub type OwnedSafeMutex<T> = SafeLock<Mutex<T>>;
 
-    fn lock_blocking(&self) -> Result<Self::Guard, Self::Error> {
+/// A wrapper around an [`Arc`]-shared [`RwLock`] from `std`, whose guards are `'static`.
+pub type SafeArcRwLock<T> = SafeLock<Arc<RwLock<T>>>;
+pub type SafeArcRwLockGuard<T> = SafeGuard<Arc<RwLock<T>>, ArcRwLockWriteGuard<T>>;
+
+/// A write guard over an [`Arc`]-shared [`RwLock`], keeping its own reference to the [`Arc`].
+///
+/// `std` has no owned guards, so this bundles the [`Arc`] with a guard borrowing from it. The data
+/// must be `'static`, since the borrow is erased.
+pub struct ArcRwLockWriteGuard<T: ?Sized + 'static> {
+    // Declared first so that it is dropped before the `Arc` it borrows from.
+    guard: RwLockWriteGuard<'static, T>,
+    lock: Arc<RwLock<T>>,
+}
+
+/// The delay the timed acquisitions wait after the first failed attempt.
+const TIMED_MIN_DELAY: Duration = Duration::from_micros(1);
+/// The maximum delay the timed acquisitions wait between two attempts.
+const TIMED_MAX_DELAY: Duration = Duration::from_millis(1);
+
+/// Emulates a timed acquisition, since `std` locks have none.
+///
+/// `try_lock` is retried until it succeeds or `deadline` is reached, parking the current thread
+/// with an exponentially increasing timeout in between. Expiring the deadline is reported as
+/// [`TimedLockError::TimedOut`].
+fn lock_until<G>(
+    deadline: Instant,
+    mut try_lock: impl FnMut() -> TryLockResult<G>,
+) -> Result<G, TimedLockError<PoisonError<G>>> {
+    let mut delay = TIMED_MIN_DELAY;
+    loop {
+        match try_lock() {
+            Ok(guard) => return Ok(guard),
+            Err(TryLockError::Poisoned(err)) => return Err(TimedLockError::Lock(err)),
+            Err(TryLockError::WouldBlock) => {}
+        }
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return Err(TimedLockError::TimedOut);
+        };
+        thread::park_timeout(delay.min(remaining));
+        delay = (delay * 2).min(TIMED_MAX_DELAY);
+    }
+}
+
+impl<T: ?Sized> LockBlocking for RwLock<T> {
+    type Error<'a>
+        = PoisonError<RwLockWriteGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
         self.write()
     }
 }
 
-impl<'a, T> LockImmediate for &'a RwLock<T> {
-    type Error = TryLockError<RwLockWriteGuard<'a, T>>;
-    type Guard = RwLockWriteGuard<'a, T>;
+impl<T: ?Sized> LockImmediate for RwLock<T> {
+    type Error<'a>
+        = TryLockError<RwLockWriteGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
 
-    fn lock_immediate(&self) -> Result<Self::Guard, Self::Error> {
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
         self.try_write()
     }
 }
+
+/// Timed acquisitions are emulated by retrying with a timeout, and fail with
+/// [`TimedLockError::TimedOut`] once the timeout expires.
+impl<T: ?Sized> LockTimed for RwLock<T> {
+    type Error<'a>
+        = TimedLockError<PoisonError<RwLockWriteGuard<'a, T>>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.lock_until(deadline),
+            None => self.write().map_err(TimedLockError::Lock),
+        }
+    }
+
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        lock_until(deadline, || self.try_write())
+    }
+}
+
+impl<T: ?Sized> LockShared for RwLock<T> {
+    type Error<'a>
+        = PoisonError<RwLockReadGuard<'a, T>>
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = TryLockError<RwLockReadGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockReadGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.read()
+    }
+
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.try_read()
+    }
+}
+
+impl<T: ?Sized> LockBlocking for Mutex<T> {
+    type Error<'a>
+        = PoisonError<MutexGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.lock()
+    }
+}
+
+impl<T: ?Sized> LockImmediate for Mutex<T> {
+    type Error<'a>
+        = TryLockError<MutexGuard<'a, T>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_lock()
+    }
+}
+
+/// Timed acquisitions are emulated by retrying with a timeout, and fail with
+/// [`TimedLockError::TimedOut`] once the timeout expires.
+impl<T: ?Sized> LockTimed for Mutex<T> {
+    type Error<'a>
+        = TimedLockError<PoisonError<MutexGuard<'a, T>>>
+    where
+        Self: 'a;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_for(&self, timeout: Duration) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.lock_until(deadline),
+            None => self.lock().map_err(TimedLockError::Lock),
+        }
+    }
+
+    fn lock_until(&self, deadline: Instant) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        lock_until(deadline, || self.try_lock())
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for SafeRwLockGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for SafeMutexGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T> From<T> for OwnedSafeRwLock<T> {
+    fn from(value: T) -> Self {
+        Self::const_new(value)
+    }
+}
+
+impl<T> From<T> for OwnedSafeMutex<T> {
+    fn from(value: T) -> Self {
+        Self::const_new(value)
+    }
+}
+
+impl<T> OwnedSafeRwLock<T> {
+    /// Creates a new [`SafeLock`] owning a [`RwLock`] protecting `value`, in a `const` context.
+    pub const fn const_new(value: T) -> Self {
+        SafeLock(RwLock::new(value))
+    }
+
+    /// Returns a mutable reference to the protected data.
+    ///
+    /// No locking is needed since the mutable borrow guarantees exclusive access. The reference is
+    /// still handed back inside the [`PoisonError`] if the lock is poisoned.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.0.get_mut()
+    }
+}
+
+impl<T> OwnedSafeMutex<T> {
+    /// Creates a new [`SafeLock`] owning a [`Mutex`] protecting `value`, in a `const` context.
+    pub const fn const_new(value: T) -> Self {
+        SafeLock(Mutex::new(value))
+    }
+
+    /// Returns a mutable reference to the protected data.
+    ///
+    /// No locking is needed since the mutable borrow guarantees exclusive access. The reference is
+    /// still handed back inside the [`PoisonError`] if the mutex is poisoned.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.0.get_mut()
+    }
+}
+
+impl<T> SafeMutexGuard<'_, T> {
+    /// Blocks the current thread until the condition variable is notified.
+    ///
+    /// The lock is released while waiting and re-acquired before returning. The returned guard is
+    /// in its read-only state, so the data must be inspected again before upgrading. If the mutex
+    /// was poisoned, the guard is still handed back inside the [`PoisonError`].
+    pub fn wait(self, condvar: &Condvar) -> LockResult<Self> {
+        let SafeGuard { lock, guard, .. } = self;
+        match condvar.wait(guard) {
+            Ok(guard) => Ok(SafeGuard::new(lock, guard)),
+            Err(err) => Err(PoisonError::new(SafeGuard::new(lock, err.into_inner()))),
+        }
+    }
+
+    /// Blocks the current thread until `condition` returns `false`, waiting on the condition variable.
+    ///
+    /// The condition only gets read-only access to the data, which is checked before the first wait
+    /// and after every wakeup. Evaluating it counts as inspecting the data, so the returned guard is
+    /// ready to be upgraded.
+    pub fn wait_while<F>(self, condvar: &Condvar, mut condition: F) -> LockResult<Self>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let SafeGuard { lock, guard, .. } = self;
+        match condvar.wait_while(guard, |value| condition(value)) {
+            Ok(guard) => Ok(Self::with_guard(lock, guard, true)),
+            Err(err) => Err(PoisonError::new(Self::with_guard(
+                lock,
+                err.into_inner(),
+                true,
+            ))),
+        }
+    }
+
+    /// Blocks the current thread until the condition variable is notified or the timeout expires.
+    ///
+    /// Returns the guard along with a [`WaitTimeoutResult`] telling whether the timeout elapsed.
+    pub fn wait_timeout(
+        self,
+        condvar: &Condvar,
+        timeout: Duration,
+    ) -> LockResult<(Self, WaitTimeoutResult)> {
+        let SafeGuard { lock, guard, .. } = self;
+        match condvar.wait_timeout(guard, timeout) {
+            Ok((guard, res)) => Ok((SafeGuard::new(lock, guard), res)),
+            Err(err) => {
+                let (guard, res) = err.into_inner();
+                Err(PoisonError::new((SafeGuard::new(lock, guard), res)))
+            }
+        }
+    }
+}
+
+impl<'a, T: ?Sized> SafeRwLock<'a, T> {
+    /// Acquires the lock in write mode, ignoring poisoning, and returns a guard for the locked data.
+    ///
+    /// If a thread panicked while holding the lock, the data may be in an inconsistent state, which
+    /// must be checked during inspection before upgrading.
+    pub fn lock_blocking_ignore_poison(self) -> SafeRwLockGuard<'a, T> {
+        let guard = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        SafeGuard::new(self, guard)
+    }
+
+    /// Acquires the lock in write mode and returns a guard for the locked data.
+    ///
+    /// If the lock is poisoned, the guard is still handed back inside the [`PoisonError`], and can
+    /// be recovered with [`PoisonError::into_inner`].
+    pub fn lock_blocking_or_poison(self) -> LockResult<SafeRwLockGuard<'a, T>> {
+        match self.0.write() {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err(PoisonError::new(SafeGuard::new(self, err.into_inner()))),
+        }
+    }
+
+    /// Checks the data in shared mode, and mutates it in write mode only if the check still holds.
+    ///
+    /// `std` has no upgradable reads, so the read lock is released before acquiring the write lock.
+    /// `check` is therefore run again once the write lock is held, and `write` is only called with
+    /// the output of that second run. Returns the result of `write` if it was called.
+    pub fn check_then_write<P, R>(
+        &self,
+        check: impl Fn(&T) -> Option<P>,
+        write: impl FnOnce(&mut T, P) -> R,
+    ) -> Result<Option<R>, LockError> {
+        if check(&*self.0.read()?).is_none() {
+            return Ok(None);
+        }
+        let mut guard = self.0.write()?;
+        Ok(check(&guard).map(|checked| write(&mut guard, checked)))
+    }
+
+    /// Returns whether the lock is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.0.is_poisoned()
+    }
+
+    /// Clears the poisoned state of the lock.
+    pub fn clear_poison(&self) {
+        self.0.clear_poison();
+    }
+}
+
+impl<T: ?Sized + 'static> SafeArcRwLock<T> {
+    /// Acquires the lock in write mode, ignoring poisoning, and returns a `'static` guard for the
+    /// locked data.
+    ///
+    /// If a thread panicked while holding the lock, the data may be in an inconsistent state, which
+    /// must be checked during inspection before upgrading.
+    pub fn lock_blocking_ignore_poison(self) -> SafeArcRwLockGuard<T> {
+        let guard = ArcRwLockWriteGuard::write(&self.0).unwrap_or_else(PoisonError::into_inner);
+        SafeGuard::new(self, guard)
+    }
+
+    /// Acquires the lock in write mode and returns a `'static` guard for the locked data.
+    ///
+    /// The returned guard keeps its own reference to the [`Arc`], so it does not borrow anything.
+    /// If the lock is poisoned, the guard is still handed back inside the [`PoisonError`].
+    pub fn lock_blocking_or_poison(self) -> LockResult<SafeArcRwLockGuard<T>> {
+        match ArcRwLockWriteGuard::write(&self.0) {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err(PoisonError::new(SafeGuard::new(self, err.into_inner()))),
+        }
+    }
+
+    /// Attempts to acquire the lock in write mode without blocking and returns a `'static` guard if
+    /// successful.
+    ///
+    /// If the lock is already held or is poisoned, this method will return `Err(self)`.
+    pub fn try_lock_immediate(self) -> Result<SafeArcRwLockGuard<T>, Self> {
+        match ArcRwLockWriteGuard::try_write(&self.0) {
+            Some(guard) => Ok(SafeGuard::new(self, guard)),
+            None => Err(self),
+        }
+    }
+}
+
+impl<T: ?Sized + 'static> ArcRwLockWriteGuard<T> {
+    fn write(lock: &Arc<RwLock<T>>) -> LockResult<Self> {
+        let lock = Arc::clone(lock);
+        // SAFETY: the lock lives in the allocation of the `Arc`, which the guard keeps alive and
+        // outlives it.
+        let rwlock: &'static RwLock<T> = unsafe { &*Arc::as_ptr(&lock) };
+        match rwlock.write() {
+            Ok(guard) => Ok(Self { guard, lock }),
+            Err(err) => Err(PoisonError::new(Self {
+                guard: err.into_inner(),
+                lock,
+            })),
+        }
+    }
+
+    fn try_write(lock: &Arc<RwLock<T>>) -> Option<Self> {
+        let lock = Arc::clone(lock);
+        // SAFETY: see `write`.
+        let rwlock: &'static RwLock<T> = unsafe { &*Arc::as_ptr(&lock) };
+        let guard = rwlock.try_write().ok()?;
+        Some(Self { guard, lock })
+    }
+
+    /// Returns the [`Arc`] the guard was acquired from.
+    pub fn rwlock(&self) -> &Arc<RwLock<T>> {
+        &self.lock
+    }
+}
+
+impl<T: ?Sized + 'static> Deref for ArcRwLockWriteGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized + 'static> DerefMut for ArcRwLockWriteGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized + fmt::Debug + 'static> fmt::Debug for ArcRwLockWriteGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.guard, f)
+    }
+}
+
+impl<'a, T: ?Sized> SafeMutex<'a, T> {
+    /// Acquires the mutex, ignoring poisoning, and returns a guard for the locked data.
+    ///
+    /// If a thread panicked while holding the mutex, the data may be in an inconsistent state, which
+    /// must be checked during inspection before upgrading.
+    pub fn lock_blocking_ignore_poison(self) -> SafeMutexGuard<'a, T> {
+        let guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        SafeGuard::new(self, guard)
+    }
+
+    /// Acquires the mutex and returns a guard for the locked data.
+    ///
+    /// If the mutex is poisoned, the guard is still handed back inside the [`PoisonError`], and can
+    /// be recovered with [`PoisonError::into_inner`].
+    pub fn lock_blocking_or_poison(self) -> LockResult<SafeMutexGuard<'a, T>> {
+        match self.0.lock() {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(err) => Err(PoisonError::new(SafeGuard::new(self, err.into_inner()))),
+        }
+    }
+
+    /// Returns whether the mutex is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.0.is_poisoned()
+    }
+
+    /// Clears the poisoned state of the mutex.
+    pub fn clear_poison(&self) {
+        self.0.clear_poison();
+    }
+}
+
+/// A [`SafeGuard`] is only [`Send`] if the underlying guard is, which a `std` guard is not:
+///
+/// ```compile_fail,E0277
+/// use std::{sync::Mutex, thread};
+/// use safe_lock::std::SafeMutex;
+///
+/// let lock = Mutex::new(0);
+/// let guard = SafeMutex::new(&lock).lock_blocking_ignore_poison();
+/// thread::scope(|s| {
+///     s.spawn(move || drop(guard));
+/// });
+/// ```
+#[cfg(doctest)]
+pub struct GuardsAreNotSend;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LockErrorKind;
+
+    crate::macros::assert_impl!(SafeMutexGuard<'static, ()>: Sync);
+    crate::macros::assert_not_impl!(SafeMutexGuard<'static, ()>: Send);
+    crate::macros::assert_not_impl!(SafeRwLockGuard<'static, ()>: Send);
+
+    #[test]
+    fn lock_for_does_not_overflow_the_deadline() {
+        let rwlock = RwLock::new(0);
+        assert!(rwlock.lock_for(Duration::MAX).is_ok());
+        let mutex = Mutex::new(0);
+        assert!(mutex.lock_for(Duration::MAX).is_ok());
+    }
+
+    #[test]
+    fn lock_for_times_out_while_the_lock_is_held() {
+        let mutex = Mutex::new(0);
+        let _guard = mutex.lock().unwrap();
+        assert!(matches!(
+            mutex.lock_for(Duration::from_millis(10)),
+            Err(TimedLockError::TimedOut)
+        ));
+    }
+
+    #[test]
+    fn expired_timeouts_convert_to_timed_out_errors() {
+        let mutex = Mutex::new(0);
+        let _guard = mutex.lock().unwrap();
+        let err = LockError::from(
+            SafeLock::new(&mutex)
+                .try_lock_blocking_for_err(Duration::from_millis(10))
+                .unwrap_err(),
+        );
+        assert_eq!(err.kind(), LockErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn wait_while_releases_the_mutex_until_notified() {
+        let (mutex, condvar) = (Mutex::new(false), Condvar::new());
+        thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&mutex).lock_blocking_or_poison().unwrap();
+                let ready = *guard;
+                *guard.upgrade() = !ready;
+                condvar.notify_one();
+            });
+            let guard = SafeLock::new(&mutex).lock_blocking_or_poison().unwrap();
+            let guard = guard.wait_while(&condvar, |ready| !*ready).unwrap();
+            assert!(*guard);
+        });
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    fn guards_returned_by_wait_while_can_be_upgraded() {
+        let (mutex, condvar) = (Mutex::new(false), Condvar::new());
+        thread::scope(|s| {
+            s.spawn(|| {
+                let guard = SafeLock::new(&mutex).lock_blocking_or_poison().unwrap();
+                assert!(!*guard);
+                *guard.upgrade() = true;
+                condvar.notify_one();
+            });
+            let guard = SafeLock::new(&mutex).lock_blocking_or_poison().unwrap();
+            let guard = guard.wait_while(&condvar, |ready| !*ready).unwrap();
+            *guard.upgrade() = false;
+        });
+        assert!(!*mutex.lock().unwrap());
+    }
+
+    #[test]
+    fn wait_timeout_hands_the_guard_back() {
+        let (mutex, condvar) = (Mutex::new(0), Condvar::new());
+        let guard = SafeLock::new(&mutex).lock_blocking_or_poison().unwrap();
+        let (guard, res) = guard
+            .wait_timeout(&condvar, Duration::from_millis(10))
+            .unwrap();
+        assert!(res.timed_out());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*mutex.lock().unwrap(), 1);
+    }
+
+    fn poison<T>(lock: &RwLock<T>) {
+        let _ = ::std::panic::catch_unwind(|| {
+            let _guard = lock.write();
+            panic!("poisoning the lock");
+        });
+    }
+
+    #[test]
+    fn backend_errors_convert_to_lock_errors() {
+        fn increment(lock: &RwLock<u32>) -> Result<u32, LockError> {
+            let guard = SafeLock::new(lock).try_lock_immediate_err()?;
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+            Ok(value + 1)
+        }
+
+        let lock = RwLock::new(0);
+        assert_eq!(increment(&lock), Ok(1));
+        let read = lock.read().unwrap();
+        let err = increment(&lock).unwrap_err();
+        assert_eq!(err.kind(), LockErrorKind::WouldBlock);
+        assert_eq!((err.name(), err.location()), (None, None));
+        drop(read);
+
+        poison(&lock);
+        let err = increment(&lock).unwrap_err();
+        assert_eq!(err.kind(), LockErrorKind::Poisoned);
+        assert_eq!(err.to_string(), "the lock is poisoned");
+    }
+
+    #[test]
+    fn poisoned_locks_can_still_be_acquired() {
+        let lock = RwLock::new(0);
+        poison(&lock);
+        let safe = SafeLock::new(&lock);
+        assert!(safe.is_poisoned());
+
+        let err = safe.lock_blocking_or_poison().unwrap_err();
+        let guard = err.into_inner();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+
+        let safe = SafeLock::new(&lock);
+        let guard = safe.lock_blocking_ignore_poison();
+        assert_eq!(*guard, 1);
+        let safe = guard.unlock();
+        safe.clear_poison();
+        assert!(!safe.is_poisoned());
+        assert!(safe.lock_blocking_or_poison().is_ok());
+    }
+
+    #[test]
+    fn owned_locks_are_const_constructible() {
+        static COUNTER: OwnedSafeMutex<u32> = OwnedSafeMutex::const_new(0);
+        let guard = COUNTER.by_ref().lock_blocking_or_poison().unwrap();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(*COUNTER.inner().lock().unwrap(), 1);
+
+        let mut config = OwnedSafeRwLock::const_new("default");
+        *config.get_mut().unwrap() = "configured";
+        assert_eq!(config.into_inner().into_inner().unwrap(), "configured");
+    }
+
+    #[test]
+    fn check_then_write_checks_again_under_the_write_lock() {
+        let lock = RwLock::new(vec![1]);
+        let safe = SafeLock::new(&lock);
+        let missing = |values: &Vec<u32>| (!values.contains(&2)).then_some(2);
+        assert_eq!(
+            safe.check_then_write(missing, |values, value| values.push(value)),
+            Ok(Some(()))
+        );
+        assert_eq!(
+            safe.check_then_write(missing, |_, _| unreachable!()),
+            Ok(None::<()>)
+        );
+
+        let checks = ::core::cell::Cell::new(0);
+        let racing = |values: &Vec<u32>| {
+            checks.set(checks.get() + 1);
+            (checks.get() == 1).then_some(values.len())
+        };
+        assert_eq!(
+            safe.check_then_write(racing, |_, _| unreachable!()),
+            Ok(None::<()>)
+        );
+        assert_eq!(checks.get(), 2);
+
+        poison(&lock);
+        let err = safe.check_then_write(missing, |_, _| ()).unwrap_err();
+        assert_eq!(err.kind(), LockErrorKind::Poisoned);
+        assert_eq!(*lock.read().unwrap_or_else(PoisonError::into_inner), [1, 2]);
+    }
+
+    #[test]
+    fn arc_guards_keep_the_lock_alive() {
+        let lock = Arc::new(RwLock::new(1));
+        let guard = SafeLock::new(Arc::clone(&lock))
+            .lock_blocking_or_poison()
+            .unwrap();
+        assert!(::core::ptr::eq(&**guard.as_guard().rwlock(), &*lock));
+        assert!(
+            SafeLock::new(Arc::clone(&lock))
+                .try_lock_immediate()
+                .is_err()
+        );
+
+        let weak = Arc::downgrade(&lock);
+        drop(lock);
+        assert!(weak.upgrade().is_some());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert!(weak.upgrade().is_none());
+    }
+}
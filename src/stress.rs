@@ -0,0 +1,238 @@
+use ::core::hint;
+use ::std::{sync::Barrier, thread};
+
+use crate::SafeLock;
+
+/// A harness hammering a [`SafeLock`] from several threads, meant for stress-testing retry logic.
+///
+/// Every phase spawns [`threads`](Self::threads) workers, released together, each running the
+/// workload [`iterations`](Self::iterations) times. Before every iteration, workers wait for a
+/// pseudo-random jitter derived from the [`seed`](Self::seed), which shuffles the interleavings,
+/// and the workload can call [`Worker::jitter`] itself to widen the window between an inspection
+/// and the upgrade. Once every worker of a phase is done, the invariant is checked on the quiescent
+/// lock.
+///
+/// The seed makes the jitter reproducible, but the interleavings still depend on the scheduler of
+/// the operating system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stress {
+    threads: usize,
+    iterations: usize,
+    phases: usize,
+    seed: u64,
+    max_jitter: u32,
+}
+
+/// The state of a worker thread of a [`Stress`] run, passed to the workload.
+#[derive(Debug)]
+pub struct Worker {
+    id: usize,
+    phase: usize,
+    iteration: usize,
+    rng: u64,
+    max_jitter: u32,
+}
+
+impl Stress {
+    /// Creates a harness running a single phase of 1000 iterations on each of 4 threads, with a
+    /// jitter of up to 64 spins.
+    pub const fn new() -> Self {
+        Self {
+            threads: 4,
+            iterations: 1000,
+            phases: 1,
+            seed: 0,
+            max_jitter: 64,
+        }
+    }
+
+    /// Sets the number of worker threads.
+    pub const fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the number of times each worker runs the workload during a phase.
+    pub const fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets the number of phases, between which the invariant is checked.
+    pub const fn phases(mut self, phases: usize) -> Self {
+        self.phases = phases;
+        self
+    }
+
+    /// Sets the seed of the jitter.
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the maximum number of spins of a jitter. Zero disables the jitter.
+    pub const fn max_jitter(mut self, max_jitter: u32) -> Self {
+        self.max_jitter = max_jitter;
+        self
+    }
+
+    /// Runs `work` concurrently on `lock`, checking `invariant` after every phase.
+    ///
+    /// `invariant` is given the index of the phase that just ended, and is expected to panic when
+    /// the invariant does not hold. A panic in a worker or in `invariant` is propagated to the
+    /// caller.
+    pub fn run<L, W, I>(&self, lock: &SafeLock<L>, work: W, mut invariant: I)
+    where
+        L: Sync,
+        W: Fn(&SafeLock<L>, &mut Worker) + Sync,
+        I: FnMut(&SafeLock<L>, usize),
+    {
+        for phase in 0..self.phases {
+            let barrier = Barrier::new(self.threads);
+            thread::scope(|scope| {
+                for id in 0..self.threads {
+                    let mut worker = self.worker(id, phase);
+                    let (barrier, work) = (&barrier, &work);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        for iteration in 0..self.iterations {
+                            worker.iteration = iteration;
+                            worker.jitter();
+                            work(lock, &mut worker);
+                        }
+                    });
+                }
+            });
+            invariant(lock, phase);
+        }
+    }
+
+    fn worker(&self, id: usize, phase: usize) -> Worker {
+        let stream = (phase as u64) << 32 | id as u64;
+        Worker {
+            id,
+            phase,
+            iteration: 0,
+            rng: splitmix64(self.seed ^ splitmix64(stream)),
+            max_jitter: self.max_jitter,
+        }
+    }
+}
+
+impl Default for Stress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Worker {
+    /// Returns the index of the worker, from zero to the number of threads.
+    pub const fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the index of the current phase.
+    pub const fn phase(&self) -> usize {
+        self.phase
+    }
+
+    /// Returns the index of the current iteration within the phase.
+    pub const fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// Returns the next pseudo-random number of the worker, derived from the seed of the run.
+    pub fn next_u64(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        splitmix64(self.rng)
+    }
+
+    /// Waits for a pseudo-random jitter, either spinning or yielding to the scheduler.
+    pub fn jitter(&mut self) {
+        if self.max_jitter == 0 {
+            return;
+        }
+        let rand = self.next_u64();
+        if rand >> 63 == 1 {
+            thread::yield_now();
+        } else {
+            for _ in 0..rand % (u64::from(self.max_jitter) + 1) {
+                hint::spin_loop();
+            }
+        }
+    }
+}
+
+/// The finalizer of the SplitMix64 generator.
+fn splitmix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use super::*;
+    use crate::parking_lot::OwnedSafeRwLock;
+
+    #[test]
+    fn checked_increments_never_overshoot() {
+        const LIMIT: usize = 1500;
+        let stress = Stress::new().iterations(500).phases(2);
+        let lock = OwnedSafeRwLock::const_new((0, 0));
+        stress.run(
+            &lock,
+            |lock, worker| {
+                let guard = lock.by_ref().lock_blocking();
+                if guard.0 < LIMIT {
+                    worker.jitter();
+                    let mut pair = guard.upgrade();
+                    pair.0 += 1;
+                    pair.1 += 1;
+                }
+            },
+            |lock, _| {
+                let pair = *lock.by_ref().read_blocking();
+                assert_eq!(pair, (LIMIT, LIMIT));
+            },
+        );
+    }
+
+    #[test]
+    fn workers_draw_reproducible_streams() {
+        let draw = |stress: Stress, id, phase| {
+            let mut worker = stress.worker(id, phase);
+            [worker.next_u64(), worker.next_u64()]
+        };
+        let stress = Stress::new().seed(7);
+        assert_eq!(draw(stress, 1, 0), draw(stress, 1, 0));
+        assert_ne!(draw(stress, 1, 0), draw(stress, 2, 0));
+        assert_ne!(draw(stress, 1, 0), draw(stress, 1, 1));
+        assert_ne!(draw(stress, 1, 0), draw(stress.seed(8), 1, 0));
+    }
+
+    #[test]
+    fn every_worker_runs_every_iteration() {
+        let stress = Stress::new()
+            .threads(3)
+            .iterations(10)
+            .phases(2)
+            .max_jitter(0);
+        let lock = OwnedSafeRwLock::const_new(Vec::new());
+        stress.run(
+            &lock,
+            |lock, worker| {
+                let guard = lock.by_ref().lock_blocking();
+                let len = guard.len();
+                guard
+                    .upgrade()
+                    .push((worker.phase(), worker.id(), worker.iteration(), len));
+            },
+            |lock, phase| assert_eq!(lock.by_ref().read_blocking().len(), 30 * (phase + 1)),
+        );
+        let mut runs = lock.into_inner().into_inner();
+        runs.sort_unstable();
+        runs.dedup_by_key(|&mut (phase, id, iteration, _)| (phase, id, iteration));
+        assert_eq!(runs.len(), 60);
+    }
+}
@@ -0,0 +1,281 @@
+use ::core::{
+    convert::Infallible,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    pin::pin,
+};
+use ::std::sync::Arc;
+
+use tokio::sync::{Notify, OwnedRwLockWriteGuard, RwLock, RwLockWriteGuard, TryLockError};
+
+use crate::{
+    LockAsync, LockError, LockErrorKind, LockImmediate, SafeGuard, SafeLock,
+    future::LockFuture,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A wrapper around [`RwLock`](RwLock) from `tokio`, providing safe locking behavior.
+pub type SafeRwLock<'a, T> = SafeLock<&'a RwLock<T>>;
+pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a RwLock<T>, RwLockWriteGuard<'a, T>>;
+
+/// A wrapper around an [`Arc`]-shared [`RwLock`] from `tokio`, whose guards are `'static`.
+pub type SafeOwnedRwLock<T> = SafeLock<Arc<RwLock<T>>>;
+pub type SafeOwnedRwLockGuard<T> = SafeGuard<Arc<RwLock<T>>, OwnedRwLockWriteGuard<T>>;
+
+/// A [`SafeLock`] over a [`Notifying`] lock, which can be awaited until a predicate holds.
+pub type NotifyingSafeLock<L> = SafeLock<Notifying<L>>;
+
+/// A lock decorator notifying the tasks waiting in [`lock_when_async`](SafeLock::lock_when_async)
+/// whenever a guard of the wrapped lock `L` that was upgraded is released.
+#[derive(Debug)]
+pub struct Notifying<L> {
+    lock: L,
+    notify: Notify,
+}
+
+/// A guard returned by a [`Notifying`] lock.
+///
+/// When dropped after being upgraded, the wrapped guard is released first, then the waiting tasks
+/// are notified.
+#[derive(Debug)]
+pub struct NotifyingGuard<'a, G> {
+    guard: ManuallyDrop<G>,
+    notify: &'a Notify,
+    written: bool,
+}
+
+impl From<TryLockError> for LockError {
+    fn from(_: TryLockError) -> Self {
+        LockErrorKind::WouldBlock.into()
+    }
+}
+
+impl<T: ?Sized> LockAsync for RwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    async fn lock_async(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        Ok(self.write().await)
+    }
+}
+
+impl<T: ?Sized> LockImmediate for RwLock<T> {
+    type Error<'a>
+        = TryLockError
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.try_write()
+    }
+}
+
+impl<T: ?Sized> SafeOwnedRwLock<T> {
+    /// Asynchronously acquires the lock in write mode and returns a `'static` guard for the locked data.
+    ///
+    /// The returned guard keeps its own reference to the [`Arc`], so it does not borrow anything
+    /// and can be held across task boundaries. Mutation is not possible until explicitly upgrading the guard.
+    /// The acquisition can be cancelled while recovering the handle, see [`LockFuture`].
+    pub fn lock_async(
+        self,
+    ) -> LockFuture<Arc<RwLock<T>>, impl Future<Output = OwnedRwLockWriteGuard<T>> + use<T>> {
+        let lock = Arc::clone(&self.0);
+        LockFuture::new(self, lock.write_owned())
+    }
+
+    /// Attempts to acquire the lock in write mode without waiting and returns a `'static` guard if successful.
+    ///
+    /// If the lock is already held, this method will return `Err(self)`.
+    pub fn try_lock_immediate(self) -> Result<SafeOwnedRwLockGuard<T>, Self> {
+        match Arc::clone(&self.0).try_write_owned() {
+            Ok(guard) => Ok(SafeGuard::new(self, guard)),
+            Err(_) => Err(self),
+        }
+    }
+}
+
+impl<L> Notifying<L> {
+    /// Wraps `lock` to notify waiting tasks of its mutations.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            notify: Notify::const_new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<G> Drop for NotifyingGuard<'_, G> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if self.written {
+            self.notify.notify_waiters();
+        }
+    }
+}
+
+impl<G: Deref> Deref for NotifyingGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for NotifyingGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.written = true;
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Notifying<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = NotifyingGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<NotifyingGuard<'_, G>, E> {
+        res.map(|guard| NotifyingGuard {
+            guard: ManuallyDrop::new(guard),
+            notify: &self.notify,
+            written: false,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Notifying<L> => LockAsync, LockImmediate;
+    type Guard = NotifyingGuard<'a, _>;
+}
+
+impl<'a, L> SafeLock<&'a Notifying<L>> {
+    /// Asynchronously acquires the lock in write mode once `pred` holds for the locked data, and
+    /// returns a guard for it.
+    ///
+    /// Whenever the predicate does not hold, the lock is released and the task waits until a guard
+    /// of the lock that was upgraded is released, before checking again. The returned guard is ready
+    /// to be upgraded.
+    #[allow(clippy::type_complexity)]
+    pub async fn lock_when_async<F, T>(
+        self,
+        mut pred: F,
+    ) -> SafeGuard<&'a Notifying<L>, NotifyingGuard<'a, L::Guard<'a>>>
+    where
+        L: LockAsync<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnMut(&T) -> bool,
+    {
+        let notify = &self.0.notify;
+        let mut safe = self;
+        loop {
+            // Registering before checking ensures no notification is missed in between.
+            let mut notified = pin!(notify.notified());
+            notified.as_mut().enable();
+            let guard = safe.lock_async().await;
+            if pred(&*guard) {
+                return guard;
+            }
+            safe = guard.unlock();
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::tokio::task;
+
+    use super::*;
+
+    async fn increment(lock: &Notifying<RwLock<i32>>) {
+        let guard = SafeLock::new(lock).lock_async().await;
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    #[tokio::test]
+    async fn owned_guards_move_across_tasks() {
+        let lock = Arc::new(RwLock::new(0));
+        let guard = SafeOwnedRwLock::new(Arc::clone(&lock)).lock_async().await;
+        let safe = SafeOwnedRwLock::new(Arc::clone(&lock))
+            .try_lock_immediate()
+            .unwrap_err();
+        task::spawn(async move {
+            let value = *guard;
+            *guard.upgrade() = value + 1;
+        })
+        .await
+        .unwrap();
+        assert_eq!(*safe.try_lock_immediate().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn waiters_are_woken_by_mutations() {
+        let lock = Notifying::new(RwLock::new(0));
+        let waiter = async {
+            let guard = SafeLock::new(&lock)
+                .lock_when_async(|value| *value == 3)
+                .await;
+            *guard.upgrade() = 0;
+        };
+        let writer = async {
+            for _ in 0..3 {
+                task::yield_now().await;
+                increment(&lock).await;
+            }
+        };
+        ::tokio::join!(waiter, writer);
+        assert_eq!(*lock.inner().read().await, 0);
+    }
+
+    #[tokio::test]
+    async fn read_only_guards_do_not_wake_waiters() {
+        let lock = Notifying::new(RwLock::new(0));
+        let checks = ::core::cell::Cell::new(0);
+        let waiter = async {
+            let guard = SafeLock::new(&lock)
+                .lock_when_async(|value| {
+                    checks.set(checks.get() + 1);
+                    *value == 1
+                })
+                .await;
+            assert_eq!(*guard, 1);
+        };
+        let writer = async {
+            task::yield_now().await;
+            let guard = SafeLock::new(&lock).lock_async().await;
+            assert_eq!(*guard, 0);
+            drop(guard);
+            for _ in 0..3 {
+                task::yield_now().await;
+            }
+            assert_eq!(checks.get(), 1);
+            increment(&lock).await;
+        };
+        ::tokio::join!(waiter, writer);
+        assert_eq!(checks.get(), 2);
+    }
+}
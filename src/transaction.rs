@@ -0,0 +1,113 @@
+use ::core::ops::{Deref, DerefMut};
+
+use crate::{SafeGuard, SafeLock};
+
+/// A [`SafeGuard`] staging mutations on a private copy of the locked data.
+///
+/// The guard dereferences to the staged copy, which can be mutated freely while the shared data
+/// stays read-only. [`commit`](Self::commit) upgrades the guard and replaces the shared data with
+/// the staged copy, while [`rollback`](Self::rollback) discards it. A panic while staging never
+/// leaves the shared data half-modified.
+#[derive(Debug)]
+pub struct TransactionalGuard<L, G, T> {
+    inner: SafeGuard<L, G>,
+    staged: T,
+}
+
+impl<L, G> SafeGuard<L, G> {
+    /// Starts a transaction over a copy of the locked data.
+    pub fn into_transaction<T>(self) -> TransactionalGuard<L, G, T>
+    where
+        G: Deref<Target = T>,
+        T: Clone,
+    {
+        let staged = (*self).clone();
+        TransactionalGuard {
+            inner: self,
+            staged,
+        }
+    }
+}
+
+impl<L, G, T> TransactionalGuard<L, G, T> {
+    /// Returns the shared data, as it was before the transaction.
+    pub fn original(&self) -> &T
+    where
+        G: Deref<Target = T>,
+    {
+        &self.inner
+    }
+
+    /// Replaces the shared data with the staged copy, and returns the guard.
+    ///
+    /// The lock is never released in between, so the returned guard observes the committed data.
+    pub fn commit(self) -> SafeGuard<L, G>
+    where
+        G: DerefMut<Target = T>,
+    {
+        let (inner, ()) = self.inner.with_upgraded(|data| *data = self.staged);
+        inner
+    }
+
+    /// Discards the staged copy, and returns the guard over the untouched shared data.
+    pub fn rollback(self) -> SafeGuard<L, G> {
+        self.inner
+    }
+
+    /// Discards the staged copy, releases the lock, and returns the original [`SafeLock`].
+    pub fn unlock(self) -> SafeLock<L> {
+        self.inner.unlock()
+    }
+}
+
+impl<L, G, T> Deref for TransactionalGuard<L, G, T> {
+    type Target = T;
+
+    /// Provides read-only access to the staged copy.
+    fn deref(&self) -> &Self::Target {
+        &self.staged
+    }
+}
+
+impl<L, G, T> DerefMut for TransactionalGuard<L, G, T> {
+    /// Provides mutable access to the staged copy, leaving the shared data untouched.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.staged
+    }
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::RwLock;
+    use ::std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+
+    #[test]
+    fn committed_transactions_replace_the_data() {
+        let lock = RwLock::new(vec![1]);
+        let mut transaction = SafeLock::new(&lock).lock_blocking().into_transaction();
+        transaction.push(2);
+        assert_eq!(transaction.original(), &[1]);
+        let guard = transaction.commit();
+        assert_eq!(*guard, [1, 2]);
+        drop(guard);
+        assert_eq!(*lock.read(), [1, 2]);
+    }
+
+    #[test]
+    fn rolled_back_transactions_leave_the_data_untouched() {
+        let lock = RwLock::new(vec![1]);
+        let mut transaction = SafeLock::new(&lock).lock_blocking().into_transaction();
+        transaction.clear();
+        assert_eq!(*transaction.rollback(), [1]);
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut transaction = SafeLock::new(&lock).lock_blocking().into_transaction();
+            transaction.push(2);
+            panic!("staging failed");
+        }));
+        assert!(res.is_err());
+        assert_eq!(*lock.read(), [1]);
+    }
+}
@@ -0,0 +1,186 @@
+use ::core::{
+    convert::Infallible,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    LockBlocking, SafeGuard, SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Versioned`] lock, tracking whether the data changed between two locks.
+pub type VersionedSafeLock<L> = SafeLock<Versioned<L>>;
+
+/// A lock decorator maintaining a generation counter, bumped every time the data of the wrapped
+/// lock `L` is accessed mutably.
+///
+/// Unlocking a guard with [`unlock_with_token`](SafeGuard::unlock_with_token) returns a
+/// [`VersionToken`], which [`relock_validate`](SafeLock::relock_validate) later compares against
+/// the current generation. If nothing was mutated in between, the new guard is ready to be upgraded
+/// without inspecting the data again, letting retry loops skip expensive validations.
+///
+/// Only mutations made through this decorator are tracked.
+#[derive(Debug)]
+pub struct Versioned<L> {
+    lock: L,
+    version: AtomicU64,
+}
+
+/// The generation of a [`Versioned`] lock when a guard was unlocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersionToken(u64);
+
+/// A guard returned by a [`Versioned`] lock, bumping its generation on the first mutable access.
+#[derive(Debug)]
+pub struct VersionedGuard<'a, G> {
+    guard: G,
+    version: &'a AtomicU64,
+    bumped: bool,
+}
+
+impl<L> Versioned<L> {
+    /// Wraps `lock` to track its generation.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns the current generation of the lock.
+    pub fn version(&self) -> VersionToken {
+        VersionToken(self.version.load(Ordering::Relaxed))
+    }
+}
+
+impl<'a, L> SafeLock<&'a Versioned<L>> {
+    /// Acquires the lock in write mode, telling whether the data changed since `token` was issued.
+    ///
+    /// Returns the guard along with `true` if nothing was mutated since the guard that issued
+    /// `token` was unlocked. In this case, the checks made before still hold and the guard can be
+    /// upgraded without inspecting the data again.
+    pub fn relock_validate(
+        self,
+        token: VersionToken,
+    ) -> (
+        SafeGuard<&'a Versioned<L>, VersionedGuard<'a, L::Guard<'a>>>,
+        bool,
+    )
+    where
+        L: LockBlocking<Error<'a> = Infallible>,
+    {
+        let guard = self.lock_blocking();
+        let unchanged = guard.lock.0.version() == token;
+        if unchanged {
+            guard.mark_inspected();
+        }
+        (guard, unchanged)
+    }
+}
+
+impl<'a, L, G> SafeGuard<&'a Versioned<L>, VersionedGuard<'a, G>> {
+    /// Releases the lock, returning the original [`SafeLock`] along with a [`VersionToken`] for
+    /// [`relock_validate`](SafeLock::relock_validate).
+    pub fn unlock_with_token(self) -> (SafeLock<&'a Versioned<L>>, VersionToken) {
+        let token = self.lock.0.version();
+        (self.unlock(), token)
+    }
+}
+
+impl<G: Deref> Deref for VersionedGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for VersionedGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !self.bumped {
+            self.bumped = true;
+            self.version.fetch_add(1, Ordering::Relaxed);
+        }
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Versioned<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = VersionedGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<VersionedGuard<'_, G>, E> {
+        res.map(|guard| VersionedGuard {
+            guard,
+            version: &self.version,
+            bumped: false,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Versioned<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = VersionedGuard<'a, _>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::Mutex;
+
+    use super::*;
+
+    fn increment(lock: &Versioned<Mutex<i32>>) {
+        let guard = SafeLock::new(lock).lock_blocking();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    #[test]
+    fn unchanged_data_skips_the_inspection() {
+        let lock = Versioned::new(Mutex::new(0));
+        let guard = SafeLock::new(&lock).lock_blocking();
+        assert_eq!(*guard, 0);
+        let (safe, token) = guard.unlock_with_token();
+        drop(SafeLock::new(&lock).lock_blocking());
+
+        let (guard, unchanged) = safe.relock_validate(token);
+        assert!(unchanged);
+        *guard.upgrade() = 1;
+        assert_ne!(lock.version(), token);
+    }
+
+    #[test]
+    fn mutations_invalidate_the_token() {
+        let lock = Versioned::new(Mutex::new(0));
+        let token = lock.version();
+        increment(&lock);
+        let (guard, unchanged) = SafeLock::new(&lock).relock_validate(token);
+        assert!(!unchanged);
+        assert_eq!(*guard, 1);
+    }
+
+    #[cfg(feature = "debug-checks")]
+    #[test]
+    #[should_panic(expected = "without inspecting the locked data")]
+    fn invalidated_guards_must_be_inspected() {
+        let lock = Versioned::new(Mutex::new(0));
+        let token = lock.version();
+        increment(&lock);
+        let (guard, _) = SafeLock::new(&lock).relock_validate(token);
+        *guard.upgrade() = 2;
+    }
+}
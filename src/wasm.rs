@@ -0,0 +1,139 @@
+use ::core::{
+    cell::{Ref, RefCell, RefMut},
+    convert::Infallible,
+};
+
+use crate::{LockBlocking, LockImmediate, LockShared, SafeGuard, SafeLock, SafeReadGuard};
+
+/// A wrapper around a [`WasmRwLock`], providing safe locking behavior on single-threaded targets.
+pub type SafeRwLock<'a, T> = SafeLock<&'a WasmRwLock<T>>;
+pub type SafeRwLockGuard<'a, T> = SafeGuard<&'a WasmRwLock<T>, RefMut<'a, T>>;
+pub type SafeRwLockReadGuard<'a, T> = SafeReadGuard<&'a WasmRwLock<T>, Ref<'a, T>>;
+
+/// A reader-writer lock for targets without threads, such as `wasm32-unknown-unknown`.
+///
+/// The lock is a [`RefCell`] and never actually blocks. Since a contended blocking acquisition can
+/// only come from the thread already holding the lock, it would deadlock, and panics instead.
+///
+/// The lock is [`Sync`] on WebAssembly targets built without the `atomics` target feature only, so
+/// that it can be declared as a `static` there. It remains usable, but not shareable, on others.
+#[derive(Debug, Default)]
+pub struct WasmRwLock<T: ?Sized>(RefCell<T>);
+
+// SAFETY: without the `atomics` target feature, WebAssembly has a single thread.
+#[cfg(all(target_family = "wasm", not(target_feature = "atomics")))]
+unsafe impl<T: ?Sized> Sync for WasmRwLock<T> {}
+
+impl<T> WasmRwLock<T> {
+    /// Creates a new lock protecting `value`.
+    pub const fn new(value: T) -> Self {
+        Self(RefCell::new(value))
+    }
+
+    /// Consumes the lock, returning the protected value.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ?Sized> WasmRwLock<T> {
+    /// Returns a mutable reference to the protected value, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut()
+    }
+}
+
+impl<T: ?Sized> LockBlocking for WasmRwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        match self.0.try_borrow_mut() {
+            Ok(guard) => Ok(guard),
+            Err(_) => panic!("deadlock: the lock is already held by the only thread"),
+        }
+    }
+}
+
+impl<T: ?Sized> LockImmediate for WasmRwLock<T> {
+    type Error<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = RefMut<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn lock_immediate(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        self.0.try_borrow_mut().map_err(|_| ())
+    }
+}
+
+impl<T: ?Sized> LockShared for WasmRwLock<T> {
+    type Error<'a>
+        = Infallible
+    where
+        Self: 'a;
+    type ImmediateError<'a>
+        = ()
+    where
+        Self: 'a;
+    type Guard<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+
+    #[track_caller]
+    fn read_blocking(&self) -> Result<Self::Guard<'_>, Self::Error<'_>> {
+        match self.0.try_borrow() {
+            Ok(guard) => Ok(guard),
+            Err(_) => panic!("deadlock: the lock is already held by the only thread"),
+        }
+    }
+
+    #[track_caller]
+    fn read_immediate(&self) -> Result<Self::Guard<'_>, Self::ImmediateError<'_>> {
+        self.0.try_borrow().map_err(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::assert_not_impl;
+
+    #[cfg(not(target_family = "wasm"))]
+    assert_not_impl!(WasmRwLock<u32>: Sync);
+
+    #[test]
+    fn readers_exclude_writers() {
+        let lock = WasmRwLock::new(0);
+        let reader = SafeRwLock::new(&lock).read_blocking();
+        let other = SafeRwLock::new(&lock).read_blocking();
+        let safe = SafeRwLock::new(&lock).try_lock_immediate().unwrap_err();
+        drop((reader, other));
+
+        let guard = safe.lock_blocking();
+        assert!(SafeRwLock::new(&lock).try_read_immediate().is_err());
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+        assert_eq!(lock.into_inner(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "deadlock")]
+    fn contended_blocking_acquisitions_panic() {
+        let lock = WasmRwLock::new(0);
+        let _guard = SafeRwLock::new(&lock).lock_blocking();
+        let _reader = SafeRwLock::new(&lock).read_blocking();
+    }
+}
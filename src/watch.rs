@@ -0,0 +1,460 @@
+use ::core::{
+    convert::Infallible,
+    future::Future,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use ::std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    LockBlocking, LockImmediate, SafeGuard, SafeLock, SafeReadGuard,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Watch`] lock, such as `SafeWatch<RwLock<T>>`, whose changes can be
+/// waited on.
+pub type SafeWatch<L> = SafeLock<Watch<L>>;
+
+/// A lock decorator turning the wrapped lock `L` into a change-notification channel.
+///
+/// Every guard that accesses the data mutably, which is after it was upgraded, bumps the version
+/// of the lock when released, and wakes up the threads and tasks waiting for a change. This covers
+/// the pattern of inspecting the data and, if it is stale, waiting for an update: the guard is
+/// released with [`wait_for_change`](SafeGuard::wait_for_change), which returns the [`SafeLock`]
+/// once another guard changed the data, ready to be locked again. Waiting until a predicate on the
+/// data holds is wrapped up in [`lock_when`](SafeLock::lock_when).
+///
+/// Only mutations made through this decorator are notified.
+#[derive(Debug)]
+pub struct Watch<L> {
+    lock: L,
+    notifier: Notifier,
+}
+
+#[derive(Debug)]
+struct Notifier {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Debug)]
+struct State {
+    version: u64,
+    releases: u64,
+    wakers: Vec<Waker>,
+    contended: Vec<Waker>,
+}
+
+/// A guard returned by a [`Watch`] lock, notifying the waiters when released after a mutation.
+#[derive(Debug)]
+pub struct WatchGuard<'a, G> {
+    guard: ManuallyDrop<G>,
+    notifier: &'a Notifier,
+    seen: u64,
+    changed: bool,
+}
+
+/// A future waiting for the version of a [`Watch`] lock to change, see
+/// [`wait_for_change_async`](Watch::wait_for_change_async).
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct WaitForChange<'a> {
+    notifier: &'a Notifier,
+    seen: u64,
+}
+
+/// A future waiting for any guard of a [`Watch`] lock to be released.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+struct WaitForRelease<'a> {
+    notifier: &'a Notifier,
+    seen: u64,
+}
+
+impl Notifier {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                version: 0,
+                releases: 0,
+                wakers: Vec::new(),
+                contended: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn release(&self, changed: bool) {
+        let wakers = {
+            let mut state = self.state();
+            state.releases += 1;
+            let mut wakers = ::core::mem::take(&mut state.contended);
+            if changed {
+                state.version += 1;
+                wakers.append(&mut state.wakers);
+            }
+            wakers
+        };
+        if changed {
+            self.changed.notify_all();
+        }
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn wait(&self, seen: u64) -> u64 {
+        let state = self.state();
+        let state = self
+            .changed
+            .wait_while(state, |state| state.version == seen)
+            .unwrap_or_else(PoisonError::into_inner);
+        state.version
+    }
+}
+
+impl<L> Watch<L> {
+    /// Wraps `lock` to notify waiters of its changes.
+    pub const fn new(lock: L) -> Self {
+        Self {
+            lock,
+            notifier: Notifier::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// Returns the current version of the lock, bumped by every release following a mutation.
+    pub fn version(&self) -> u64 {
+        self.notifier.state().version
+    }
+
+    /// Blocks the current thread until the version of the lock differs from `seen`, and returns the
+    /// new version.
+    pub fn wait_for_change(&self, seen: u64) -> u64 {
+        self.notifier.wait(seen)
+    }
+
+    /// Waits until the version of the lock differs from `seen`, resolving to the new version.
+    ///
+    /// The future is runtime-agnostic.
+    pub const fn wait_for_change_async(&self, seen: u64) -> WaitForChange<'_> {
+        WaitForChange {
+            notifier: &self.notifier,
+            seen,
+        }
+    }
+}
+
+impl<'a, L> SafeLock<&'a Watch<L>> {
+    /// Acquires the lock in write mode once `pred` holds for the locked data, and returns a guard
+    /// for it.
+    ///
+    /// Whenever the predicate does not hold, the lock is released and the current thread parks until
+    /// a guard changes the data, before checking again. Changes made while the predicate is being
+    /// checked are not missed. The returned guard is ready to be upgraded.
+    #[track_caller]
+    pub fn lock_when<F, T>(
+        self,
+        mut pred: F,
+    ) -> SafeGuard<&'a Watch<L>, WatchGuard<'a, L::Guard<'a>>>
+    where
+        L: LockBlocking<Error<'a> = Infallible>,
+        L::Guard<'a>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnMut(&T) -> bool,
+    {
+        let mut safe = self;
+        loop {
+            let guard = safe.lock_blocking();
+            if pred(&guard) {
+                return guard;
+            }
+            safe = guard.wait_for_change();
+        }
+    }
+
+    /// Acquires the lock in write mode once `pred` holds for the locked data, and resolves to a
+    /// guard for it.
+    ///
+    /// This is the asynchronous version of [`lock_when`](Self::lock_when). The lock is only ever
+    /// acquired without blocking: whenever it is unavailable, the task waits until a guard of the
+    /// lock is released, before trying again. Any error of the immediate acquisition counts as the
+    /// lock being unavailable.
+    pub async fn lock_when_async<F, T>(
+        self,
+        mut pred: F,
+    ) -> SafeGuard<&'a Watch<L>, WatchGuard<'a, L::Guard<'a>>>
+    where
+        L: LockImmediate,
+        L::Guard<'a>: Deref<Target = T>,
+        T: ?Sized,
+        F: FnMut(&T) -> bool,
+    {
+        let mut safe = self;
+        loop {
+            // Reading the releases before trying ensures none is missed in between.
+            let releases = safe.0.notifier.state().releases;
+            safe = match safe.try_lock_immediate() {
+                Ok(guard) if pred(&guard) => return guard,
+                Ok(guard) => guard.wait_for_change_async().await,
+                Err(safe) => {
+                    WaitForRelease {
+                        notifier: &safe.0.notifier,
+                        seen: releases,
+                    }
+                    .await;
+                    safe
+                }
+            };
+        }
+    }
+}
+
+impl<'a, L, G> SafeGuard<&'a Watch<L>, WatchGuard<'a, G>> {
+    /// Releases the lock, blocks until the data is changed by another guard, then returns the
+    /// original [`SafeLock`] to lock it again.
+    ///
+    /// Changes made since this guard was acquired also count, so none is missed. The data must be
+    /// inspected again once locked.
+    pub fn wait_for_change(self) -> SafeLock<&'a Watch<L>> {
+        let seen = self.guard.seen;
+        let lock = self.unlock();
+        lock.0.wait_for_change(seen);
+        lock
+    }
+
+    /// Releases the lock, waits until the data is changed by another guard, then resolves to the
+    /// original [`SafeLock`] to lock it again.
+    ///
+    /// This is the asynchronous version of [`wait_for_change`](Self::wait_for_change).
+    pub async fn wait_for_change_async(self) -> SafeLock<&'a Watch<L>> {
+        let seen = self.guard.seen;
+        let lock = self.unlock();
+        lock.0.wait_for_change_async(seen).await;
+        lock
+    }
+}
+
+impl<'a, L, G> SafeReadGuard<&'a Watch<L>, WatchGuard<'a, G>> {
+    /// Releases the lock, blocks until the data is changed by another guard, then returns the
+    /// original [`SafeLock`] to lock it again.
+    ///
+    /// Changes made since this guard was acquired also count, so none is missed.
+    pub fn wait_for_change(self) -> SafeLock<&'a Watch<L>> {
+        let seen = self.guard.seen;
+        let lock = self.unlock();
+        lock.0.wait_for_change(seen);
+        lock
+    }
+
+    /// Releases the lock, waits until the data is changed by another guard, then resolves to the
+    /// original [`SafeLock`] to lock it again.
+    ///
+    /// This is the asynchronous version of [`wait_for_change`](Self::wait_for_change).
+    pub async fn wait_for_change_async(self) -> SafeLock<&'a Watch<L>> {
+        let seen = self.guard.seen;
+        let lock = self.unlock();
+        lock.0.wait_for_change_async(seen).await;
+        lock
+    }
+}
+
+impl Future for WaitForChange<'_> {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.notifier.state();
+        if state.version != self.seen {
+            return Poll::Ready(state.version);
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl Future for WaitForRelease<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.notifier.state();
+        if state.releases != self.seen {
+            return Poll::Ready(());
+        }
+        if !state
+            .contended
+            .iter()
+            .any(|waker| waker.will_wake(cx.waker()))
+        {
+            state.contended.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl<G> Drop for WatchGuard<'_, G> {
+    fn drop(&mut self) {
+        // The lock is released before notifying, so the waiters do not block on it again right away.
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.notifier.release(self.changed);
+    }
+}
+
+impl<G: Deref> Deref for WatchGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for WatchGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.changed = true;
+        &mut self.guard
+    }
+}
+
+impl<L> Layer for Watch<L> {
+    type Inner = L;
+    type Guard<'a, G>
+        = WatchGuard<'a, G>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(&self, res: Result<G, E>, _exclusive: bool) -> Result<WatchGuard<'_, G>, E> {
+        res.map(|guard| WatchGuard {
+            guard: ManuallyDrop::new(guard),
+            notifier: &self.notifier,
+            seen: self.version(),
+            changed: false,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L] Watch<L> => LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = WatchGuard<'a, _>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::parking_lot::{Mutex, RwLock};
+    use ::std::thread;
+    use ::tokio::task;
+
+    use super::*;
+
+    #[test]
+    fn only_upgraded_guards_bump_the_version() {
+        let watch = Watch::new(RwLock::new(0));
+        drop(SafeLock::new(&watch).lock_blocking());
+        drop(SafeLock::new(&watch).read_blocking());
+        assert_eq!(watch.version(), 0);
+
+        let guard = SafeLock::new(&watch).lock_blocking();
+        assert_eq!(*guard, 0);
+        drop(guard.upgrade());
+        assert_eq!(watch.version(), 0);
+
+        increment(&watch);
+        assert_eq!(watch.version(), 1);
+    }
+
+    fn increment(watch: &Watch<RwLock<i32>>) {
+        let guard = SafeLock::new(watch).lock_blocking();
+        let value = *guard;
+        *guard.upgrade() = value + 1;
+    }
+
+    #[test]
+    fn changes_made_while_holding_the_guard_are_not_missed() {
+        let watch = Watch::new(RwLock::new(0));
+        let guard = SafeLock::new(&watch).read_blocking();
+        let version = watch.version();
+        thread::scope(|s| {
+            s.spawn(|| increment(&watch));
+            thread::sleep(::core::time::Duration::from_millis(10));
+            let safe = guard.wait_for_change();
+            assert!(safe.inner().version() > version);
+        });
+    }
+
+    #[test]
+    fn lock_when_wakes_up_on_upgrades() {
+        let watch = Watch::new(RwLock::new(0));
+        thread::scope(|s| {
+            let waiter = s.spawn(|| *SafeLock::new(&watch).lock_when(|value| *value >= 3));
+            for _ in 0..3 {
+                increment(&watch);
+            }
+            assert_eq!(waiter.join().unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn lock_when_checks_before_waiting() {
+        let watch = Watch::new(Mutex::new(7));
+        let guard = SafeLock::new(&watch).lock_when(|value| *value == 7);
+        assert_eq!(*guard, 7);
+    }
+
+    #[tokio::test]
+    async fn async_waiters_wait_for_the_held_lock_without_blocking() {
+        let watch = Watch::new(Mutex::new(0));
+        let guard = SafeLock::new(&watch).lock_blocking();
+        let waiter = async {
+            let guard = SafeLock::new(&watch)
+                .lock_when_async(|value| *value == 0)
+                .await;
+            assert_eq!(*guard, 0);
+        };
+        let holder = async {
+            // A waiter blocking on the lock would never let this task release it.
+            task::yield_now().await;
+            assert_eq!(*guard, 0);
+            drop(guard);
+        };
+        ::tokio::join!(waiter, holder);
+        assert_eq!(watch.version(), 0);
+    }
+
+    #[tokio::test]
+    async fn async_waiters_are_woken_up_by_changes() {
+        let watch = Watch::new(Mutex::new(String::new()));
+        let waiter = async {
+            SafeLock::new(&watch)
+                .lock_when_async(|text| text.len() == 3)
+                .await
+                .clone()
+        };
+        let writer = async {
+            for letter in ['a', 'b', 'c'] {
+                task::yield_now().await;
+                let guard = SafeLock::new(&watch).lock_blocking();
+                if !guard.ends_with(letter) {
+                    guard.upgrade().push(letter);
+                }
+            }
+        };
+        let (text, ()) = ::tokio::join!(waiter, writer);
+        assert_eq!((text.as_str(), watch.version()), ("abc", 3));
+    }
+}
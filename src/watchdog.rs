@@ -0,0 +1,219 @@
+use ::core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+use ::std::{backtrace::Backtrace, time::Instant};
+
+use crate::{
+    SafeLock,
+    layer::{Layer, forward_lock_traits},
+};
+
+/// A [`SafeLock`] over a [`Watchdog`] lock, reporting guards held for too long.
+pub type WatchdogSafeLock<L, F = fn(&HoldReport)> = SafeLock<Watchdog<L, F>>;
+
+/// A lock decorator reporting the guards of the wrapped lock `L` held longer than a threshold.
+///
+/// When such a guard is released, the callback is invoked with a [`HoldReport`], which includes a
+/// backtrace of the release site. Only the guards exceeding the threshold pay for capturing it.
+#[derive(Debug)]
+pub struct Watchdog<L, F = fn(&HoldReport)> {
+    lock: L,
+    threshold: Duration,
+    callback: F,
+}
+
+/// A report of a guard held longer than the threshold of its [`Watchdog`].
+#[derive(Debug)]
+pub struct HoldReport {
+    /// The threshold of the lock.
+    pub threshold: Duration,
+    /// How long the guard was held in total.
+    pub held: Duration,
+    /// How long the guard was held after the data was first accessed mutably, which is after the
+    /// guard was upgraded. `None` if it never was.
+    pub upgraded: Option<Duration>,
+    /// The backtrace of the release of the guard.
+    pub backtrace: Backtrace,
+}
+
+/// A guard returned by a [`Watchdog`] lock, checking its hold time when dropped.
+#[derive(Debug)]
+pub struct WatchdogGuard<'a, G, F: Fn(&HoldReport)> {
+    guard: ManuallyDrop<G>,
+    threshold: Duration,
+    callback: &'a F,
+    acquired_at: Instant,
+    upgraded_at: Option<Instant>,
+}
+
+impl fmt::Display for HoldReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lock held for {:?}, exceeding the threshold of {:?}",
+            self.held, self.threshold
+        )?;
+        if let Some(upgraded) = self.upgraded {
+            write!(f, " ({upgraded:?} after the upgrade)")?;
+        }
+        write!(f, ", released at:\n{}", self.backtrace)
+    }
+}
+
+/// Discards the report, as done by the default callback of [`Watchdog`].
+///
+/// The crate never prints on its own: pass a callback to [`Watchdog::with_callback`] to forward the
+/// reports to a logger, or to print them with their [`Display`](fmt::Display) implementation.
+pub fn ignore_report(_report: &HoldReport) {}
+
+impl<L> Watchdog<L> {
+    /// Wraps `lock` with a callback discarding the reports, which
+    /// [`with_callback`](Self::with_callback) replaces by one acting on them.
+    pub const fn new(lock: L, threshold: Duration) -> Self {
+        Self::with_callback(lock, threshold, ignore_report)
+    }
+}
+
+impl<L, F: Fn(&HoldReport)> Watchdog<L, F> {
+    /// Wraps `lock` to invoke `callback` whenever a guard is held longer than `threshold`.
+    pub const fn with_callback(lock: L, threshold: Duration, callback: F) -> Self {
+        Self {
+            lock,
+            threshold,
+            callback,
+        }
+    }
+
+    /// Returns the threshold of the lock.
+    pub const fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Returns a reference to the wrapped lock.
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<G, F: Fn(&HoldReport)> Drop for WatchdogGuard<'_, G, F> {
+    fn drop(&mut self) {
+        let released_at = Instant::now();
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        // The backtrace and the callback can be slow, so they run after the lock is released.
+        let held = released_at - self.acquired_at;
+        if held > self.threshold {
+            (self.callback)(&HoldReport {
+                threshold: self.threshold,
+                held,
+                upgraded: self
+                    .upgraded_at
+                    .map(|upgraded_at| released_at - upgraded_at),
+                backtrace: Backtrace::force_capture(),
+            });
+        }
+    }
+}
+
+impl<G: Deref, F: Fn(&HoldReport)> Deref for WatchdogGuard<'_, G, F> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut, F: Fn(&HoldReport)> DerefMut for WatchdogGuard<'_, G, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.upgraded_at.get_or_insert_with(Instant::now);
+        &mut self.guard
+    }
+}
+
+impl<L, F: Fn(&HoldReport)> Layer for Watchdog<L, F> {
+    type Inner = L;
+    type Guard<'a, G>
+        = WatchdogGuard<'a, G, F>
+    where
+        Self: 'a;
+    type Error<E> = E;
+
+    fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    fn wrap<G, E>(
+        &self,
+        res: Result<G, E>,
+        _exclusive: bool,
+    ) -> Result<WatchdogGuard<'_, G, F>, E> {
+        res.map(|guard| WatchdogGuard {
+            guard: ManuallyDrop::new(guard),
+            threshold: self.threshold,
+            callback: &self.callback,
+            acquired_at: Instant::now(),
+            upgraded_at: None,
+        })
+    }
+}
+
+forward_lock_traits! {
+    impl[L, F: Fn(&HoldReport)] Watchdog<L, F> =>
+        LockBlocking, LockImmediate, LockTimed, LockShared;
+    type Guard = WatchdogGuard<'a, _, F>;
+}
+
+#[cfg(all(test, feature = "parking_lot"))]
+mod tests {
+    use ::core::cell::Cell;
+    use ::parking_lot::Mutex;
+    use ::std::thread;
+
+    use super::*;
+
+    #[test]
+    fn guards_held_past_the_threshold_are_reported() {
+        let reports = Cell::new(0);
+        let lock = Watchdog::with_callback(Mutex::new(0), Duration::from_millis(5), |report| {
+            assert!(report.held > report.threshold);
+            assert!(report.upgraded.is_some());
+            reports.set(reports.get() + 1);
+        });
+
+        drop(SafeLock::new(&lock).lock_blocking());
+        assert_eq!(reports.get(), 0);
+
+        let guard = SafeLock::new(&lock).lock_blocking();
+        let value = *guard;
+        let mut guard = guard.upgrade();
+        *guard = value + 1;
+        thread::sleep(Duration::from_millis(10));
+        drop(guard);
+        assert_eq!(reports.get(), 1);
+    }
+
+    #[test]
+    fn callbacks_run_once_the_lock_is_released() {
+        let mutex = Mutex::new(0);
+        let reports = Cell::new(0);
+        let lock = Watchdog::with_callback(&mutex, Duration::ZERO, |_| {
+            assert!(mutex.try_lock().is_some());
+            reports.set(reports.get() + 1);
+        });
+        let guard = SafeLock::new(&lock).lock_blocking();
+        thread::sleep(Duration::from_millis(1));
+        drop(guard);
+        assert_eq!(reports.get(), 1);
+    }
+
+    #[test]
+    fn the_default_callback_discards_the_reports() {
+        let lock = Watchdog::new(Mutex::new(0), Duration::ZERO);
+        let guard = SafeLock::new(&lock).lock_blocking();
+        thread::sleep(Duration::from_millis(1));
+        assert_eq!(*guard, 0);
+    }
+}